@@ -0,0 +1,387 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `check-genesis` subcommand, reporting inconsistencies in the genesis of a chain spec
+//! before a network is launched.
+//!
+//! Storage based checks run against the genesis storage with the native runtime and work for
+//! raw specs as well. Duplicate entries are merged when the storage is built, so they can only
+//! be found in specs that still carry the runtime genesis config.
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fmt,
+	panic::{self, AssertUnwindSafe},
+	path::PathBuf,
+};
+
+use crate::{chain_spec, genesis::parse_amount};
+use codec::{Decode, Encode};
+use frame_support::metadata::{DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed};
+use node_polkadex_runtime::{constants::currency::PDEX, Balances, GenesisConfig, Runtime, Session};
+use polkadex_primitives::{AccountId, Balance};
+use sc_cli::{CliConfiguration, SharedParams, SubstrateCli};
+use sp_core::{crypto::Ss58Codec, hashing::twox_128, hexdisplay::HexDisplay, storage::Storage};
+use sp_runtime::BuildStorage;
+use structopt::StructOpt;
+
+/// Storage prefixes of the runtime pallets, read from the runtime metadata so that pallets
+/// added later are named as well. Used to name differing storage keys.
+pub fn storage_prefixes() -> Vec<String> {
+	let encoded = Runtime::metadata().encode();
+	// Decoding turns every `DecodeDifferent` into its `Decoded` variant.
+	let modules = match RuntimeMetadataPrefixed::decode(&mut &encoded[..]) {
+		Ok(RuntimeMetadataPrefixed(_, RuntimeMetadata::V13(metadata))) => match metadata.modules {
+			DecodeDifferent::Decoded(modules) => modules,
+			DecodeDifferent::Encode(_) => return Vec::new(),
+		},
+		_ => return Vec::new(),
+	};
+	modules
+		.into_iter()
+		.filter_map(|module| match module.storage? {
+			DecodeDifferent::Decoded(storage) => match storage.prefix {
+				DecodeDifferent::Decoded(prefix) => Some(prefix),
+				DecodeDifferent::Encode(_) => None,
+			},
+			DecodeDifferent::Encode(_) => None,
+		})
+		.collect()
+}
+
+/// The `check-genesis` command.
+#[derive(Debug, StructOpt)]
+pub struct CheckGenesisCmd {
+	/// Intended total supply, in the smallest unit or suffixed with ` PDEX`.
+	#[structopt(long, value_name = "AMOUNT", parse(try_from_str = parse_amount))]
+	pub expected_supply: Option<Balance>,
+
+	/// Raw chain spec to compare the genesis storage with, such as
+	/// `extras/customSpecRaw.json`.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub reference: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl CliConfiguration for CheckGenesisCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// Severity of a finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+	/// The genesis should not be launched.
+	Error,
+	/// The genesis may be intended, but should be double checked.
+	Warning,
+}
+
+/// A single inconsistency found in the genesis.
+#[derive(Debug, Clone)]
+pub struct Finding {
+	/// Severity of the finding.
+	pub severity: Severity,
+	/// Description of the finding.
+	pub message: String,
+}
+
+impl fmt::Display for Finding {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.severity {
+			Severity::Error => write!(f, "error: {}", self.message),
+			Severity::Warning => write!(f, "warning: {}", self.message),
+		}
+	}
+}
+
+/// Findings of a genesis check.
+#[derive(Debug, Default)]
+pub struct Report(pub Vec<Finding>);
+
+impl Report {
+	/// Adds a finding with severity `Error`.
+	pub fn error(&mut self, message: String) {
+		self.0.push(Finding { severity: Severity::Error, message });
+	}
+
+	/// Adds a finding with severity `Warning`.
+	pub fn warning(&mut self, message: String) {
+		self.0.push(Finding { severity: Severity::Warning, message });
+	}
+
+	/// Number of findings with severity `Error`.
+	pub fn errors(&self) -> usize {
+		self.0.iter().filter(|finding| finding.severity == Severity::Error).count()
+	}
+}
+
+fn pdex(amount: Balance) -> String {
+	format!("{}.{:011} PDEX", amount / PDEX, amount % PDEX)
+}
+
+/// Flags accounts listed more than once in the runtime genesis config.
+pub fn check_duplicates(config: &GenesisConfig, report: &mut Report) {
+	let mut seen = BTreeSet::new();
+	for (account, _) in config.balances.balances.iter() {
+		if !seen.insert(account) {
+			report.error(format!("{} is endowed more than once", account.to_ss58check()));
+		}
+	}
+
+	let mut bonded = BTreeSet::new();
+	for (stash, controller, _, _) in config.staking.stakers.iter() {
+		if !bonded.insert(stash) {
+			report.error(format!("{} is bonded more than once", stash.to_ss58check()));
+		}
+		if controller != stash && !bonded.insert(controller) {
+			report.error(format!(
+				"{} is used as a controller by another staker",
+				controller.to_ss58check()
+			));
+		}
+	}
+
+	let vested: BTreeSet<_> =
+		config.orml_vesting.vesting.iter().map(|(account, ..)| account).collect();
+	for account in bonded.intersection(&vested) {
+		report.warning(format!("{} is both vested and bonded", account.to_ss58check()));
+	}
+}
+
+/// Checks the genesis storage with the native runtime.
+pub fn check_storage(storage: Storage, expected_supply: Option<Balance>, report: &mut Report) {
+	sp_io::TestExternalities::new(storage).execute_with(|| {
+		let mut free = BTreeMap::<AccountId, Balance>::new();
+		let mut total: Balance = 0;
+		for (account, info) in frame_system::Account::<Runtime>::iter() {
+			total = total.saturating_add(info.data.free).saturating_add(info.data.reserved);
+			free.insert(account, info.data.free);
+		}
+		let issuance = Balances::total_issuance();
+		println!("Total issuance: {}", pdex(issuance));
+		if total != issuance {
+			report.error(format!(
+				"Total issuance {} does not match the sum of all balances {}",
+				pdex(issuance),
+				pdex(total)
+			));
+		}
+		if let Some(expected) = expected_supply {
+			if issuance != expected {
+				report.error(format!(
+					"Total issuance {} differs from the intended supply {}",
+					pdex(issuance),
+					pdex(expected)
+				));
+			}
+		}
+
+		for (account, schedules) in orml_vesting::VestingSchedules::<Runtime>::iter() {
+			let vested = schedules.iter().fold(0 as Balance, |vested, schedule| {
+				let total = schedule.per_period.saturating_mul(schedule.period_count.into());
+				vested.saturating_add(total)
+			});
+			let balance = free.get(&account).copied().unwrap_or_default();
+			if vested > balance {
+				report.error(format!(
+					"{} vests {} but only holds {}",
+					account.to_ss58check(),
+					pdex(vested),
+					pdex(balance)
+				));
+			}
+		}
+
+		let keyed: BTreeSet<_> =
+			Session::queued_keys().into_iter().map(|(validator, _)| validator).collect();
+		for validator in pallet_staking::Validators::<Runtime>::iter_keys() {
+			if !keyed.contains(&validator) {
+				report.error(format!("Validator {} has no session keys", validator.to_ss58check()));
+			}
+		}
+	});
+}
+
+fn prefix_name(prefixes: &[String], key: &[u8]) -> String {
+	prefixes
+		.iter()
+		.find(|name| key.starts_with(&twox_128(name.as_bytes())))
+		.cloned()
+		.unwrap_or_else(|| format!("0x{}", HexDisplay::from(&&key[..key.len().min(16)])))
+}
+
+/// Compares the genesis storage with the one of a reference spec.
+pub fn check_reference(storage: &Storage, reference: &Storage, report: &mut Report) {
+	let prefixes = storage_prefixes();
+	let prefix_name = |key: &[u8]| prefix_name(&prefixes, key);
+	let code = sp_core::storage::well_known_keys::CODE;
+	let mut missing = BTreeMap::<String, usize>::new();
+	let mut extra = BTreeMap::<String, usize>::new();
+	let mut changed = BTreeMap::<String, usize>::new();
+
+	for (key, value) in reference.top.iter() {
+		match storage.top.get(key) {
+			None => *missing.entry(prefix_name(key)).or_default() += 1,
+			Some(_) if key.as_slice() == code => {},
+			Some(ours) if ours != value => *changed.entry(prefix_name(key)).or_default() += 1,
+			Some(_) => {},
+		}
+	}
+	for key in storage.top.keys().filter(|key| !reference.top.contains_key(*key)) {
+		*extra.entry(prefix_name(key)).or_default() += 1;
+	}
+	if storage.top.get(code) != reference.top.get(code) {
+		report.warning("Runtime code differs from the reference".into());
+	}
+
+	for (prefix, count) in missing {
+		report.warning(format!("{}: {} keys of the reference are missing", prefix, count));
+	}
+	for (prefix, count) in extra {
+		report.warning(format!("{}: {} keys are not in the reference", prefix, count));
+	}
+	for (prefix, count) in changed {
+		report.warning(format!("{}: {} values differ from the reference", prefix, count));
+	}
+}
+
+/// Builds the genesis storage of `spec`, reporting an error instead of panicking when a
+/// genesis config is rejected by a pallet's genesis build.
+pub fn build_storage(spec: &dyn BuildStorage, report: &mut Report) -> Option<Storage> {
+	match panic::catch_unwind(AssertUnwindSafe(|| spec.build_storage())) {
+		Ok(Ok(storage)) => Some(storage),
+		Ok(Err(e)) => {
+			report.error(format!("Unable to build the genesis storage: {}", e));
+			None
+		},
+		Err(payload) => {
+			let reason = payload
+				.downcast_ref::<&str>()
+				.map(|reason| reason.to_string())
+				.or_else(|| payload.downcast_ref::<String>().cloned())
+				.unwrap_or_else(|| "unknown reason".into());
+			report.error(format!("Building the genesis storage panicked: {}", reason));
+			None
+		},
+	}
+}
+
+impl CheckGenesisCmd {
+	/// Run the check-genesis command.
+	pub fn run<C: SubstrateCli>(&self, cli: &C) -> sc_cli::Result<()> {
+		let spec = cli.load_spec(&self.shared_params.chain_id(self.shared_params.is_dev()))?;
+		let mut report = Report::default();
+		let mut printed = 0;
+		let mut print_new = |report: &Report| {
+			for finding in report.0[printed..].iter() {
+				println!("{}", finding);
+			}
+			printed = report.0.len();
+		};
+
+		let json: serde_json::Value = serde_json::from_str(&spec.as_json(false)?)
+			.map_err(|e| format!("Invalid chain spec: {}", e))?;
+		match json.pointer("/genesis/runtime") {
+			Some(runtime) => {
+				let config: GenesisConfig = serde_json::from_value(runtime.clone())
+					.map_err(|e| format!("Invalid runtime genesis config: {}", e))?;
+				check_duplicates(&config, &mut report);
+			},
+			None => println!("Raw chain spec: skipping the duplicate account checks"),
+		}
+		// Pallets may panic on the duplicates while building the storage.
+		print_new(&report);
+
+		if let Some(storage) = build_storage(spec.as_storage_builder(), &mut report) {
+			if let Some(path) = &self.reference {
+				let reference = chain_spec::ChainSpec::from_json_file(path.clone())?;
+				if let Some(reference) = build_storage(&reference, &mut report) {
+					check_reference(&storage, &reference, &mut report);
+				}
+			}
+			check_storage(storage, self.expected_supply, &mut report);
+		}
+		print_new(&report);
+
+		match report.errors() {
+			0 => {
+				println!("Genesis of {} looks consistent", spec.name());
+				Ok(())
+			},
+			errors =>
+				Err(format!("Found {} errors in the genesis of {}", errors, spec.name()).into()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn storage_prefixes_follow_the_runtime() {
+		let prefixes = storage_prefixes();
+		for pallet in &[
+			"System",
+			"Balances",
+			"EthereumLightClient",
+			"BasicInboundModule",
+			"TradingFees",
+			"FeeTiers",
+			"Ido",
+			"Oracle",
+			"Twap",
+		] {
+			assert!(prefixes.iter().any(|prefix| prefix == pallet), "{} is missing", pallet);
+		}
+	}
+
+	#[test]
+	fn differing_keys_are_named_after_their_pallet() {
+		let key = [&twox_128(b"Twap")[..], &twox_128(b"Observations")[..]].concat();
+		let storage = |value: u8| Storage {
+			top: vec![(key.clone(), vec![value])].into_iter().collect(),
+			children_default: Default::default(),
+		};
+		let mut report = Report::default();
+		check_reference(&storage(1), &storage(2), &mut report);
+
+		let messages: Vec<_> = report.0.iter().map(|finding| finding.message.as_str()).collect();
+		assert!(messages.contains(&"Twap: 1 values differ from the reference"));
+	}
+
+	#[test]
+	fn panicking_genesis_build_is_reported() {
+		struct Panics;
+		impl BuildStorage for Panics {
+			fn assimilate_storage(&self, _storage: &mut Storage) -> Result<(), String> {
+				panic!("Stash already bonded")
+			}
+		}
+
+		let mut report = Report::default();
+		assert!(build_storage(&Panics, &mut report).is_none());
+		assert_eq!(report.errors(), 1);
+		assert!(report.0[0].message.ends_with("Stash already bonded"));
+	}
+}
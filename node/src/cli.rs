@@ -16,7 +16,11 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+pub use crate::service::Sealing;
+use crate::eth_relayer::RelayerConfig;
 use sc_cli::{KeySubcommand, RunCmd, SignCmd, VanityCmd, VerifyCmd};
+use sp_core::H160;
+use std::{path::PathBuf, str::FromStr, time::Duration};
 use structopt::StructOpt;
 
 /// An overarching CLI command definition.
@@ -28,6 +32,70 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub run: RunCmd,
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub eth_relayer: EthRelayerParams,
+
+	/// Produce blocks with manual seal instead of BABE and GRANDPA, for development.
+	///
+	/// `instant` seals a block for every transaction, `manual` only on `engine_createBlock`
+	/// calls and `interval=<ms>` every given number of milliseconds.
+	#[structopt(long, value_name = "MODE")]
+	pub sealing: Option<Sealing>,
+
+	/// Index finalized blocks into the SQLite database at `PATH`.
+	///
+	/// Transfers, mints, vesting claims and staking payouts are indexed. Orders are not, as the
+	/// runtime has no order book yet. Indexing reads the state of every block, so the node has
+	/// to run with `--pruning archive`.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub indexer_db: Option<PathBuf>,
+}
+
+/// Parameters of the Ethereum relayer task.
+#[derive(Debug, StructOpt)]
+pub struct EthRelayerParams {
+	/// Relay Ethereum headers and messages from the HTTP JSON-RPC endpoint at `URL`.
+	///
+	/// Extrinsics are signed with the `ethr` key of the node's keystore.
+	#[structopt(long = "eth-relayer", value_name = "URL")]
+	pub endpoint: Option<String>,
+
+	/// Address of the basic outbound channel contract on Ethereum.
+	#[structopt(long = "eth-relayer-source-channel", value_name = "ADDRESS", parse(try_from_str = parse_h160))]
+	pub source_channel: Option<H160>,
+
+	/// Seconds between two polls of the Ethereum node.
+	#[structopt(long = "eth-relayer-poll-interval", value_name = "SECS", default_value = "15")]
+	pub poll_interval: u64,
+
+	/// First Ethereum block to relay. Defaults to resuming from the state of the bridge on
+	/// chain.
+	#[structopt(long = "eth-relayer-start-block", value_name = "NUMBER")]
+	pub start_block: Option<u64>,
+}
+
+impl EthRelayerParams {
+	/// Relayer configuration, if the relayer is enabled.
+	pub fn relayer_config(&self) -> Result<Option<RelayerConfig>, String> {
+		let endpoint = match &self.endpoint {
+			Some(endpoint) => endpoint.clone(),
+			None => return Ok(None),
+		};
+		let source_channel = self
+			.source_channel
+			.ok_or("`--eth-relayer-source-channel` is required by `--eth-relayer`")?;
+		Ok(Some(RelayerConfig {
+			endpoint,
+			source_channel,
+			poll_interval: Duration::from_secs(self.poll_interval),
+			start_block: self.start_block,
+		}))
+	}
+}
+
+fn parse_h160(s: &str) -> Result<H160, String> {
+	H160::from_str(s.trim_start_matches("0x")).map_err(|e| format!("Invalid address: {}", e))
 }
 
 /// Possible subcommands of the main binary.
@@ -36,12 +104,13 @@ pub enum Subcommand {
 	/// Key management cli utilities
 	Key(KeySubcommand),
 
-	// /// The custom inspect subcommmand for decoding blocks and extrinsics.
-	// #[structopt(
-	// name = "inspect",
-	// about = "Decode given block or extrinsic using current native runtime."
-	// )]
-	// Inspect(node_inspect::cli::InspectKeyCmd),
+	/// The custom inspect subcommmand for decoding blocks and extrinsics.
+	#[structopt(
+		name = "inspect",
+		about = "Decode given block or extrinsic using current native runtime."
+	)]
+	Inspect(crate::inspect::InspectCmd),
+
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
@@ -63,6 +132,21 @@ pub enum Subcommand {
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
 
+	/// Generate a chain specification from a TOML or JSON genesis description.
+	GenerateSpec(crate::genesis::GenerateSpecCmd),
+
+	/// Check the consistency of a genesis before launching a network.
+	CheckGenesis(crate::check_genesis::CheckGenesisCmd),
+
+	/// Generate, insert and verify the session keys of a validator.
+	SessionKeys(crate::session_keys::SessionKeysCmd),
+
+	/// Report the session, heartbeat, BABE, GRANDPA and offence status of a validator.
+	ValidatorStatus(crate::validator_status::ValidatorStatusCmd),
+
+	/// Export the balances, vesting schedules and staking ledgers of every account to CSV files.
+	ExportBalances(crate::export_balances::ExportBalancesCmd),
+
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
 
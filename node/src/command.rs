@@ -20,11 +20,12 @@ use crate::{
 	cli::{Cli, Subcommand},
 	service,
 	service::new_partial,
+	session_keys::SessionKeysCmd,
 };
 use node_executor::ExecutorDispatch;
 use node_polkadex_runtime::Block;
 use sc_cli::{ChainSpec, Result, Role, RuntimeVersion, SubstrateCli};
-use sc_service::PartialComponents;
+use sc_service::{Error as ServiceError, PartialComponents};
 // use node_polkadex_runtime::RuntimeApi;
 
 impl SubstrateCli for Cli {
@@ -59,9 +60,9 @@ impl SubstrateCli for Cli {
 					"Please specify which chain you want to run, e.g. --dev or --chain=local"
 						.into(),
 				),
-			"dev" => Box::new(chain_spec::development_config()),
-			"udon" => Box::new(chain_spec::udon_testnet_config()),
-			"soba" => Box::new(chain_spec::soba_testnet_config()),
+			"dev" => Box::new(chain_spec::development_config()?),
+			"udon" => Box::new(chain_spec::udon_testnet_config()?),
+			"soba" => Box::new(chain_spec::soba_testnet_config()?),
 			path =>
 				Box::new(chain_spec::ChainSpec::from_json_file(std::path::PathBuf::from(path))?),
 		};
@@ -80,19 +81,28 @@ pub fn run() -> Result<()> {
 	match &cli.subcommand {
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
+			let eth_relayer = cli.eth_relayer.relayer_config()?;
+			let sealing = cli.sealing;
+			let indexer_db = cli.indexer_db.clone();
 			runner.run_node_until_exit(|config| async move {
-				match config.role {
-					Role::Light => service::new_light(config),
-					_ => service::new_full(config),
+				match (&config.role, sealing) {
+					(Role::Light, Some(_)) =>
+						Err(ServiceError::Other("`--sealing` requires a full node".into())),
+					(Role::Light, None) => service::new_light(config),
+					(_, Some(sealing)) =>
+						service::new_manual_seal(config, sealing, eth_relayer, indexer_db),
+					(_, None) => service::new_full(config, eth_relayer, indexer_db),
 				}
 				.map_err(sc_cli::Error::Service)
 			})
 		},
-		// Some(Subcommand::Inspect(cmd)) => {
-		//     let runner = cli.create_runner(cmd)?;
-		//
-		//     runner.sync_run(|config| cmd.run::<Block, RuntimeApi, ExecutorDispatch>(config))
-		// },
+		Some(Subcommand::Inspect(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = new_partial(&config)?;
+				cmd.run(client)
+			})
+		},
 		Some(Subcommand::Benchmark(cmd)) =>
 			if cfg!(feature = "runtime-benchmarks") {
 				let runner = cli.create_runner(cmd)?;
@@ -111,6 +121,19 @@ pub fn run() -> Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
 		},
+		Some(Subcommand::GenerateSpec(cmd)) => cmd.run(),
+		Some(Subcommand::CheckGenesis(cmd)) => cmd.run(&cli),
+		Some(Subcommand::SessionKeys(SessionKeysCmd::Generate(cmd))) => cmd.run(),
+		Some(Subcommand::SessionKeys(SessionKeysCmd::Insert(cmd))) => cmd.run(&cli),
+		Some(Subcommand::SessionKeys(SessionKeysCmd::Verify(cmd))) => cmd.run(&cli),
+		Some(Subcommand::ValidatorStatus(cmd)) => cmd.run(),
+		Some(Subcommand::ExportBalances(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = new_partial(&config)?;
+				cmd.run(client)
+			})
+		},
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
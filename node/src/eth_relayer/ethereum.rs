@@ -0,0 +1,182 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Minimal Ethereum JSON-RPC client used by the relayer.
+
+use jsonrpc_core_client::{transports::http, RawClient};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use snowbridge_ethereum_light_client::EthereumHeader;
+use sp_core::{Bytes, H160, H256, U256};
+
+use super::{
+	receipt_proof::{Log, Receipt},
+	Error,
+};
+
+/// Block as returned by `eth_getBlockByNumber`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlock {
+	pub hash: H256,
+	pub parent_hash: H256,
+	pub number: U256,
+	pub timestamp: U256,
+	pub miner: H160,
+	pub transactions_root: H256,
+	pub sha3_uncles: H256,
+	pub extra_data: Bytes,
+	pub state_root: H256,
+	pub receipts_root: H256,
+	pub logs_bloom: Bytes,
+	pub gas_used: U256,
+	pub gas_limit: U256,
+	pub difficulty: U256,
+	pub mix_hash: H256,
+	pub nonce: Bytes,
+	pub base_fee_per_gas: Option<U256>,
+	pub transactions: Vec<H256>,
+}
+
+impl RpcBlock {
+	/// Converts the block into the header format expected by the light client.
+	pub fn to_header(&self) -> Result<EthereumHeader, Error> {
+		let number = self.number.low_u64();
+		if self.logs_bloom.len() != 256 || self.nonce.len() != 8 {
+			return Err(Error::InvalidHeader(number))
+		}
+		let mut logs_bloom = [0u8; 256];
+		logs_bloom.copy_from_slice(&self.logs_bloom[..]);
+		Ok(EthereumHeader {
+			parent_hash: self.parent_hash,
+			timestamp: self.timestamp.low_u64(),
+			number,
+			author: self.miner,
+			transactions_root: self.transactions_root,
+			ommers_hash: self.sha3_uncles,
+			extra_data: self.extra_data.to_vec(),
+			state_root: self.state_root,
+			receipts_root: self.receipts_root,
+			logs_bloom: (&logs_bloom).into(),
+			gas_used: self.gas_used,
+			gas_limit: self.gas_limit,
+			difficulty: self.difficulty,
+			// The seal fields are stored RLP encoded: a 32 byte and an 8 byte string.
+			seal: vec![
+				[&[0xa0][..], self.mix_hash.as_bytes()].concat(),
+				[&[0x88][..], &self.nonce[..]].concat(),
+			],
+			base_fee: self.base_fee_per_gas,
+		})
+	}
+}
+
+/// Log as returned by `eth_getLogs` and within receipts.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcLog {
+	pub address: H160,
+	pub topics: Vec<H256>,
+	pub data: Bytes,
+	pub block_number: U256,
+	pub transaction_index: U256,
+}
+
+impl From<&RpcLog> for Log {
+	fn from(log: &RpcLog) -> Self {
+		Log { address: log.address, topics: log.topics.clone(), data: log.data.to_vec() }
+	}
+}
+
+/// Receipt as returned by `eth_getTransactionReceipt`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcReceipt {
+	#[serde(rename = "type", default)]
+	pub tx_type: Option<U256>,
+	pub status: U256,
+	pub cumulative_gas_used: U256,
+	pub logs_bloom: Bytes,
+	pub logs: Vec<RpcLog>,
+}
+
+impl From<&RpcReceipt> for Receipt {
+	fn from(receipt: &RpcReceipt) -> Self {
+		Receipt {
+			tx_type: receipt.tx_type.map(|t| t.low_u32() as u8).unwrap_or_default(),
+			status: receipt.status.low_u64(),
+			cumulative_gas_used: receipt.cumulative_gas_used.low_u64(),
+			logs_bloom: receipt.logs_bloom.to_vec(),
+			logs: receipt.logs.iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// Thin wrapper over a JSON-RPC connection to an Ethereum node.
+pub struct EthereumClient {
+	client: RawClient,
+}
+
+impl EthereumClient {
+	/// Connects to the HTTP JSON-RPC endpoint at `url`.
+	pub async fn connect(url: &str) -> Result<Self, Error> {
+		let client = http::connect::<RawClient>(url).await.map_err(Error::Rpc)?;
+		Ok(EthereumClient { client })
+	}
+
+	async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, Error> {
+		let params = match params {
+			Value::Array(params) => jsonrpc_core::Params::Array(params),
+			_ => jsonrpc_core::Params::None,
+		};
+		let value = self.client.call_method(method, params).await.map_err(Error::Rpc)?;
+		serde_json::from_value(value).map_err(Error::Decode)
+	}
+
+	/// Number of the latest Ethereum block.
+	pub async fn block_number(&self) -> Result<u64, Error> {
+		self.call::<U256>("eth_blockNumber", json!([])).await.map(|n| n.low_u64())
+	}
+
+	/// Block with the given number, if known.
+	pub async fn block_by_number(&self, number: u64) -> Result<RpcBlock, Error> {
+		self.call::<Option<RpcBlock>>("eth_getBlockByNumber", json!([U256::from(number), false]))
+			.await?
+			.ok_or(Error::MissingBlock(number))
+	}
+
+	/// Logs emitted by `address` between blocks `from` and `to`, inclusive.
+	pub async fn logs(&self, address: H160, from: u64, to: u64) -> Result<Vec<RpcLog>, Error> {
+		self.call(
+			"eth_getLogs",
+			json!([{
+				"address": address,
+				"fromBlock": U256::from(from),
+				"toBlock": U256::from(to),
+			}]),
+		)
+		.await
+	}
+
+	/// Receipt of the transaction with hash `tx`.
+	pub async fn receipt(&self, tx: H256) -> Result<RpcReceipt, Error> {
+		self.call::<Option<RpcReceipt>>("eth_getTransactionReceipt", json!([tx]))
+			.await?
+			.ok_or(Error::MissingReceipt(tx))
+	}
+}
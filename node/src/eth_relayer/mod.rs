@@ -0,0 +1,575 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Ethereum relayer running as a task of the node.
+//!
+//! The relayer polls an Ethereum JSON-RPC endpoint, imports new headers into
+//! `EthereumLightClient` and, once a block is final according to
+//! `DescendantsUntilFinalized`, submits the messages emitted by the basic outbound
+//! channel contract to `BasicInboundChannel` together with their receipt proofs.
+
+use std::{
+	collections::{BTreeMap, VecDeque},
+	sync::Arc,
+	time::Duration,
+};
+
+use codec::Encode;
+use futures::future::BoxFuture;
+use node_polkadex_runtime::{
+	Address, Call, DescendantsUntilFinalized, Runtime, SignedPayload, UncheckedExtrinsic,
+};
+use parking_lot::Mutex;
+use polkadex_primitives::{AccountId, Block, Index};
+use polkadex_runtime_api::bridge::{BridgeApi, EthereumHeaderId};
+use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+use snowbridge_core::{Message, Proof};
+use snowbridge_ethereum_light_client::EthereumHeader;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::KeyTypeId, sr25519, H160, H256};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{
+	generic::{BlockId, Era},
+	traits::IdentifyAccount,
+	MultiSigner,
+};
+use std::convert::TryFrom;
+
+pub mod ethereum;
+pub mod receipt_proof;
+
+use ethereum::EthereumClient;
+
+/// Key type of the account signing the relayer's extrinsics.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"ethr");
+
+const LOG_TARGET: &str = "eth-relayer";
+
+/// Number of imported headers remembered to find the common ancestor of a reorg.
+const MAX_REORG_DEPTH: usize = 64;
+
+/// Number of Ethereum blocks covered by a single `eth_getLogs` call when resuming.
+const LOG_SCAN_WINDOW: u64 = 1_000;
+
+/// Errors raised while relaying.
+#[derive(Debug)]
+pub enum Error {
+	/// The Ethereum JSON-RPC endpoint returned an error.
+	Rpc(jsonrpc_core_client::RpcError),
+	/// A JSON-RPC response could not be decoded.
+	Decode(serde_json::Error),
+	/// The Ethereum node does not know the block.
+	MissingBlock(u64),
+	/// The Ethereum node does not know the receipt of a transaction.
+	MissingReceipt(sp_core::H256),
+	/// The receipts of a block do not match its `receiptsRoot`.
+	InvalidReceiptsRoot(u64),
+	/// The logs bloom or seal of a block does not have the expected length.
+	InvalidHeader(u64),
+	/// Ethereum reorganized below the oldest header the relayer remembers.
+	ReorgTooDeep(u64),
+	/// An extrinsic could not be built or submitted.
+	Submit(String),
+}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Error::Rpc(e) => write!(f, "Ethereum RPC error: {}", e),
+			Error::Decode(e) => write!(f, "Invalid Ethereum RPC response: {}", e),
+			Error::MissingBlock(n) => write!(f, "Ethereum block {} not found", n),
+			Error::MissingReceipt(tx) => write!(f, "Receipt of {:?} not found", tx),
+			Error::InvalidReceiptsRoot(n) => write!(f, "Receipts of block {} do not match its root", n),
+			Error::InvalidHeader(n) => write!(f, "Block {} has a malformed logs bloom or seal", n),
+			Error::ReorgTooDeep(n) => write!(f, "No common ancestor found for block {}", n),
+			Error::Submit(e) => write!(f, "Failed to submit extrinsic: {}", e),
+		}
+	}
+}
+
+/// Relayer settings, taken from the command line.
+#[derive(Debug, Clone)]
+pub struct RelayerConfig {
+	/// HTTP JSON-RPC endpoint of the Ethereum node.
+	pub endpoint: String,
+	/// Address of the basic outbound channel contract.
+	pub source_channel: H160,
+	/// Delay between two polls of the Ethereum node.
+	pub poll_interval: Duration,
+	/// First Ethereum block to relay, defaults to resuming from the state of the bridge.
+	pub start_block: Option<u64>,
+}
+
+/// A call the relayer needs to get included on chain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelayCall {
+	/// `EthereumLightClient::import_header`
+	ImportHeader(EthereumHeader),
+	/// `BasicInboundChannel::submit`
+	SubmitMessage(Message),
+}
+
+/// State of the bridge on chain the relayer resumes from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeState {
+	/// Best header imported by the light client.
+	pub best: EthereumHeaderId,
+	/// Lowest Ethereum block whose header the light client may still hold.
+	pub oldest_header: u64,
+	/// Nonce of the last message the inbound channel accepted from the source channel.
+	pub accepted_nonce: u64,
+}
+
+/// Submits relayed calls to the chain.
+pub trait Submitter: Send + Sync {
+	fn submit(&self, call: RelayCall) -> BoxFuture<'static, Result<(), Error>>;
+
+	/// State of the bridge for messages from `source_channel`, if it can be read.
+	fn bridge_state(&self, _source_channel: H160) -> Option<BridgeState> {
+		None
+	}
+}
+
+/// Nonce of a message emitted by the basic outbound channel contract, the second word of
+/// the ABI encoded `Message(address source, uint64 nonce, bytes payload)` event data.
+pub fn message_nonce(data: &[u8]) -> Option<u64> {
+	let word = data.get(32..64)?;
+	if word[..24].iter().any(|byte| *byte != 0) {
+		return None
+	}
+	let mut nonce = [0u8; 8];
+	nonce.copy_from_slice(&word[24..]);
+	Some(u64::from_be_bytes(nonce))
+}
+
+/// Relays headers and messages from `from` onward.
+pub struct Relayer<S> {
+	ethereum: EthereumClient,
+	submitter: S,
+	source_channel: H160,
+	confirmations: u64,
+	/// Next Ethereum block whose header must be imported.
+	next_header: u64,
+	/// Next Ethereum block whose messages must be relayed.
+	next_message: u64,
+	/// Number and hash of the latest imported headers, oldest first.
+	imported: VecDeque<(u64, H256)>,
+}
+
+impl<S: Submitter> Relayer<S> {
+	pub fn new(ethereum: EthereumClient, submitter: S, source_channel: H160, from: u64) -> Self {
+		Relayer {
+			ethereum,
+			submitter,
+			source_channel,
+			confirmations: DescendantsUntilFinalized::get() as u64,
+			next_header: from,
+			next_message: from,
+			imported: VecDeque::new(),
+		}
+	}
+
+	/// Creates a relayer continuing from the state of the bridge: headers after the light
+	/// client's best header and messages from the block of the last message the inbound
+	/// channel accepted. Messages it accepted in that block are skipped by their nonce.
+	pub async fn resume(
+		ethereum: EthereumClient,
+		submitter: S,
+		source_channel: H160,
+		state: BridgeState,
+	) -> Result<Self, Error> {
+		let next_message = last_accepted_message_block(&ethereum, source_channel, &state).await?;
+		let mut relayer = Relayer::new(ethereum, submitter, source_channel, state.best.number + 1);
+		relayer.next_message = next_message;
+		relayer.imported.push_back((state.best.number, state.best.hash));
+		Ok(relayer)
+	}
+
+	/// Imports all new headers and relays the messages of newly finalized blocks.
+	pub async fn poll(&mut self) -> Result<(), Error> {
+		let head = self.ethereum.block_number().await?;
+
+		while self.next_header <= head {
+			let block = self.ethereum.block_by_number(self.next_header).await?;
+			if matches!(self.imported.back(), Some((_, last)) if *last != block.parent_hash) {
+				self.rewind().await?;
+				continue
+			}
+			self.submitter.submit(RelayCall::ImportHeader(block.to_header()?)).await?;
+			self.imported.push_back((self.next_header, block.hash));
+			if self.imported.len() > MAX_REORG_DEPTH {
+				self.imported.pop_front();
+			}
+			self.next_header += 1;
+		}
+
+		let finalized = match head.checked_sub(self.confirmations) {
+			Some(finalized) if finalized >= self.next_message => finalized,
+			_ => return Ok(()),
+		};
+		let logs = self.ethereum.logs(self.source_channel, self.next_message, finalized).await?;
+		let accepted_nonce = self
+			.submitter
+			.bridge_state(self.source_channel)
+			.map(|state| state.accepted_nonce);
+
+		// Group the logs by block so that each block's receipts are fetched once.
+		let mut by_block = BTreeMap::<u64, Vec<_>>::new();
+		for log in logs.iter() {
+			// The inbound channel rejects messages it already accepted.
+			let accepted = match (accepted_nonce, message_nonce(&log.data)) {
+				(Some(accepted_nonce), Some(nonce)) => nonce <= accepted_nonce,
+				_ => false,
+			};
+			if accepted {
+				continue
+			}
+			by_block.entry(log.block_number.low_u64()).or_default().push(log);
+		}
+		for (number, logs) in by_block {
+			let block = self.ethereum.block_by_number(number).await?;
+			let mut receipts = Vec::with_capacity(block.transactions.len());
+			for tx in block.transactions.iter() {
+				let receipt = self.ethereum.receipt(*tx).await?;
+				receipts.push(receipt_proof::encode_receipt(&(&receipt).into()));
+			}
+			for log in logs {
+				let tx_index = log.transaction_index.low_u32();
+				let (root, nodes) = receipt_proof::build_proof(&receipts, tx_index as usize);
+				if root != block.receipts_root {
+					return Err(Error::InvalidReceiptsRoot(number))
+				}
+				let keys = nodes
+					.iter()
+					.map(|node| sp_core::hashing::keccak_256(node).to_vec())
+					.collect();
+				let message = Message {
+					data: receipt_proof::encode_log(&log.into()),
+					proof: Proof { block_hash: block.hash, tx_index, data: (keys, nodes) },
+				};
+				self.submitter.submit(RelayCall::SubmitMessage(message)).await?;
+			}
+		}
+		self.next_message = finalized + 1;
+
+		Ok(())
+	}
+
+	/// Walks back the imported headers to the latest one still on the canonical chain and
+	/// resumes importing right after it.
+	async fn rewind(&mut self) -> Result<(), Error> {
+		while let Some((number, hash)) = self.imported.back().copied() {
+			if self.ethereum.block_by_number(number).await?.hash == hash {
+				self.next_header = number + 1;
+				log::info!(
+					target: LOG_TARGET,
+					"Ethereum reorg, importing headers from block {}",
+					self.next_header,
+				);
+				return Ok(())
+			}
+			self.imported.pop_back();
+		}
+		Err(Error::ReorgTooDeep(self.next_header))
+	}
+}
+
+/// Block of the last message from `source_channel` the inbound channel accepted, searched
+/// backwards from the light client's best header. Messages older than the oldest header
+/// the light client holds cannot be proven anymore, so the search stops there.
+async fn last_accepted_message_block(
+	ethereum: &EthereumClient,
+	source_channel: H160,
+	state: &BridgeState,
+) -> Result<u64, Error> {
+	if state.accepted_nonce == 0 {
+		return Ok(state.oldest_header)
+	}
+	let mut to = state.best.number;
+	while to >= state.oldest_header {
+		let from = to.saturating_sub(LOG_SCAN_WINDOW - 1).max(state.oldest_header);
+		let accepted = ethereum
+			.logs(source_channel, from, to)
+			.await?
+			.iter()
+			.filter(
+				|log| matches!(message_nonce(&log.data), Some(nonce) if nonce <= state.accepted_nonce),
+			)
+			.map(|log| log.block_number.low_u64())
+			.max();
+		if let Some(number) = accepted {
+			return Ok(number)
+		}
+		if from == 0 {
+			break
+		}
+		to = from - 1;
+	}
+	Ok(state.oldest_header)
+}
+
+/// Runs the relayer until the node shuts down.
+pub async fn run<S: Submitter>(config: RelayerConfig, submitter: S) {
+	let ethereum = match EthereumClient::connect(&config.endpoint).await {
+		Ok(ethereum) => ethereum,
+		Err(e) => {
+			log::error!(target: LOG_TARGET, "Unable to connect to {}: {}", config.endpoint, e);
+			return
+		},
+	};
+	let relayer = match (config.start_block, submitter.bridge_state(config.source_channel)) {
+		(Some(from), _) => Ok(Relayer::new(ethereum, submitter, config.source_channel, from)),
+		(None, Some(state)) =>
+			Relayer::resume(ethereum, submitter, config.source_channel, state).await,
+		(None, None) => {
+			log::warn!(target: LOG_TARGET, "Unable to read the bridge state, starting at the head");
+			match ethereum.block_number().await {
+				Ok(head) => Ok(Relayer::new(ethereum, submitter, config.source_channel, head)),
+				Err(e) => Err(e),
+			}
+		},
+	};
+	let mut relayer = match relayer {
+		Ok(relayer) => relayer,
+		Err(e) => {
+			log::error!(target: LOG_TARGET, "Unable to start relaying: {}", e);
+			return
+		},
+	};
+	log::info!(
+		target: LOG_TARGET,
+		"Relaying Ethereum headers from block {} and messages from block {}",
+		relayer.next_header,
+		relayer.next_message,
+	);
+	loop {
+		if let Err(e) = relayer.poll().await {
+			log::warn!(target: LOG_TARGET, "{}", e);
+		}
+		futures_timer::Delay::new(config.poll_interval).await;
+	}
+}
+
+/// Signs `call` with the `ethr` key `public` of `keystore`, for the chain with the given
+/// `(spec_version, transaction_version)` and genesis hash.
+fn sign_extrinsic(
+	keystore: &dyn SyncCryptoStore,
+	public: sr25519::Public,
+	call: Call,
+	nonce: Index,
+	(spec_version, transaction_version): (u32, u32),
+	genesis_hash: H256,
+) -> Result<UncheckedExtrinsic, Error> {
+	let extra = (
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		trading_fees_pallet::ChargeTradingFee::<Runtime>::from(0),
+		extrinsic_index_pallet::IndexExtrinsic::<Runtime>::new(),
+	);
+	let raw_payload = SignedPayload::from_raw(
+		call,
+		extra,
+		(spec_version, transaction_version, genesis_hash, genesis_hash, (), (), (), ()),
+	);
+	let signature = raw_payload
+		.using_encoded(|payload| {
+			SyncCryptoStore::sign_with(keystore, KEY_TYPE, &public.into(), payload)
+		})
+		.map_err(|e| Error::Submit(e.to_string()))?
+		.ok_or_else(|| Error::Submit("Relayer key is missing from the keystore".into()))?;
+	let signature = sr25519::Signature::try_from(signature.as_slice())
+		.map_err(|_| Error::Submit("Invalid signature".into()))?;
+	let (call, extra, _) = raw_payload.deconstruct();
+
+	let account: AccountId = MultiSigner::from(public).into_account();
+	Ok(UncheckedExtrinsic::new_signed(call, Address::Id(account), signature.into(), extra))
+}
+
+/// Submits relayed calls as extrinsics signed with the `ethr` key of the local keystore.
+pub struct TransactionSubmitter<C, P> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	keystore: SyncCryptoStorePtr,
+	public: sr25519::Public,
+	/// Nonce to use for the next extrinsic, ahead of the chain while previous ones are pending.
+	next_nonce: Arc<Mutex<Index>>,
+}
+
+impl<C, P> TransactionSubmitter<C, P> {
+	/// Fails if the keystore holds no `ethr` key.
+	pub fn new(client: Arc<C>, pool: Arc<P>, keystore: SyncCryptoStorePtr) -> Result<Self, String> {
+		let public = SyncCryptoStore::sr25519_public_keys(&*keystore, KEY_TYPE)
+			.into_iter()
+			.next()
+			.ok_or_else(|| "No `ethr` key found in the keystore for the Ethereum relayer".to_string())?;
+		Ok(TransactionSubmitter { client, pool, keystore, public, next_nonce: Default::default() })
+	}
+}
+
+impl<C, P> TransactionSubmitter<C, P>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: sp_api::Core<Block> + substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
+{
+	/// Signs `call` with the next nonce, also returning that nonce and the on-chain one.
+	///
+	/// The nonce is not reserved: `submit` only commits it once the pool accepted the
+	/// extrinsic.
+	fn create_extrinsic(&self, call: Call) -> Result<(UncheckedExtrinsic, Index, Index), Error> {
+		use sp_api::Core;
+		use substrate_frame_rpc_system::AccountNonceApi;
+
+		let account: AccountId = MultiSigner::from(self.public).into_account();
+		let info = self.client.info();
+		let at = BlockId::hash(info.best_hash);
+		let on_chain = self
+			.client
+			.runtime_api()
+			.account_nonce(&at, account)
+			.map_err(|e| Error::Submit(e.to_string()))?;
+		let nonce = on_chain.max(*self.next_nonce.lock());
+		let version = self.client.runtime_api().version(&at).map_err(|e| Error::Submit(e.to_string()))?;
+
+		let extrinsic = sign_extrinsic(
+			&*self.keystore,
+			self.public,
+			call,
+			nonce,
+			(version.spec_version, version.transaction_version),
+			info.genesis_hash,
+		)?;
+		Ok((extrinsic, nonce, on_chain))
+	}
+}
+
+impl<C, P> Submitter for TransactionSubmitter<C, P>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: sp_api::Core<Block>
+		+ substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>
+		+ BridgeApi<Block>,
+	P: TransactionPool<Block = Block> + 'static,
+{
+	fn bridge_state(&self, source_channel: H160) -> Option<BridgeState> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		let (_, accepted_nonce) = api
+			.inbound_nonces(&at)
+			.ok()??
+			.into_iter()
+			.find(|(channel, _)| *channel == source_channel)?;
+		Some(BridgeState {
+			best: api.best_block(&at).ok()??,
+			oldest_header: api.oldest_header(&at).ok()??,
+			accepted_nonce,
+		})
+	}
+
+	fn submit(&self, call: RelayCall) -> BoxFuture<'static, Result<(), Error>> {
+		let call = match call {
+			RelayCall::ImportHeader(header) =>
+				Call::EthereumLightClient(snowbridge_ethereum_light_client::Call::import_header(
+					header,
+					// Proof of work is not verified by the runtime, see `VerifyPoW`.
+					Vec::new(),
+				)),
+			RelayCall::SubmitMessage(message) =>
+				Call::BasicInboundChannel(snowbridge_basic_channel::inbound::Call::submit(message)),
+		};
+		let extrinsic = self.create_extrinsic(call);
+		let pool = self.pool.clone();
+		let next_nonce = self.next_nonce.clone();
+		let at = BlockId::hash(self.client.info().best_hash);
+
+		Box::pin(async move {
+			let (extrinsic, nonce, on_chain) = extrinsic?;
+			match pool.submit_one(&at, TransactionSource::Local, extrinsic.into()).await {
+				Ok(_) => {
+					*next_nonce.lock() = nonce + 1;
+					Ok(())
+				},
+				Err(e) => {
+					// Pending extrinsics may have been dropped from the pool as well, so start
+					// over from the chain rather than leave a gap that nothing ever fills.
+					*next_nonce.lock() = on_chain;
+					Err(Error::Submit(e.to_string()))
+				},
+			}
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+	use sc_keystore::LocalKeystore;
+	use sp_runtime::{
+		traits::Checkable,
+		transaction_validity::{InvalidTransaction, TransactionValidityError},
+	};
+
+	#[test]
+	fn signed_extrinsics_pass_the_runtime_checks() {
+		let keystore = LocalKeystore::in_memory();
+		let public = SyncCryptoStore::sr25519_generate_new(&keystore, KEY_TYPE, None).unwrap();
+		let genesis_hash = H256::repeat_byte(1);
+		let version = node_polkadex_runtime::VERSION;
+		let call = Call::System(frame_system::Call::remark(vec![1, 2, 3]));
+		let sign = |transaction_version| {
+			let version = (version.spec_version, transaction_version);
+			let extrinsic =
+				sign_extrinsic(&keystore, public, call.clone(), 7, version, genesis_hash).unwrap();
+			// The extrinsic reaches the pool encoded.
+			UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..]).unwrap()
+		};
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			frame_system::BlockHash::<Runtime>::insert(0, genesis_hash);
+			let context = frame_system::ChainContext::<Runtime>::default();
+
+			let checked = sign(version.transaction_version).check(&context).unwrap();
+			let (signer, extra) = checked.signed.unwrap();
+			assert_eq!(signer, MultiSigner::from(public).into_account());
+			assert_eq!(extra.4, frame_system::CheckNonce::from(7));
+			assert_eq!(checked.function, call);
+
+			// The signature covers the additional signed data the runtime expects.
+			assert_eq!(
+				sign(version.transaction_version + 1).check(&context).map(|_| ()),
+				Err(TransactionValidityError::Invalid(InvalidTransaction::BadProof))
+			);
+		});
+	}
+
+	#[test]
+	fn extrinsics_require_the_relayer_key() {
+		let keystore = LocalKeystore::in_memory();
+		let public = sr25519::Public::from_raw([1; 32]);
+		let call = Call::System(frame_system::Call::remark(vec![]));
+		assert!(matches!(
+			sign_extrinsic(&keystore, public, call, 0, (1, 1), H256::zero()),
+			Err(Error::Submit(_))
+		));
+	}
+}
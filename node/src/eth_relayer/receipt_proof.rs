@@ -0,0 +1,223 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Receipt encoding and Merkle Patricia proofs against an Ethereum `receiptsRoot`.
+
+use rlp::RlpStream;
+use sp_core::{hashing::keccak_256, H160, H256};
+
+/// A log entry as emitted by an Ethereum contract.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Log {
+	pub address: H160,
+	pub topics: Vec<H256>,
+	pub data: Vec<u8>,
+}
+
+/// A transaction receipt as stored in the receipts trie of a block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Receipt {
+	/// EIP-2718 transaction type, `0` for legacy transactions.
+	pub tx_type: u8,
+	pub status: u64,
+	pub cumulative_gas_used: u64,
+	pub logs_bloom: Vec<u8>,
+	pub logs: Vec<Log>,
+}
+
+/// RLP encoding of a log, as expected by the inbound channel.
+pub fn encode_log(log: &Log) -> Vec<u8> {
+	let mut stream = RlpStream::new();
+	append_log(&mut stream, log);
+	stream.out().to_vec()
+}
+
+fn append_log(stream: &mut RlpStream, log: &Log) {
+	stream.begin_list(3);
+	stream.append(&log.address.as_bytes());
+	stream.begin_list(log.topics.len());
+	for topic in log.topics.iter() {
+		stream.append(&topic.as_bytes());
+	}
+	stream.append(&log.data.as_slice());
+}
+
+/// Encoding of a receipt as it appears as a value in the receipts trie.
+pub fn encode_receipt(receipt: &Receipt) -> Vec<u8> {
+	let mut stream = RlpStream::new_list(4);
+	stream.append(&receipt.status);
+	stream.append(&receipt.cumulative_gas_used);
+	stream.append(&receipt.logs_bloom.as_slice());
+	stream.begin_list(receipt.logs.len());
+	for log in receipt.logs.iter() {
+		append_log(&mut stream, log);
+	}
+	let encoded = stream.out().to_vec();
+	match receipt.tx_type {
+		0 => encoded,
+		tx_type => [vec![tx_type], encoded].concat(),
+	}
+}
+
+/// Builds the receipts trie of a block from its encoded receipts and returns the trie root
+/// together with the nodes on the path to the receipt at `index`, root node first.
+pub fn build_proof(encoded_receipts: &[Vec<u8>], index: usize) -> (H256, Vec<Vec<u8>>) {
+	let items: Vec<(Vec<u8>, &[u8])> = encoded_receipts
+		.iter()
+		.enumerate()
+		.map(|(i, value)| (to_nibbles(&rlp::encode(&(i as u64))), value.as_slice()))
+		.collect();
+	let target = to_nibbles(&rlp::encode(&(index as u64)));
+
+	let mut proof = Vec::new();
+	let root = encode_node(&items, 0, Some(&target), &mut proof);
+	// The root node is always referenced by its hash, whatever its size.
+	(H256::from(keccak_256(&root)), proof)
+}
+
+/// Returns the receipts root of a block from its encoded receipts.
+pub fn receipts_root(encoded_receipts: &[Vec<u8>]) -> H256 {
+	build_proof(encoded_receipts, 0).0
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+	bytes.iter().flat_map(|b| vec![b >> 4, b & 0x0f]).collect()
+}
+
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+	let flag = if leaf { 2 } else { 0 };
+	let (mut out, rest) = if nibbles.len() % 2 == 1 {
+		(vec![((flag + 1) << 4) | nibbles[0]], &nibbles[1..])
+	} else {
+		(vec![flag << 4], nibbles)
+	};
+	out.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+	out
+}
+
+fn append_child(stream: &mut RlpStream, encoded: &[u8]) {
+	if encoded.len() < 32 {
+		stream.append_raw(encoded, 1);
+	} else {
+		stream.append(&keccak_256(encoded).to_vec());
+	}
+}
+
+/// Encodes the sub-trie made of `items` whose keys share their first `depth` nibbles.
+/// Nodes on the path to `target` are pushed to `proof`, parents before children.
+fn encode_node(
+	items: &[(Vec<u8>, &[u8])],
+	depth: usize,
+	target: Option<&[u8]>,
+	proof: &mut Vec<Vec<u8>>,
+) -> Vec<u8> {
+	let slot = proof.len();
+	if target.is_some() {
+		// Reserve the slot of this node so that it precedes its children.
+		proof.push(Vec::new());
+	}
+
+	let encoded = match items {
+		[] => rlp::NULL_RLP.to_vec(),
+		[(key, value)] => {
+			let mut stream = RlpStream::new_list(2);
+			stream.append(&hex_prefix(&key[depth..], true));
+			stream.append(value);
+			stream.out().to_vec()
+		},
+		_ => {
+			let shared = (depth..)
+				.take_while(|i| {
+					let first = &items[0].0;
+					*i < first.len() && items.iter().all(|(key, _)| key.get(*i) == first.get(*i))
+				})
+				.count();
+			if shared > 0 {
+				let child = encode_node(items, depth + shared, target, proof);
+				let mut stream = RlpStream::new_list(2);
+				stream.append(&hex_prefix(&items[0].0[depth..depth + shared], false));
+				append_child(&mut stream, &child);
+				stream.out().to_vec()
+			} else {
+				let mut stream = RlpStream::new_list(17);
+				for nibble in 0..16u8 {
+					let children: Vec<(Vec<u8>, &[u8])> = items
+						.iter()
+						.filter(|(key, _)| key.get(depth) == Some(&nibble))
+						.cloned()
+						.collect();
+					if children.is_empty() {
+						stream.append_empty_data();
+					} else {
+						let on_path = target.filter(|t| t.get(depth) == Some(&nibble));
+						let child = encode_node(&children, depth + 1, on_path, proof);
+						append_child(&mut stream, &child);
+					}
+				}
+				match items.iter().find(|(key, _)| key.len() == depth) {
+					Some((_, value)) => stream.append(value),
+					None => stream.append_empty_data(),
+				};
+				stream.out().to_vec()
+			}
+		},
+	};
+
+	if target.is_some() {
+		proof[slot] = encoded.clone();
+	}
+	encoded
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn receipt(gas: u64) -> Receipt {
+		Receipt {
+			tx_type: 0,
+			status: 1,
+			cumulative_gas_used: gas,
+			logs_bloom: vec![0u8; 256],
+			logs: vec![],
+		}
+	}
+
+	#[test]
+	fn single_receipt_root_is_hash_of_leaf() {
+		let encoded = vec![encode_receipt(&receipt(21_000))];
+		let (root, proof) = build_proof(&encoded, 0);
+		assert_eq!(proof.len(), 1);
+		assert_eq!(root, H256::from(keccak_256(&proof[0])));
+	}
+
+	#[test]
+	fn proof_nodes_are_linked_by_hash() {
+		let encoded: Vec<_> = (0..20).map(|i| encode_receipt(&receipt(21_000 * (i + 1)))).collect();
+		for index in 0..encoded.len() {
+			let (root, proof) = build_proof(&encoded, index);
+			assert_eq!(root, receipts_root(&encoded));
+			assert_eq!(root, H256::from(keccak_256(&proof[0])));
+			for pair in proof.windows(2) {
+				let child_hash = keccak_256(&pair[1]);
+				assert!(pair[0].windows(32).any(|w| w == child_hash));
+			}
+			assert!(proof.last().unwrap().windows(encoded[index].len()).any(|w| w == &encoded[index][..]));
+		}
+	}
+}
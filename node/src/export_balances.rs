@@ -0,0 +1,409 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `export-balances` subcommand, writing the balances, vesting schedules and staking
+//! ledgers of every account at a block of the local database to CSV files.
+//!
+//! Amounts are written in the smallest unit, `1 PDEX = 10^11`. The sum of the free and
+//! reserved balances of every pallet and asset is reconciled against its `TotalIssuance` in
+//! `totals.csv`, and the command fails if they differ.
+//!
+//! Schedules of the `Vesting` pallet unlock every block, so they are written with a period of
+//! one block and without a period count.
+
+use std::{
+	collections::BTreeMap,
+	fs::{self, File},
+	io::{BufWriter, Write},
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use crate::{inspect::parse_block_id, node_client::storage_key};
+use codec::Decode;
+use pallet_staking::StakingLedger;
+use polkadex_primitives::{assets::AssetId, AccountId, Balance, Block, BlockNumber, Index};
+use sc_cli::{CliConfiguration, Error, ImportParams, Result, SharedParams};
+use sc_client_api::StorageProvider;
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, storage::StorageKey};
+use sp_runtime::{generic::BlockId, traits::ConvertInto};
+use structopt::StructOpt;
+
+type AccountInfo = frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>>;
+type OrmlVestingSchedule = orml_vesting::VestingSchedule<BlockNumber, Balance>;
+type VestingInfo = pallet_vesting::VestingInfo<Balance, BlockNumber>;
+
+/// Length of the `Blake2_128Concat` hash in front of a key.
+const BLAKE2_128_LEN: usize = 16;
+/// Length of the `Twox64Concat` hash in front of a key.
+const TWOX_64_LEN: usize = 8;
+
+/// The `export-balances` command.
+#[derive(Debug, StructOpt)]
+pub struct ExportBalancesCmd {
+	/// Hash or number of the block to export, the last finalized block by default.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub block: Option<String>,
+
+	/// Directory the CSV files are written to, created if it does not exist.
+	#[structopt(long, parse(from_os_str))]
+	pub output: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl CliConfiguration for ExportBalancesCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+/// Balance of an account in the `Balances` or `Tokens` pallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BalanceRow {
+	account: AccountId,
+	pallet: &'static str,
+	asset: AssetId,
+	free: Balance,
+	reserved: Balance,
+	locked: Balance,
+}
+
+/// Total balances of an asset in a pallet, next to its `TotalIssuance`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Total {
+	free: Balance,
+	reserved: Balance,
+	locked: Balance,
+	issuance: Balance,
+}
+
+impl Total {
+	fn reconciles(&self) -> bool {
+		self.free.checked_add(self.reserved) == Some(self.issuance)
+	}
+}
+
+/// Sums `rows` by pallet and asset and adds the issuance of every asset in `issuance`.
+fn totals(
+	rows: &[BalanceRow],
+	issuance: &[(&'static str, AssetId, Balance)],
+) -> BTreeMap<(&'static str, String), Total> {
+	let mut totals = BTreeMap::<_, Total>::new();
+	for row in rows {
+		let total = totals.entry((row.pallet, format!("{:?}", row.asset))).or_default();
+		total.free = total.free.saturating_add(row.free);
+		total.reserved = total.reserved.saturating_add(row.reserved);
+		total.locked = total.locked.saturating_add(row.locked);
+	}
+	for (pallet, asset, amount) in issuance {
+		totals.entry((*pallet, format!("{:?}", asset))).or_default().issuance = *amount;
+	}
+	totals
+}
+
+/// Splits the account off a `Blake2_128Concat` hashed key, returning the rest of the key.
+fn decode_account(key: &[u8]) -> Result<(AccountId, &[u8])> {
+	let mut rest = key.get(BLAKE2_128_LEN..).ok_or("Storage key too short")?;
+	let account = AccountId::decode(&mut rest)?;
+	Ok((account, rest))
+}
+
+/// Decodes the asset of a `Twox64Concat` hashed key.
+fn decode_asset(key: &[u8]) -> Result<AssetId> {
+	let mut rest = key.get(TWOX_64_LEN..).ok_or("Storage key too short")?;
+	AssetId::decode(&mut rest).map_err(Into::into)
+}
+
+/// Reads state of a single block.
+struct State<'a, C> {
+	client: &'a C,
+	id: BlockId<Block>,
+}
+
+impl<'a, C> State<'a, C>
+where
+	C: StorageProvider<Block, sc_service::TFullBackend<Block>>,
+{
+	fn value<T: Decode>(&self, pallet: &str, item: &str) -> Result<Option<T>> {
+		self.client
+			.storage(&self.id, &StorageKey(storage_key(pallet, item)))?
+			.map(|data| T::decode(&mut &data.0[..]))
+			.transpose()
+			.map_err(Into::into)
+	}
+
+	/// Returns every entry of a map, with the key stripped of the storage prefix.
+	fn pairs<T: Decode>(&self, pallet: &str, item: &str) -> Result<Vec<(Vec<u8>, T)>> {
+		let prefix = storage_key(pallet, item);
+		self.client
+			.storage_pairs(&self.id, &StorageKey(prefix.clone()))?
+			.into_iter()
+			.map(|(key, data)| {
+				let value = T::decode(&mut &data.0[..])?;
+				Ok((key.0[prefix.len()..].to_vec(), value))
+			})
+			.collect()
+	}
+
+	fn balances(&self) -> Result<Vec<BalanceRow>> {
+		let mut rows = Vec::new();
+		for (key, info) in self.pairs::<AccountInfo>("System", "Account")? {
+			let (account, _) = decode_account(&key)?;
+			rows.push(BalanceRow {
+				account,
+				pallet: "Balances",
+				asset: AssetId::POLKADEX,
+				free: info.data.free,
+				reserved: info.data.reserved,
+				locked: info.data.misc_frozen.max(info.data.fee_frozen),
+			});
+		}
+		for (key, data) in self.pairs::<orml_tokens::AccountData<Balance>>("Tokens", "Accounts")? {
+			let (account, rest) = decode_account(&key)?;
+			rows.push(BalanceRow {
+				account,
+				pallet: "Tokens",
+				asset: decode_asset(rest)?,
+				free: data.free,
+				reserved: data.reserved,
+				locked: data.frozen,
+			});
+		}
+		Ok(rows)
+	}
+
+	fn issuance(&self) -> Result<Vec<(&'static str, AssetId, Balance)>> {
+		let native = self.value::<Balance>("Balances", "TotalIssuance")?.unwrap_or_default();
+		let mut issuance = vec![("Balances", AssetId::POLKADEX, native)];
+		for (key, amount) in self.pairs::<Balance>("Tokens", "TotalIssuance")? {
+			issuance.push(("Tokens", decode_asset(&key)?, amount));
+		}
+		Ok(issuance)
+	}
+}
+
+/// Creates `name` in `directory` and writes the CSV `header` to it.
+fn create_csv(directory: &Path, name: &str, header: &str) -> Result<BufWriter<File>> {
+	let mut file = BufWriter::new(File::create(directory.join(name))?);
+	writeln!(file, "{}", header)?;
+	Ok(file)
+}
+
+impl ExportBalancesCmd {
+	/// Run the export-balances command against the client's database.
+	pub fn run<C>(&self, client: Arc<C>) -> Result<()>
+	where
+		C: HeaderBackend<Block> + StorageProvider<Block, sc_service::TFullBackend<Block>>,
+	{
+		let id = match &self.block {
+			Some(block) => parse_block_id(block)?,
+			None => BlockId::Hash(client.info().finalized_hash),
+		};
+		let hash = client
+			.block_hash_from_id(&id)?
+			.ok_or_else(|| Error::Input(format!("Block {} not found", id)))?;
+		let number = client
+			.number(hash)?
+			.ok_or_else(|| Error::Input(format!("Block {} not found", id)))?;
+		let state = State { client: &*client, id: BlockId::Hash(hash) };
+		if state.value::<Balance>("Balances", "TotalIssuance").is_err() {
+			return Err(Error::Input(format!(
+				"State of block #{} is not available, it may have been pruned",
+				number
+			)))
+		}
+		fs::create_dir_all(&self.output)?;
+
+		let rows = state.balances()?;
+		let mut file =
+			create_csv(&self.output, "balances.csv", "account,pallet,asset,free,reserved,locked")?;
+		for row in &rows {
+			writeln!(
+				file,
+				"{},{},{:?},{},{},{}",
+				row.account.to_ss58check(),
+				row.pallet,
+				row.asset,
+				row.free,
+				row.reserved,
+				row.locked
+			)?;
+		}
+		file.flush()?;
+
+		let mut file = create_csv(
+			&self.output,
+			"vesting.csv",
+			"account,pallet,start,period,period_count,per_period,locked",
+		)?;
+		for (key, schedules) in
+			state.pairs::<Vec<OrmlVestingSchedule>>("OrmlVesting", "VestingSchedules")?
+		{
+			let (account, _) = decode_account(&key)?;
+			for schedule in schedules {
+				writeln!(
+					file,
+					"{},OrmlVesting,{},{},{},{},{}",
+					account.to_ss58check(),
+					schedule.start,
+					schedule.period,
+					schedule.period_count,
+					schedule.per_period,
+					schedule.locked_amount(number)
+				)?;
+			}
+		}
+		for (key, schedules) in state.pairs::<Vec<VestingInfo>>("Vesting", "Vesting")? {
+			let (account, _) = decode_account(&key)?;
+			for schedule in schedules {
+				writeln!(
+					file,
+					"{},Vesting,{},1,,{},{}",
+					account.to_ss58check(),
+					schedule.starting_block(),
+					schedule.per_block(),
+					schedule.locked_at::<ConvertInto>(number)
+				)?;
+			}
+		}
+		file.flush()?;
+
+		let mut file =
+			create_csv(&self.output, "staking.csv", "stash,controller,total,active,unlocking")?;
+		for (key, ledger) in
+			state.pairs::<StakingLedger<AccountId, Balance>>("Staking", "Ledger")?
+		{
+			let (controller, _) = decode_account(&key)?;
+			let unlocking = ledger
+				.unlocking
+				.iter()
+				.fold(0, |sum: Balance, chunk| sum.saturating_add(chunk.value));
+			writeln!(
+				file,
+				"{},{},{},{},{}",
+				ledger.stash.to_ss58check(),
+				controller.to_ss58check(),
+				ledger.total,
+				ledger.active,
+				unlocking
+			)?;
+		}
+		file.flush()?;
+
+		let totals = totals(&rows, &state.issuance()?);
+		let mut file = create_csv(
+			&self.output,
+			"totals.csv",
+			"pallet,asset,free,reserved,locked,total_issuance,reconciled",
+		)?;
+		for ((pallet, asset), total) in &totals {
+			writeln!(
+				file,
+				"{},{},{},{},{},{},{}",
+				pallet,
+				asset,
+				total.free,
+				total.reserved,
+				total.locked,
+				total.issuance,
+				total.reconciles()
+			)?;
+		}
+		file.flush()?;
+
+		println!(
+			"Exported {} balances of block #{} ({}) to {}",
+			rows.len(),
+			number,
+			hash,
+			self.output.display()
+		);
+		let mismatches: Vec<_> = totals
+			.iter()
+			.filter(|(_, total)| !total.reconciles())
+			.map(|((pallet, asset), total)| {
+				format!(
+					"{} {}: free {} + reserved {} != total issuance {}",
+					pallet, asset, total.free, total.reserved, total.issuance
+				)
+			})
+			.collect();
+		if mismatches.is_empty() {
+			Ok(())
+		} else {
+			Err(format!("Balances do not reconcile with TotalIssuance:\n{}", mismatches.join("\n"))
+				.into())
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_polkadex_runtime::Runtime;
+	use sp_keyring::AccountKeyring;
+
+	#[test]
+	fn decodes_tokens_account_key() {
+		let account = AccountKeyring::Alice.to_account_id();
+		let key = orml_tokens::Accounts::<Runtime>::hashed_key_for(&account, &AssetId::POLKADEX);
+		let prefix = storage_key("Tokens", "Accounts");
+		assert!(key.starts_with(&prefix));
+
+		let (decoded, rest) = decode_account(&key[prefix.len()..]).unwrap();
+		assert_eq!(decoded, account);
+		assert_eq!(decode_asset(rest).unwrap(), AssetId::POLKADEX);
+	}
+
+	#[test]
+	fn reconciles_totals_per_pallet() {
+		let row = |account: AccountKeyring, pallet, free, reserved| BalanceRow {
+			account: account.to_account_id(),
+			pallet,
+			asset: AssetId::POLKADEX,
+			free,
+			reserved,
+			locked: 0,
+		};
+		let rows = vec![
+			row(AccountKeyring::Alice, "Balances", 70, 30),
+			row(AccountKeyring::Bob, "Balances", 50, 0),
+			row(AccountKeyring::Alice, "Tokens", 10, 0),
+		];
+		let issuance =
+			vec![("Balances", AssetId::POLKADEX, 150), ("Tokens", AssetId::POLKADEX, 20)];
+		let totals = totals(&rows, &issuance);
+
+		let native = &totals[&("Balances", format!("{:?}", AssetId::POLKADEX))];
+		assert_eq!((native.free, native.reserved, native.issuance), (120, 30, 150));
+		assert!(native.reconciles());
+		assert!(!totals[&("Tokens", format!("{:?}", AssetId::POLKADEX))].reconciles());
+	}
+}
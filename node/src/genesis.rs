@@ -0,0 +1,623 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Declarative genesis descriptions.
+//!
+//! A description is a TOML or JSON file listing the authorities, endowed balances, vesting
+//! schedules, council and bridge configuration of a network. Lists of allocations can be
+//! shared between networks by naming other files in `include`, resolved relative to the
+//! description.
+//!
+//! Accounts and keys are given as SS58 addresses, `0x` prefixed hex public keys or
+//! development seeds such as `//Alice`. Balances are integers in the smallest unit or
+//! strings, optionally suffixed with ` PDEX`.
+
+use std::{
+	convert::TryInto,
+	path::{Path, PathBuf},
+};
+
+use crate::chain_spec::{
+	authority_keys_from_seed, get_account_id_from_seed, get_from_seed, session_keys,
+};
+use grandpa_primitives::AuthorityId as GrandpaId;
+use node_polkadex_runtime::{
+	constants::currency::PDEX, wasm_binary_unwrap, AuthorityDiscoveryConfig, BabeConfig,
+	BalancesConfig, BasicInboundChannelConfig, CouncilConfig, DispatchWhitelistConfig,
+	ERC20PDEXConfig, ElectionsConfig, EthereumLightClientConfig, GenesisConfig, GrandpaConfig,
+	ImOnlineConfig, IndicesConfig, OrmlVestingConfig, PolkadexTreasuryModuleId, SessionConfig,
+	StakerStatus, StakingConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig,
+};
+use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
+use polkadex_primitives::{AccountId, Balance, BlockNumber};
+use sc_chain_spec::Properties;
+use sc_network::config::MultiaddrWithPeerId;
+use sc_service::ChainType;
+use serde::{Deserialize, Deserializer};
+use snowbridge_ethereum_light_client::EthereumHeader;
+use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
+use sp_consensus_babe::AuthorityId as BabeId;
+use sp_core::{
+	bytes::from_hex, crypto::Ss58Codec, sr25519, Bytes, Pair, Public, H160, H256, U256,
+};
+use sp_runtime::{traits::AccountIdConversion, Perbill};
+
+/// Stash, controller and session keys of a genesis authority.
+pub type AuthorityKeys =
+	(AccountId, AccountId, GrandpaId, BabeId, ImOnlineId, AuthorityDiscoveryId);
+
+/// Description of the genesis of a network.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisDescription {
+	/// Human readable name of the network.
+	pub name: String,
+	/// Identifier of the network.
+	pub id: String,
+	/// Type of the network: `Development`, `Local` or `Live`.
+	pub chain_type: ChainType,
+	/// Boot nodes of the network.
+	#[serde(default)]
+	pub boot_nodes: Vec<MultiaddrWithPeerId>,
+	/// Telemetry endpoints.
+	#[serde(default)]
+	pub telemetry_endpoints: Vec<TelemetryEndpoint>,
+	/// Network protocol id.
+	pub protocol_id: Option<String>,
+	/// Properties reported to clients, such as the token symbol.
+	pub properties: Option<Properties>,
+	/// Allocation files whose endowments and vesting schedules are added to this genesis.
+	#[serde(default)]
+	pub include: Vec<PathBuf>,
+	/// Sudo key.
+	pub root_key: String,
+	/// Endowment of the Polkadex treasury account.
+	#[serde(deserialize_with = "amount")]
+	pub treasury: Balance,
+	/// Genesis validators.
+	pub authorities: Vec<AuthorityDescription>,
+	/// Genesis nominators, bonded with the staking stash.
+	#[serde(default)]
+	pub nominators: Vec<NominatorDescription>,
+	/// Endowed accounts.
+	#[serde(default)]
+	pub endowed: Vec<Endowment>,
+	/// Vesting schedules.
+	#[serde(default)]
+	pub vesting: Vec<VestingDescription>,
+	/// Staking configuration.
+	pub staking: StakingDescription,
+	/// Council and technical committee.
+	pub council: CouncilDescription,
+	/// Ethereum bridge configuration.
+	pub bridge: BridgeDescription,
+}
+
+/// A telemetry endpoint.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TelemetryEndpoint {
+	/// Websocket URL of the endpoint.
+	pub url: String,
+	/// Verbosity of the telemetry sent to the endpoint.
+	pub verbosity: u8,
+}
+
+/// Endowments and vesting schedules that can be shared between descriptions.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Allocations {
+	/// Endowed accounts.
+	#[serde(default)]
+	pub endowed: Vec<Endowment>,
+	/// Vesting schedules.
+	#[serde(default)]
+	pub vesting: Vec<VestingDescription>,
+}
+
+/// A genesis validator.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum AuthorityDescription {
+	/// Development validator whose keys are all derived from `seed`.
+	Seed {
+		/// Seed, such as `Alice`.
+		seed: String,
+	},
+	/// Validator with explicit keys.
+	Keys {
+		/// Stash account.
+		stash: String,
+		/// Controller account.
+		controller: String,
+		/// GRANDPA key.
+		grandpa: String,
+		/// BABE key.
+		babe: String,
+		/// ImOnline key.
+		im_online: String,
+		/// Authority discovery key.
+		authority_discovery: String,
+	},
+}
+
+/// A genesis nominator.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NominatorDescription {
+	/// Nominator stash, also used as controller.
+	pub account: String,
+	/// Stashes of the nominated validators.
+	pub targets: Vec<String>,
+}
+
+/// An endowed account.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Endowment {
+	/// Endowed account.
+	pub account: String,
+	/// Free balance at genesis.
+	#[serde(deserialize_with = "amount")]
+	pub balance: Balance,
+}
+
+/// An `orml_vesting` schedule.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VestingDescription {
+	/// Vested account.
+	pub account: String,
+	/// Block at which the first period starts.
+	pub start: BlockNumber,
+	/// Number of blocks in a period.
+	pub period: BlockNumber,
+	/// Number of periods.
+	pub period_count: u32,
+	/// Amount released every period.
+	#[serde(deserialize_with = "amount")]
+	pub per_period: Balance,
+}
+
+/// Staking configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StakingDescription {
+	/// Amount bonded by every validator and nominator.
+	#[serde(deserialize_with = "amount")]
+	pub stash: Balance,
+	/// Ideal number of validators. Defaults to twice the number of authorities.
+	pub validator_count: Option<u32>,
+	/// Minimum number of validators. Defaults to the number of authorities.
+	pub minimum_validator_count: Option<u32>,
+}
+
+/// Council and technical committee.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CouncilDescription {
+	/// Council members elected at genesis.
+	pub members: Vec<String>,
+	/// Amount bonded by every council member.
+	#[serde(deserialize_with = "amount")]
+	pub bond: Balance,
+	/// Technical committee members.
+	pub technical_committee: Vec<String>,
+}
+
+/// Ethereum bridge configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BridgeDescription {
+	/// Address of the basic outbound channel contract on Ethereum.
+	pub source_channel: H160,
+	/// Address of the ERC20 PDEX contract.
+	pub erc20_pdex: H160,
+	/// Calls that may be dispatched from Ethereum.
+	#[serde(default)]
+	pub whitelist: Vec<WhitelistEntry>,
+	/// Total difficulty of the chain up to and including `initial_header`.
+	#[serde(deserialize_with = "amount")]
+	pub initial_difficulty: u128,
+	/// Ethereum header the light client starts from.
+	pub initial_header: EthereumHeaderDescription,
+}
+
+/// A call that may be dispatched from Ethereum.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WhitelistEntry {
+	/// Index of the pallet in `construct_runtime!`.
+	pub pallet_index: u8,
+	/// Index of the call in the pallet.
+	pub call_index: u8,
+	/// Restricts the call to a single source contract.
+	pub source: Option<H160>,
+}
+
+/// An Ethereum header, as returned by `eth_getBlockByNumber`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[allow(missing_docs)]
+pub struct EthereumHeaderDescription {
+	pub parent_hash: H256,
+	pub timestamp: u64,
+	pub number: u64,
+	pub author: H160,
+	pub transactions_root: H256,
+	pub ommers_hash: H256,
+	pub extra_data: Bytes,
+	pub state_root: H256,
+	pub receipts_root: H256,
+	pub logs_bloom: Bytes,
+	pub gas_used: u64,
+	pub gas_limit: u64,
+	pub difficulty: u64,
+	pub seal: Vec<Bytes>,
+	pub base_fee: Option<u64>,
+}
+
+/// Parses an amount in the smallest unit, or in PDEX when suffixed with ` PDEX`.
+pub fn parse_amount(s: &str) -> Result<u128, String> {
+	let s = s.trim();
+	let (number, unit) = match s.strip_suffix("PDEX") {
+		Some(number) => (number.trim(), PDEX),
+		None => (s, 1),
+	};
+	number
+		.replace('_', "")
+		.parse::<u128>()
+		.ok()
+		.and_then(|number| number.checked_mul(unit))
+		.ok_or_else(|| format!("Invalid amount: {}", s))
+}
+
+fn amount<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Amount {
+		Number(u64),
+		Text(String),
+	}
+	match Amount::deserialize(deserializer)? {
+		Amount::Number(number) => Ok(number as u128),
+		Amount::Text(text) => parse_amount(&text).map_err(serde::de::Error::custom),
+	}
+}
+
+fn public_bytes(s: &str) -> Result<[u8; 32], String> {
+	if s.starts_with("0x") {
+		let bytes = from_hex(s).map_err(|e| format!("Invalid public key {}: {}", s, e))?;
+		bytes.as_slice().try_into().map_err(|_| format!("Invalid public key length: {}", s))
+	} else {
+		AccountId::from_ss58check(s)
+			.map(Into::into)
+			.map_err(|e| format!("Invalid address {}: {:?}", s, e))
+	}
+}
+
+/// Parses an account given as an SS58 address, a hex public key or a `//Seed`.
+pub fn parse_account(s: &str) -> Result<AccountId, String> {
+	match s.strip_prefix("//") {
+		Some(seed) => Ok(get_account_id_from_seed::<sr25519::Public>(seed)),
+		None => public_bytes(s).map(AccountId::from),
+	}
+}
+
+fn parse_key<T>(s: &str) -> Result<T, String>
+where
+	T: Public,
+	T::Pair: Pair<Public = T>,
+{
+	match s.strip_prefix("//") {
+		Some(seed) => Ok(get_from_seed::<T>(seed)),
+		None => public_bytes(s).map(|bytes| T::from_slice(&bytes)),
+	}
+}
+
+fn parse_accounts(accounts: &[String]) -> Result<Vec<AccountId>, String> {
+	accounts.iter().map(|account| parse_account(account)).collect()
+}
+
+fn parse_format<T: serde::de::DeserializeOwned>(path: &Path, content: &str) -> Result<T, String> {
+	let result = match path.extension().and_then(|ext| ext.to_str()) {
+		Some("json") => serde_json::from_str(content).map_err(|e| e.to_string()),
+		Some("toml") => toml::from_str(content).map_err(|e| e.to_string()),
+		_ => return Err(format!("{}: expected a .toml or .json file", path.display())),
+	};
+	result.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+impl GenesisDescription {
+	/// Loads a description from `path`, resolving `include` relative to it.
+	pub fn from_file(path: &Path) -> Result<Self, String> {
+		let read = |path: &Path| {
+			std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+		};
+		let base = path.parent().map(Path::to_path_buf).unwrap_or_default();
+		Self::load(path, &read(path)?, |include| read(&base.join(include)))
+	}
+
+	/// Loads a description named `path` with the given content. Included files are read
+	/// with `read`.
+	pub fn load(
+		path: &Path,
+		content: &str,
+		read: impl Fn(&Path) -> Result<String, String>,
+	) -> Result<Self, String> {
+		let mut description: Self = parse_format(path, content)?;
+		for include in description.include.clone() {
+			let allocations: Allocations = parse_format(&include, &read(&include)?)?;
+			description.endowed.extend(allocations.endowed);
+			description.vesting.extend(allocations.vesting);
+		}
+		Ok(description)
+	}
+
+	/// Resolves all accounts and keys of the description.
+	pub fn resolve(&self) -> Result<Genesis, String> {
+		let authorities = self
+			.authorities
+			.iter()
+			.map(|authority| match authority {
+				AuthorityDescription::Seed { seed } => Ok(authority_keys_from_seed(seed)),
+				AuthorityDescription::Keys {
+					stash,
+					controller,
+					grandpa,
+					babe,
+					im_online,
+					authority_discovery,
+				} => Ok((
+					parse_account(stash)?,
+					parse_account(controller)?,
+					parse_key(grandpa)?,
+					parse_key(babe)?,
+					parse_key(im_online)?,
+					parse_key(authority_discovery)?,
+				)),
+			})
+			.collect::<Result<Vec<_>, String>>()?;
+		let nominators = self
+			.nominators
+			.iter()
+			.map(|nominator| {
+				Ok((parse_account(&nominator.account)?, parse_accounts(&nominator.targets)?))
+			})
+			.collect::<Result<Vec<_>, String>>()?;
+
+		let treasury = (PolkadexTreasuryModuleId::get().into_account(), self.treasury);
+		let balances = self
+			.endowed
+			.iter()
+			.map(|endowment| Ok((parse_account(&endowment.account)?, endowment.balance)))
+			.chain(std::iter::once(Ok(treasury)))
+			.collect::<Result<Vec<_>, String>>()?;
+		let vesting = self
+			.vesting
+			.iter()
+			.map(|schedule| {
+				Ok((
+					parse_account(&schedule.account)?,
+					schedule.start,
+					schedule.period,
+					schedule.period_count,
+					schedule.per_period,
+				))
+			})
+			.collect::<Result<Vec<_>, String>>()?;
+
+		let header = &self.bridge.initial_header;
+		let logs_bloom: [u8; 256] = header
+			.logs_bloom
+			.0
+			.as_slice()
+			.try_into()
+			.map_err(|_| "The logs bloom of the initial Ethereum header must be 256 bytes")?;
+		let initial_header = EthereumHeader {
+			parent_hash: header.parent_hash,
+			timestamp: header.timestamp.into(),
+			number: header.number.into(),
+			author: header.author,
+			transactions_root: header.transactions_root,
+			ommers_hash: header.ommers_hash,
+			extra_data: header.extra_data.0.clone(),
+			state_root: header.state_root,
+			receipts_root: header.receipts_root,
+			logs_bloom: (&logs_bloom).into(),
+			gas_used: header.gas_used.into(),
+			gas_limit: header.gas_limit.into(),
+			difficulty: header.difficulty.into(),
+			seal: header.seal.iter().map(|item| item.0.clone()).collect(),
+			base_fee: header.base_fee.map(U256::from),
+		};
+
+		Ok(Genesis {
+			root_key: parse_account(&self.root_key)?,
+			validator_count: self.staking.validator_count.unwrap_or(authorities.len() as u32 * 2),
+			minimum_validator_count: self
+				.staking
+				.minimum_validator_count
+				.unwrap_or(authorities.len() as u32),
+			authorities,
+			nominators,
+			balances,
+			vesting,
+			stash: self.staking.stash,
+			council: parse_accounts(&self.council.members)?,
+			council_bond: self.council.bond,
+			technical_committee: parse_accounts(&self.council.technical_committee)?,
+			source_channel: self.bridge.source_channel,
+			erc20_pdex: self.bridge.erc20_pdex,
+			whitelist: self
+				.bridge
+				.whitelist
+				.iter()
+				.map(|entry| (entry.pallet_index, entry.call_index, entry.source))
+				.collect(),
+			initial_header,
+			initial_difficulty: U256::from(self.bridge.initial_difficulty),
+		})
+	}
+}
+
+/// A genesis description with all accounts and keys resolved.
+#[derive(Clone)]
+pub struct Genesis {
+	/// Sudo key.
+	pub root_key: AccountId,
+	/// Genesis validators.
+	pub authorities: Vec<AuthorityKeys>,
+	/// Genesis nominators with their targets.
+	pub nominators: Vec<(AccountId, Vec<AccountId>)>,
+	/// Free balances, including the treasury.
+	pub balances: Vec<(AccountId, Balance)>,
+	/// `orml_vesting` schedules.
+	pub vesting: Vec<(AccountId, BlockNumber, BlockNumber, u32, Balance)>,
+	/// Amount bonded by every validator and nominator.
+	pub stash: Balance,
+	/// Ideal number of validators.
+	pub validator_count: u32,
+	/// Minimum number of validators.
+	pub minimum_validator_count: u32,
+	/// Council members.
+	pub council: Vec<AccountId>,
+	/// Amount bonded by every council member.
+	pub council_bond: Balance,
+	/// Technical committee members.
+	pub technical_committee: Vec<AccountId>,
+	/// Address of the basic outbound channel contract on Ethereum.
+	pub source_channel: H160,
+	/// Address of the ERC20 PDEX contract.
+	pub erc20_pdex: H160,
+	/// Calls that may be dispatched from Ethereum.
+	pub whitelist: Vec<(u8, u8, Option<H160>)>,
+	/// Ethereum header the light client starts from.
+	pub initial_header: EthereumHeader,
+	/// Total difficulty up to and including `initial_header`.
+	pub initial_difficulty: U256,
+}
+
+impl Genesis {
+	/// Builds the runtime genesis configuration.
+	pub fn build(&self) -> GenesisConfig {
+		let stakers = self
+			.authorities
+			.iter()
+			.map(|x| (x.0.clone(), x.1.clone(), self.stash, StakerStatus::Validator))
+			.chain(self.nominators.iter().map(|(nominator, targets)| {
+				let status = StakerStatus::Nominator(targets.clone());
+				(nominator.clone(), nominator.clone(), self.stash, status)
+			}))
+			.collect::<Vec<_>>();
+
+		GenesisConfig {
+			system: SystemConfig {
+				code: wasm_binary_unwrap().to_vec(),
+				changes_trie_config: Default::default(),
+			},
+			balances: BalancesConfig { balances: self.balances.clone() },
+			indices: IndicesConfig { indices: vec![] },
+			session: SessionConfig {
+				keys: self
+					.authorities
+					.iter()
+					.map(|x| {
+						(
+							x.0.clone(),
+							x.0.clone(),
+							session_keys(x.2.clone(), x.3.clone(), x.4.clone(), x.5.clone()),
+						)
+					})
+					.collect::<Vec<_>>(),
+			},
+			staking: StakingConfig {
+				validator_count: self.validator_count,
+				minimum_validator_count: self.minimum_validator_count,
+				invulnerables: self.authorities.iter().map(|x| x.0.clone()).collect(),
+				slash_reward_fraction: Perbill::from_percent(10),
+				stakers,
+				..Default::default()
+			},
+			elections: ElectionsConfig {
+				members: self
+					.council
+					.iter()
+					.cloned()
+					.map(|member| (member, self.council_bond))
+					.collect(),
+			},
+			council: CouncilConfig::default(),
+			technical_committee: TechnicalCommitteeConfig {
+				members: self.technical_committee.clone(),
+				phantom: Default::default(),
+			},
+			sudo: SudoConfig { key: self.root_key.clone() },
+			babe: BabeConfig {
+				authorities: vec![],
+				epoch_config: Some(node_polkadex_runtime::BABE_GENESIS_EPOCH_CONFIG),
+			},
+			im_online: ImOnlineConfig { keys: vec![] },
+			authority_discovery: AuthorityDiscoveryConfig { keys: vec![] },
+			grandpa: GrandpaConfig { authorities: vec![] },
+			technical_membership: Default::default(),
+			treasury: Default::default(),
+			vesting: Default::default(),
+			orml_vesting: OrmlVestingConfig { vesting: self.vesting.clone() },
+			tokens: Default::default(),
+			ethereum_light_client: EthereumLightClientConfig {
+				initial_header: self.initial_header.clone(),
+				initial_difficulty: self.initial_difficulty,
+			},
+			basic_inbound_channel: BasicInboundChannelConfig { source_channel: self.source_channel },
+			erc20pdex: ERC20PDEXConfig { address: self.erc20_pdex },
+			dispatch_whitelist: DispatchWhitelistConfig { whitelist: self.whitelist.clone() },
+			ethereum_header_pruning: Default::default(),
+		}
+	}
+}
+
+/// The `generate-spec` command, building a chain spec from a genesis description.
+#[derive(Debug, structopt::StructOpt)]
+pub struct GenerateSpecCmd {
+	/// Genesis description, a `.toml` or `.json` file.
+	#[structopt(parse(from_os_str))]
+	pub genesis: PathBuf,
+
+	/// Emit the raw storage of the genesis instead of the runtime genesis config.
+	#[structopt(long)]
+	pub raw: bool,
+
+	/// Write the chain spec to this file instead of standard output.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+}
+
+impl GenerateSpecCmd {
+	/// Run the generate-spec command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let description = GenesisDescription::from_file(&self.genesis)?;
+		let spec = crate::chain_spec::from_description(description)?;
+		let json = sc_service::chain_ops::build_spec(&spec, self.raw)?;
+		match &self.output {
+			Some(path) => std::fs::write(path, json)?,
+			None => println!("{}", json),
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,568 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Chain indexer running as a task of the node.
+//!
+//! The indexer walks finalized blocks, decodes their extrinsics and events with the native
+//! runtime and writes them into normalized tables of an SQLite database. Every block is
+//! written in a single transaction together with its hash, so that a restart resumes after the
+//! last indexed block and blocks that are no longer canonical, e.g. after a `revert`, are
+//! removed before indexing continues.
+//!
+//! Indexing starts at genesis and reads the state of every block, so the node has to keep the
+//! state of old blocks, e.g. with `--pruning archive`. The runtime has no order book yet, so
+//! there is no table of orders.
+
+use std::{
+	fmt,
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::Duration,
+};
+
+use codec::{Decode, Encode};
+use frame_support::dispatch::GetCallMetadata;
+use frame_system::Phase;
+use futures::StreamExt;
+use node_polkadex_runtime::{Event, Runtime, UncheckedExtrinsic};
+use polkadex_primitives::{AccountId, Block, BlockNumber, Hash};
+use rusqlite::{params, Connection, OptionalExtension};
+use sc_client_api::{BlockBackend, BlockchainEvents, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, storage::StorageKey};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{BlakeTwo256, Block as BlockT, Hash as HashT},
+	MultiAddress,
+};
+
+const LOG_TARGET: &str = "polkadex-indexer";
+
+/// Delay before indexing is retried after the first failure. It doubles with every further
+/// failure, up to `MAX_RETRY_DELAY`.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+/// Tables of the database, all keyed by block number first.
+const TABLES: &[&str] =
+	&["blocks", "extrinsics", "transfers", "mints", "vesting_claims", "staking_payouts"];
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS blocks (
+	block_number INTEGER PRIMARY KEY,
+	hash TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS extrinsics (
+	block_number INTEGER NOT NULL,
+	extrinsic_index INTEGER NOT NULL,
+	hash TEXT NOT NULL,
+	signer TEXT,
+	pallet TEXT NOT NULL,
+	call TEXT NOT NULL,
+	success INTEGER NOT NULL,
+	PRIMARY KEY (block_number, extrinsic_index)
+);
+CREATE INDEX IF NOT EXISTS extrinsics_hash ON extrinsics (hash);
+CREATE INDEX IF NOT EXISTS extrinsics_signer ON extrinsics (signer);
+CREATE TABLE IF NOT EXISTS transfers (
+	block_number INTEGER NOT NULL,
+	event_index INTEGER NOT NULL,
+	extrinsic_index INTEGER,
+	asset TEXT NOT NULL,
+	sender TEXT NOT NULL,
+	recipient TEXT NOT NULL,
+	amount TEXT NOT NULL,
+	PRIMARY KEY (block_number, event_index)
+);
+CREATE INDEX IF NOT EXISTS transfers_sender ON transfers (sender);
+CREATE INDEX IF NOT EXISTS transfers_recipient ON transfers (recipient);
+CREATE TABLE IF NOT EXISTS mints (
+	block_number INTEGER NOT NULL,
+	event_index INTEGER NOT NULL,
+	extrinsic_index INTEGER,
+	ethereum_sender TEXT NOT NULL,
+	recipient TEXT NOT NULL,
+	amount TEXT NOT NULL,
+	PRIMARY KEY (block_number, event_index)
+);
+CREATE INDEX IF NOT EXISTS mints_recipient ON mints (recipient);
+CREATE TABLE IF NOT EXISTS vesting_claims (
+	block_number INTEGER NOT NULL,
+	event_index INTEGER NOT NULL,
+	extrinsic_index INTEGER,
+	account TEXT NOT NULL,
+	amount TEXT NOT NULL,
+	PRIMARY KEY (block_number, event_index)
+);
+CREATE INDEX IF NOT EXISTS vesting_claims_account ON vesting_claims (account);
+CREATE TABLE IF NOT EXISTS staking_payouts (
+	block_number INTEGER NOT NULL,
+	event_index INTEGER NOT NULL,
+	extrinsic_index INTEGER,
+	stash TEXT NOT NULL,
+	amount TEXT NOT NULL,
+	PRIMARY KEY (block_number, event_index)
+);
+CREATE INDEX IF NOT EXISTS staking_payouts_stash ON staking_payouts (stash);
+";
+
+/// Errors raised while indexing.
+#[derive(Debug)]
+pub enum Error {
+	/// The database could not be read or written.
+	Database(rusqlite::Error),
+	/// The client could not be queried.
+	Client(sp_blockchain::Error),
+	/// A finalized block is missing from the client.
+	MissingBlock(BlockNumber),
+	/// The state of a block was pruned.
+	MissingState(BlockNumber),
+	/// Extrinsics or events could not be decoded with the native runtime.
+	Decode(codec::Error),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Database(e) => write!(f, "Database error: {}", e),
+			Error::Client(e) => write!(f, "Client error: {}", e),
+			Error::MissingBlock(n) => write!(f, "Block #{} not found", n),
+			Error::MissingState(n) =>
+				write!(f, "State of block #{} is pruned, run the node with `--pruning archive`", n),
+			Error::Decode(e) => write!(f, "Unable to decode with the native runtime: {}", e),
+		}
+	}
+}
+
+impl From<rusqlite::Error> for Error {
+	fn from(e: rusqlite::Error) -> Self {
+		Error::Database(e)
+	}
+}
+
+impl From<sp_blockchain::Error> for Error {
+	fn from(e: sp_blockchain::Error) -> Self {
+		Error::Client(e)
+	}
+}
+
+impl From<codec::Error> for Error {
+	fn from(e: codec::Error) -> Self {
+		Error::Decode(e)
+	}
+}
+
+/// A decoded extrinsic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtrinsicRow {
+	/// Index of the extrinsic in its block.
+	pub index: u32,
+	/// Hash of the encoded extrinsic.
+	pub hash: Hash,
+	/// SS58 address of the signer, if the extrinsic is signed.
+	pub signer: Option<String>,
+	/// Name of the called pallet.
+	pub pallet: String,
+	/// Name of the called function.
+	pub call: String,
+	/// Whether the call was dispatched successfully.
+	pub success: bool,
+}
+
+/// An indexed event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventRow {
+	/// Transfer of the native token or of an `orml_tokens` asset.
+	Transfer { asset: String, sender: AccountId, recipient: AccountId, amount: u128 },
+	/// PDEX minted by the ERC20 migration.
+	Mint { ethereum_sender: sp_core::H160, recipient: AccountId, amount: u128 },
+	/// Vested PDEX unlocked by `orml_vesting`.
+	VestingClaim { account: AccountId, amount: u128 },
+	/// Staking reward paid to a stash.
+	StakingPayout { stash: AccountId, amount: u128 },
+}
+
+/// Rows of a block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockRows {
+	/// Extrinsics of the block.
+	pub extrinsics: Vec<ExtrinsicRow>,
+	/// Indexed events with their index and the index of the extrinsic that emitted them.
+	pub events: Vec<(u32, Option<u32>, EventRow)>,
+}
+
+fn account(account: &AccountId) -> String {
+	account.to_ss58check()
+}
+
+fn hash(hash: &Hash) -> String {
+	format!("{:?}", hash)
+}
+
+/// Decodes the rows of a block from its extrinsics and events.
+pub fn block_rows(
+	extrinsics: &[<Block as BlockT>::Extrinsic],
+	events: &[EventRecord],
+) -> Result<BlockRows, Error> {
+	let mut rows = BlockRows::default();
+	for (index, extrinsic) in extrinsics.iter().enumerate() {
+		let index = index as u32;
+		let encoded = extrinsic.encode();
+		let xt = UncheckedExtrinsic::decode(&mut &encoded[..])?;
+		let metadata = xt.function.get_call_metadata();
+		let success = events.iter().any(|record| {
+			record.phase == Phase::ApplyExtrinsic(index) &&
+				matches!(record.event, Event::System(frame_system::Event::ExtrinsicSuccess(..)))
+		});
+		rows.extrinsics.push(ExtrinsicRow {
+			index,
+			hash: BlakeTwo256::hash(&encoded),
+			signer: xt.signature.as_ref().map(|(address, ..)| match address {
+				MultiAddress::Id(id) => account(id),
+				other => format!("{:?}", other),
+			}),
+			pallet: metadata.pallet_name.into(),
+			call: metadata.function_name.into(),
+			success,
+		});
+	}
+
+	for (event_index, record) in events.iter().enumerate() {
+		let row = match &record.event {
+			Event::Balances(pallet_balances::Event::Transfer(sender, recipient, amount)) =>
+				EventRow::Transfer {
+					asset: "PDEX".into(),
+					sender: sender.clone(),
+					recipient: recipient.clone(),
+					amount: *amount,
+				},
+			Event::Tokens(orml_tokens::Event::Transfer(asset, sender, recipient, amount)) =>
+				EventRow::Transfer {
+					asset: format!("{:?}", asset),
+					sender: sender.clone(),
+					recipient: recipient.clone(),
+					amount: *amount,
+				},
+			Event::ERC20PDEX(erc20_pdex_migration_pallet::RawEvent::NativePDEXMinted(
+				_,
+				ethereum_sender,
+				recipient,
+				amount,
+				_,
+			)) => EventRow::Mint {
+				ethereum_sender: *ethereum_sender,
+				recipient: recipient.clone(),
+				amount: amount.low_u128(),
+			},
+			Event::OrmlVesting(orml_vesting::Event::Claimed(account, amount)) =>
+				EventRow::VestingClaim { account: account.clone(), amount: *amount },
+			Event::Staking(pallet_staking::Event::Reward(stash, amount)) =>
+				EventRow::StakingPayout { stash: stash.clone(), amount: *amount },
+			_ => continue,
+		};
+		let extrinsic_index = match record.phase {
+			Phase::ApplyExtrinsic(index) => Some(index),
+			_ => None,
+		};
+		rows.events.push((event_index as u32, extrinsic_index, row));
+	}
+	Ok(rows)
+}
+
+/// The SQLite database of the indexer.
+pub struct Database {
+	connection: Connection,
+}
+
+impl Database {
+	/// Opens the database at `path`, creating the tables if needed.
+	pub fn open(path: &Path) -> Result<Self, Error> {
+		Self::new(Connection::open(path)?)
+	}
+
+	fn new(connection: Connection) -> Result<Self, Error> {
+		connection.execute_batch(SCHEMA)?;
+		Ok(Database { connection })
+	}
+
+	/// Number and hash of the last indexed block.
+	pub fn last_block(&self) -> Result<Option<(BlockNumber, String)>, Error> {
+		self.connection
+			.query_row(
+				"SELECT block_number, hash FROM blocks ORDER BY block_number DESC LIMIT 1",
+				params![],
+				|row| Ok((row.get(0)?, row.get(1)?)),
+			)
+			.optional()
+			.map_err(Into::into)
+	}
+
+	/// Removes the indexed blocks from `number` on.
+	pub fn remove_from(&mut self, number: BlockNumber) -> Result<(), Error> {
+		let transaction = self.connection.transaction()?;
+		for table in TABLES {
+			transaction.execute(
+				&format!("DELETE FROM {} WHERE block_number >= ?1", table),
+				params![number],
+			)?;
+		}
+		transaction.commit().map_err(Into::into)
+	}
+
+	/// Writes the rows of a block in a single transaction.
+	pub fn insert_block(
+		&mut self,
+		number: BlockNumber,
+		block_hash: Hash,
+		rows: &BlockRows,
+	) -> Result<(), Error> {
+		let transaction = self.connection.transaction()?;
+		transaction.execute(
+			"INSERT INTO blocks (block_number, hash) VALUES (?1, ?2)",
+			params![number, hash(&block_hash)],
+		)?;
+		for extrinsic in rows.extrinsics.iter() {
+			transaction.execute(
+				"INSERT INTO extrinsics \
+				 (block_number, extrinsic_index, hash, signer, pallet, call, success) \
+				 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+				params![
+					number,
+					extrinsic.index,
+					hash(&extrinsic.hash),
+					extrinsic.signer,
+					extrinsic.pallet,
+					extrinsic.call,
+					extrinsic.success,
+				],
+			)?;
+		}
+		for (event_index, extrinsic_index, row) in rows.events.iter() {
+			match row {
+				EventRow::Transfer { asset, sender, recipient, amount } => transaction.execute(
+					"INSERT INTO transfers \
+					 (block_number, event_index, extrinsic_index, asset, sender, recipient, \
+					 amount) \
+					 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+					params![
+						number,
+						event_index,
+						extrinsic_index,
+						asset,
+						account(sender),
+						account(recipient),
+						amount.to_string(),
+					],
+				)?,
+				EventRow::Mint { ethereum_sender, recipient, amount } => transaction.execute(
+					"INSERT INTO mints \
+					 (block_number, event_index, extrinsic_index, ethereum_sender, recipient, \
+					 amount) \
+					 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+					params![
+						number,
+						event_index,
+						extrinsic_index,
+						format!("{:?}", ethereum_sender),
+						account(recipient),
+						amount.to_string(),
+					],
+				)?,
+				EventRow::VestingClaim { account: who, amount } => transaction.execute(
+					"INSERT INTO vesting_claims \
+					 (block_number, event_index, extrinsic_index, account, amount) \
+					 VALUES (?1, ?2, ?3, ?4, ?5)",
+					params![
+						number,
+						event_index,
+						extrinsic_index,
+						account(who),
+						amount.to_string(),
+					],
+				)?,
+				EventRow::StakingPayout { stash, amount } => transaction.execute(
+					"INSERT INTO staking_payouts \
+					 (block_number, event_index, extrinsic_index, stash, amount) \
+					 VALUES (?1, ?2, ?3, ?4, ?5)",
+					params![
+						number,
+						event_index,
+						extrinsic_index,
+						account(stash),
+						amount.to_string(),
+					],
+				)?,
+			};
+		}
+		transaction.commit().map_err(Into::into)
+	}
+
+	/// Removes the indexed blocks that are no longer canonical according to `canonical_hash`.
+	pub fn rewind(
+		&mut self,
+		canonical_hash: impl Fn(BlockNumber) -> Result<Option<Hash>, Error>,
+	) -> Result<(), Error> {
+		while let Some((number, indexed)) = self.last_block()? {
+			if canonical_hash(number)?.map(|canonical| hash(&canonical)) == Some(indexed) {
+				break
+			}
+			log::info!(target: LOG_TARGET, "Block #{} is no longer canonical, removing it", number);
+			self.remove_from(number)?;
+		}
+		Ok(())
+	}
+}
+
+/// Indexes the finalized blocks that are not in the database yet.
+fn sync<C>(client: &C, database: &mut Database) -> Result<(), Error>
+where
+	C: BlockBackend<Block>
+		+ HeaderBackend<Block>
+		+ StorageProvider<Block, sc_service::TFullBackend<Block>>,
+{
+	database.rewind(|number| client.hash(number).map_err(Into::into))?;
+	let next = database.last_block()?.map(|(number, _)| number + 1).unwrap_or_default();
+	let events_key = StorageKey(frame_system::Events::<Runtime>::hashed_key().to_vec());
+
+	for number in next..=client.info().finalized_number {
+		let block_hash = client.hash(number)?.ok_or(Error::MissingBlock(number))?;
+		let id = BlockId::Hash(block_hash);
+		let block = client.block(&id)?.ok_or(Error::MissingBlock(number))?.block;
+		let events = match client.storage(&id, &events_key) {
+			Ok(Some(data)) => Vec::<EventRecord>::decode(&mut &data.0[..])?,
+			Ok(None) => Vec::new(),
+			Err(_) => return Err(Error::MissingState(number)),
+		};
+		let rows = block_rows(block.extrinsics(), &events)?;
+		database.insert_block(number, block_hash, &rows)?;
+	}
+	Ok(())
+}
+
+/// Runs the indexer until the node shuts down.
+pub async fn run<C>(client: Arc<C>, path: PathBuf)
+where
+	C: BlockBackend<Block>
+		+ HeaderBackend<Block>
+		+ BlockchainEvents<Block>
+		+ StorageProvider<Block, sc_service::TFullBackend<Block>>
+		+ Send
+		+ Sync
+		+ 'static,
+{
+	let mut database = match Database::open(&path) {
+		Ok(database) => database,
+		Err(e) => {
+			log::error!(target: LOG_TARGET, "Unable to open {}: {}", path.display(), e);
+			return
+		},
+	};
+	log::info!(target: LOG_TARGET, "Indexing finalized blocks into {}", path.display());
+
+	let mut finality = client.finality_notification_stream();
+	let mut retry_delay = RETRY_DELAY;
+	loop {
+		match sync(&*client, &mut database) {
+			Ok(()) => retry_delay = RETRY_DELAY,
+			Err(e) => {
+				log::error!(
+					target: LOG_TARGET,
+					"Indexing failed, retrying in {}s: {}",
+					retry_delay.as_secs(),
+					e
+				);
+				futures_timer::Delay::new(retry_delay).await;
+				retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+				continue
+			},
+		}
+		if finality.next().await.is_none() {
+			return
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_keyring::AccountKeyring;
+
+	fn database() -> Database {
+		Database::new(Connection::open_in_memory().unwrap()).unwrap()
+	}
+
+	fn block_hash(number: BlockNumber, fork: u8) -> Hash {
+		let mut hash = Hash::from_low_u64_be(number as u64);
+		hash.0[0] = fork;
+		hash
+	}
+
+	fn transfer(amount: u128) -> BlockRows {
+		BlockRows {
+			extrinsics: Vec::new(),
+			events: vec![(
+				0,
+				Some(1),
+				EventRow::Transfer {
+					asset: "PDEX".into(),
+					sender: AccountKeyring::Alice.to_account_id(),
+					recipient: AccountKeyring::Bob.to_account_id(),
+					amount,
+				},
+			)],
+		}
+	}
+
+	fn count(database: &Database, table: &str) -> u32 {
+		database
+			.connection
+			.query_row(&format!("SELECT COUNT(*) FROM {}", table), params![], |row| row.get(0))
+			.unwrap()
+	}
+
+	#[test]
+	fn restart_resumes_after_last_block() {
+		let mut database = database();
+		assert_eq!(database.last_block().unwrap(), None);
+		for number in 0..3 {
+			database.insert_block(number, block_hash(number, 0), &transfer(10)).unwrap();
+		}
+		database.rewind(|number| Ok(Some(block_hash(number, 0)))).unwrap();
+
+		assert_eq!(database.last_block().unwrap(), Some((2, hash(&block_hash(2, 0)))));
+		assert_eq!(count(&database, "transfers"), 3);
+	}
+
+	#[test]
+	fn blocks_no_longer_canonical_are_removed() {
+		let mut database = database();
+		for number in 0..5 {
+			database.insert_block(number, block_hash(number, 0), &transfer(10)).unwrap();
+		}
+		// The chain was reverted to block #2 and continued on another fork.
+		database
+			.rewind(|number| Ok(Some(block_hash(number, if number > 2 { 1 } else { 0 }))))
+			.unwrap();
+
+		assert_eq!(database.last_block().unwrap(), Some((2, hash(&block_hash(2, 0)))));
+		assert_eq!(count(&database, "blocks"), 3);
+		assert_eq!(count(&database, "transfers"), 3);
+	}
+}
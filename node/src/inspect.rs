@@ -0,0 +1,212 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `inspect` subcommand, decoding blocks and extrinsics of the local database with the
+//! native runtime.
+
+use std::{str::FromStr, sync::Arc};
+
+use codec::{Decode, Encode};
+use frame_system::{EventRecord, Phase};
+use node_polkadex_runtime::{Event, Runtime, UncheckedExtrinsic};
+use polkadex_primitives::{Block, BlockNumber, Hash};
+use sc_cli::{CliConfiguration, Error, ImportParams, Result, SharedParams};
+use sc_client_api::{BlockBackend, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::{bytes::from_hex, crypto::Ss58Codec, storage::StorageKey};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT},
+	MultiAddress,
+};
+use structopt::StructOpt;
+
+/// The `inspect` command used to print decoded chain data.
+#[derive(Debug, StructOpt)]
+pub struct InspectCmd {
+	#[allow(missing_docs)]
+	#[structopt(subcommand)]
+	pub command: InspectSubCmd,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+/// A possible inspect sub-command.
+#[derive(Debug, StructOpt)]
+pub enum InspectSubCmd {
+	/// Decode a block with the native runtime and print out its extrinsics and events.
+	Block {
+		/// Hash or number of the block.
+		#[structopt(value_name = "HASH or NUMBER")]
+		input: String,
+	},
+	/// Decode an extrinsic with the native runtime and print out its details.
+	///
+	/// Events are only available for extrinsics addressed by `BLOCK:INDEX`.
+	Extrinsic {
+		/// `BLOCK:INDEX`, where `BLOCK` is a block hash or number, or hex encoded extrinsic
+		/// bytes.
+		#[structopt(value_name = "BLOCK:INDEX or BYTES")]
+		input: String,
+	},
+}
+
+impl CliConfiguration for InspectCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+type Events = Vec<EventRecord<Event, Hash>>;
+
+/// Parses a block hash or number.
+pub fn parse_block_id(input: &str) -> Result<BlockId<Block>> {
+	if let Ok(number) = input.parse::<BlockNumber>() {
+		return Ok(BlockId::Number(number))
+	}
+	Hash::from_str(input.trim_start_matches("0x"))
+		.map(BlockId::Hash)
+		.map_err(|_| Error::Input(format!("Invalid block hash or number: {}", input)))
+}
+
+fn decode_extrinsic(bytes: &[u8]) -> Result<UncheckedExtrinsic> {
+	UncheckedExtrinsic::decode(&mut &bytes[..]).map_err(Into::into)
+}
+
+fn print_extrinsic(
+	index: Option<usize>,
+	extrinsic: &UncheckedExtrinsic,
+	events: Option<&Events>,
+) {
+	match index {
+		Some(index) => println!("Extrinsic #{}", index),
+		None => println!("Extrinsic"),
+	}
+	println!("  call: {:#?}", extrinsic.function);
+	match &extrinsic.signature {
+		Some((address, _signature, extra)) => {
+			match address {
+				MultiAddress::Id(account) => println!("  signer: {}", account.to_ss58check()),
+				other => println!("  signer: {:?}", other),
+			}
+			let (spec_version, tx_version, genesis, era, nonce, weight, payment, index_extrinsic) =
+				extra;
+			println!("  signed extensions:");
+			println!("    {:?}", spec_version);
+			println!("    {:?}", tx_version);
+			println!("    {:?}", genesis);
+			println!("    {:?}", era);
+			println!("    {:?}", nonce);
+			println!("    {:?}", weight);
+			println!("    {:?}", payment);
+			println!("    {:?}", index_extrinsic);
+		},
+		None => println!("  signer: none (unsigned)"),
+	}
+	if let (Some(index), Some(events)) = (index, events) {
+		println!("  events:");
+		events
+			.iter()
+			.filter(|record| record.phase == Phase::ApplyExtrinsic(index as u32))
+			.for_each(|record| println!("    {:#?}", record.event));
+	}
+}
+
+impl InspectCmd {
+	/// Run the inspect command against the client's database.
+	pub fn run<C>(&self, client: Arc<C>) -> Result<()>
+	where
+		C: BlockBackend<Block>
+			+ HeaderBackend<Block>
+			+ StorageProvider<Block, sc_service::TFullBackend<Block>>,
+	{
+		match &self.command {
+			InspectSubCmd::Block { input } => {
+				let id = parse_block_id(input)?;
+				let (block, events) = load_block(&*client, &id)?;
+				let header = block.header();
+				println!("Block #{} ({})", header.number(), header.hash());
+				println!("  parent: {}", header.parent_hash());
+				println!("  state root: {}", header.state_root());
+				println!("  extrinsics root: {}", header.extrinsics_root());
+				for (index, extrinsic) in block.extrinsics().iter().enumerate() {
+					let extrinsic = decode_extrinsic(&extrinsic.encode())?;
+					print_extrinsic(Some(index), &extrinsic, Some(&events));
+				}
+				println!("Block events:");
+				events
+					.iter()
+					.filter(|record| !matches!(record.phase, Phase::ApplyExtrinsic(_)))
+					.for_each(|record| println!("  {:?}: {:#?}", record.phase, record.event));
+			},
+			InspectSubCmd::Extrinsic { input } => match input.split_once(':') {
+				Some((block, index)) => {
+					let id = parse_block_id(block)?;
+					let index: usize = index
+						.parse()
+						.map_err(|_| Error::Input(format!("Invalid extrinsic index: {}", index)))?;
+					let (block, events) = load_block(&*client, &id)?;
+					let extrinsic = block.extrinsics().get(index).ok_or_else(|| {
+						Error::Input(format!("Block {} has no extrinsic #{}", id, index))
+					})?;
+					let extrinsic = decode_extrinsic(&extrinsic.encode())?;
+					print_extrinsic(Some(index), &extrinsic, Some(&events));
+				},
+				None => {
+					let bytes = from_hex(input)
+						.map_err(|e| Error::Input(format!("Invalid extrinsic bytes: {}", e)))?;
+					print_extrinsic(None, &decode_extrinsic(&bytes)?, None);
+				},
+			},
+		}
+		Ok(())
+	}
+}
+
+/// Loads a block and the events it emitted.
+fn load_block<C>(client: &C, id: &BlockId<Block>) -> Result<(Block, Events)>
+where
+	C: BlockBackend<Block>
+		+ HeaderBackend<Block>
+		+ StorageProvider<Block, sc_service::TFullBackend<Block>>,
+{
+	let hash = client
+		.block_hash_from_id(id)?
+		.ok_or_else(|| Error::Input(format!("Block {} not found", id)))?;
+	let block = client
+		.block(&BlockId::Hash(hash))?
+		.ok_or_else(|| Error::Input(format!("Block {} not found", id)))?
+		.block;
+
+	let key = StorageKey(frame_system::Events::<Runtime>::hashed_key().to_vec());
+	let events = match client.storage(&BlockId::Hash(hash), &key)? {
+		Some(data) => Events::decode(&mut &data.0[..])?,
+		None => Vec::new(),
+	};
+	Ok((block, events))
+}
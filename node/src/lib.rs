@@ -1,3 +1,7 @@
 pub mod chain_spec;
+pub mod eth_relayer;
+pub mod genesis;
+pub mod indexer;
+pub mod metrics;
 pub mod rpc;
 pub mod service;
@@ -2,11 +2,21 @@
 #![warn(missing_docs)]
 
 mod chain_spec;
+mod check_genesis;
+mod eth_relayer;
+mod export_balances;
+mod genesis;
+mod indexer;
+mod inspect;
+mod metrics;
+mod node_client;
 #[macro_use]
 mod service;
 mod cli;
 mod command;
 mod rpc;
+mod session_keys;
+mod validator_status;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
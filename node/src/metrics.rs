@@ -0,0 +1,338 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Polkadex specific Prometheus metrics.
+//!
+//! Gauges are updated on every new best block, counters are fed from the events of
+//! finalized blocks so that they never count the events of a retracted fork twice.
+
+use std::sync::Arc;
+
+use codec::Decode;
+use futures::{future, StreamExt};
+use node_polkadex_runtime::{constants::currency::PDEX, Event, Runtime, TreasuryPalletId};
+use polkadex_primitives::{Block, Hash};
+use polkadex_runtime_api::bridge::BridgeApi;
+use prometheus_endpoint::{
+	register, Counter, CounterVec, Gauge, Opts, PrometheusError, Registry, F64, U64,
+};
+use sc_client_api::{BlockchainEvents, StorageProvider};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, storage::StorageKey};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{AccountIdConversion, Header as HeaderT},
+};
+
+const LOG_TARGET: &str = "polkadex-metrics";
+
+type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+/// Polkadex metrics registered in the node's Prometheus registry.
+pub struct Metrics {
+	eth_best_block: Gauge<U64>,
+	eth_finalized_block: Gauge<U64>,
+	inbound_nonce: Gauge<U64>,
+	inbound_nonce_stalled_blocks: Gauge<U64>,
+	migration_minted: Counter<F64>,
+	treasury_balance: Gauge<F64>,
+	missed_heartbeats: CounterVec<U64>,
+}
+
+impl Metrics {
+	/// Registers the metrics in `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Metrics {
+			eth_best_block: register(
+				Gauge::new(
+					"polkadex_eth_light_client_best_block",
+					"Number of the best Ethereum block imported by the light client",
+				)?,
+				registry,
+			)?,
+			eth_finalized_block: register(
+				Gauge::new(
+					"polkadex_eth_light_client_finalized_block",
+					"Number of the latest Ethereum block finalized by the light client",
+				)?,
+				registry,
+			)?,
+			inbound_nonce: register(
+				Gauge::new(
+					"polkadex_inbound_channel_nonce",
+					"Nonce of the last message processed by the basic inbound channel",
+				)?,
+				registry,
+			)?,
+			inbound_nonce_stalled_blocks: register(
+				Gauge::new(
+					"polkadex_inbound_channel_nonce_stalled_blocks",
+					"Number of blocks since the basic inbound channel nonce last advanced",
+				)?,
+				registry,
+			)?,
+			migration_minted: register(
+				Counter::new(
+					"polkadex_migration_minted_pdex_total",
+					"PDEX minted by the ERC20 migration in finalized blocks",
+				)?,
+				registry,
+			)?,
+			treasury_balance: register(
+				Gauge::new("polkadex_treasury_balance_pdex", "Free balance of the treasury in PDEX")?,
+				registry,
+			)?,
+			missed_heartbeats: register(
+				CounterVec::new(
+					Opts::new(
+						"polkadex_im_online_missed_heartbeats_total",
+						"Sessions in which a validator did not send an ImOnline heartbeat",
+					),
+					&["validator"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+fn to_pdex(amount: u128) -> f64 {
+	amount as f64 / PDEX as f64
+}
+
+fn read_storage<C, T>(client: &C, at: Hash, key: Vec<u8>) -> Option<T>
+where
+	C: StorageProvider<Block, sc_service::TFullBackend<Block>>,
+	T: Decode,
+{
+	client
+		.storage(&BlockId::hash(at), &StorageKey(key))
+		.ok()
+		.flatten()
+		.and_then(|data| T::decode(&mut &data.0[..]).ok())
+}
+
+/// Last inbound nonce and the block it was first seen at.
+#[derive(Default)]
+struct InboundNonce {
+	nonce: u64,
+	since: u32,
+}
+
+impl InboundNonce {
+	/// Records the nonce seen at block `number` and returns the number of blocks it has not
+	/// advanced for.
+	fn observe(&mut self, nonce: u64, number: u32) -> u32 {
+		if nonce != self.nonce {
+			*self = InboundNonce { nonce, since: number };
+		}
+		number.saturating_sub(self.since)
+	}
+}
+
+/// Updates the gauges from the state of the best block `at`.
+fn update_gauges<C>(
+	client: &C,
+	metrics: &Metrics,
+	at: Hash,
+	number: u32,
+	inbound_nonce: &mut InboundNonce,
+) where
+	C: ProvideRuntimeApi<Block> + StorageProvider<Block, sc_service::TFullBackend<Block>>,
+	C::Api: BridgeApi<Block>,
+{
+	let api = client.runtime_api();
+	let block_id = BlockId::hash(at);
+	if let Ok(Some(best)) = api.best_block(&block_id) {
+		metrics.eth_best_block.set(best.number);
+	}
+	if let Ok(Some(finalized)) = api.finalized_block(&block_id) {
+		metrics.eth_finalized_block.set(finalized.number);
+	}
+	if let Ok(Some(nonces)) = api.inbound_nonces(&block_id) {
+		let nonce = nonces.iter().map(|(_, nonce)| *nonce).max().unwrap_or_default();
+		let stalled_blocks = inbound_nonce.observe(nonce, number);
+		metrics.inbound_nonce.set(nonce);
+		metrics.inbound_nonce_stalled_blocks.set(stalled_blocks as u64);
+	}
+
+	let treasury = TreasuryPalletId::get().into_account();
+	let key = frame_system::Account::<Runtime>::hashed_key_for(&treasury);
+	if let Some(info) = read_storage::<_, frame_system::AccountInfo<
+		polkadex_primitives::Index,
+		<Runtime as frame_system::Config>::AccountData,
+	>>(client, at, key)
+	{
+		metrics.treasury_balance.set(to_pdex(info.data.free));
+	}
+}
+
+/// Feeds the counters from the events of the finalized block `at`.
+fn update_counters<C>(client: &C, metrics: &Metrics, at: Hash)
+where
+	C: StorageProvider<Block, sc_service::TFullBackend<Block>>,
+{
+	let key = frame_system::Events::<Runtime>::hashed_key().to_vec();
+	let events = read_storage::<_, Vec<EventRecord>>(client, at, key).unwrap_or_default();
+	count_events(metrics, events);
+}
+
+/// Feeds the counters from the events of a finalized block.
+fn count_events(metrics: &Metrics, events: Vec<EventRecord>) {
+	for record in events {
+		match record.event {
+			Event::ERC20PDEX(erc20_pdex_migration_pallet::RawEvent::NativePDEXMinted(
+				_,
+				_,
+				_,
+				amount,
+				_,
+			)) => metrics.migration_minted.inc_by(to_pdex(amount.low_u128())),
+			Event::ImOnline(pallet_im_online::Event::SomeOffline(offline)) =>
+				for (validator, _) in offline {
+					metrics
+						.missed_heartbeats
+						.with_label_values(&[&validator.to_ss58check()])
+						.inc();
+				},
+			_ => {},
+		}
+	}
+}
+
+/// Runs the metrics worker until the node shuts down.
+pub async fn run<C>(client: Arc<C>, registry: Registry)
+where
+	C: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ BlockchainEvents<Block>
+		+ StorageProvider<Block, sc_service::TFullBackend<Block>>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: BridgeApi<Block>,
+{
+	let metrics = match Metrics::register(&registry) {
+		Ok(metrics) => metrics,
+		Err(e) => {
+			log::error!(target: LOG_TARGET, "Failed to register metrics: {:?}", e);
+			return
+		},
+	};
+
+	let mut inbound_nonce = InboundNonce::default();
+	let mut last_finalized = client.info().finalized_number;
+
+	let imports = client
+		.import_notification_stream()
+		.filter(|n| future::ready(n.is_new_best))
+		.map(|n| (true, n.hash, *n.header.number()));
+	let finality = client
+		.finality_notification_stream()
+		.map(|n| (false, n.hash, *n.header.number()));
+
+	let mut notifications = futures::stream::select(imports, finality);
+	while let Some((is_import, hash, number)) = notifications.next().await {
+		if is_import {
+			update_gauges(&*client, &metrics, hash, number, &mut inbound_nonce);
+			continue
+		}
+		// Finality notifications may cover several blocks at once.
+		for n in last_finalized + 1..=number {
+			match client.hash(n) {
+				Ok(Some(hash)) => update_counters(&*client, &metrics, hash),
+				_ => log::debug!(target: LOG_TARGET, "Finalized block #{} not found", n),
+			}
+		}
+		last_finalized = last_finalized.max(number);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{H160, U256};
+	use sp_keyring::AccountKeyring;
+
+	fn record(event: Event) -> EventRecord {
+		EventRecord { phase: frame_system::Phase::Finalization, event, topics: vec![] }
+	}
+
+	#[test]
+	fn registers_the_polkadex_metrics() {
+		let registry = Registry::new();
+		let metrics = Metrics::register(&registry).unwrap();
+		metrics.missed_heartbeats.with_label_values(&["validator"]).inc();
+
+		let mut names: Vec<_> =
+			registry.gather().iter().map(|family| family.get_name().to_owned()).collect();
+		names.sort();
+		assert_eq!(
+			names,
+			vec![
+				"polkadex_eth_light_client_best_block",
+				"polkadex_eth_light_client_finalized_block",
+				"polkadex_im_online_missed_heartbeats_total",
+				"polkadex_inbound_channel_nonce",
+				"polkadex_inbound_channel_nonce_stalled_blocks",
+				"polkadex_migration_minted_pdex_total",
+				"polkadex_treasury_balance_pdex",
+			]
+		);
+		// Registering twice in the same registry fails.
+		assert!(Metrics::register(&registry).is_err());
+	}
+
+	#[test]
+	fn counts_blocks_since_the_inbound_nonce_advanced() {
+		let mut inbound_nonce = InboundNonce::default();
+		assert_eq!(inbound_nonce.observe(0, 5), 5);
+		assert_eq!(inbound_nonce.observe(1, 6), 0);
+		assert_eq!(inbound_nonce.observe(1, 9), 3);
+		assert_eq!(inbound_nonce.observe(2, 10), 0);
+		// A retracted best block does not underflow.
+		assert_eq!(inbound_nonce.observe(2, 8), 0);
+	}
+
+	#[test]
+	fn counts_migration_mints_and_missed_heartbeats() {
+		let registry = Registry::new();
+		let metrics = Metrics::register(&registry).unwrap();
+		let alice = AccountKeyring::Alice.to_account_id();
+		let mint = |amount: u128| {
+			record(Event::ERC20PDEX(erc20_pdex_migration_pallet::RawEvent::NativePDEXMinted(
+				H160::zero(),
+				H160::zero(),
+				alice.clone(),
+				U256::from(amount),
+				0,
+			)))
+		};
+		let offline = record(Event::ImOnline(pallet_im_online::Event::SomeOffline(vec![(
+			alice.clone(),
+			Default::default(),
+		)])));
+
+		count_events(&metrics, vec![mint(3 * PDEX), offline.clone()]);
+		count_events(&metrics, vec![mint(PDEX / 2), offline]);
+
+		assert!((metrics.migration_minted.get() - 3.5).abs() < f64::EPSILON);
+		assert_eq!(metrics.missed_heartbeats.with_label_values(&[&alice.to_ss58check()]).get(), 2);
+	}
+}
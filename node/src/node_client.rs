@@ -0,0 +1,173 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Client of the JSON-RPC API of a running node, for subcommands inspecting its chain and
+//! keystore without opening its database.
+
+use std::future::Future;
+
+use codec::Decode;
+use jsonrpc_core::{Params, Value};
+use jsonrpc_core_client::{transports::http, RawClient};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sp_core::{
+	hashing::{twox_128, twox_64},
+	Bytes,
+};
+
+/// Storage key of the storage value `item` of `pallet`, or the prefix of the map `item`.
+pub fn storage_key(pallet: &str, item: &str) -> Vec<u8> {
+	[twox_128(pallet.as_bytes()), twox_128(item.as_bytes())].concat()
+}
+
+/// Key `key` hashed with `Twox64Concat`.
+pub fn twox_64_concat(key: &[u8]) -> Vec<u8> {
+	[&twox_64(key)[..], key].concat()
+}
+
+/// Runs `future` to completion on a new runtime.
+pub fn block_on<F: Future>(future: F) -> sc_cli::Result<F::Output> {
+	let runtime = tokio::runtime::Builder::new_current_thread()
+		.enable_all()
+		.build()
+		.map_err(|e| format!("Unable to start the RPC client: {}", e))?;
+	Ok(runtime.block_on(future))
+}
+
+/// Thin wrapper over a JSON-RPC connection to a node.
+pub struct NodeClient {
+	url: String,
+	client: RawClient,
+}
+
+impl NodeClient {
+	/// Connects to the HTTP JSON-RPC endpoint at `url`.
+	pub async fn connect(url: &str) -> sc_cli::Result<Self> {
+		let client = http::connect::<RawClient>(url)
+			.await
+			.map_err(|e| format!("Unable to connect to {}: {}", url, e))?;
+		Ok(NodeClient { url: url.into(), client })
+	}
+
+	/// Calls `method` with `params`.
+	pub async fn call<T: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: Vec<Value>,
+	) -> sc_cli::Result<T> {
+		let value = self
+			.client
+			.call_method(method, Params::Array(params))
+			.await
+			.map_err(|e| format!("{} failed on {}: {}", method, self.url, e))?;
+		serde_json::from_value(value)
+			.map_err(|e| format!("Invalid {} response: {}", method, e).into())
+	}
+
+	/// Value at `key` in the storage of the best block.
+	pub async fn storage<T: Decode>(&self, key: &[u8]) -> sc_cli::Result<Option<T>> {
+		let value: Option<Bytes> =
+			self.call("state_getStorage", vec![json!(Bytes(key.to_vec()))]).await?;
+		value
+			.map(|value| T::decode(&mut &value[..]))
+			.transpose()
+			.map_err(|e| format!("Unable to decode the storage at {}: {}", hex(key), e).into())
+	}
+
+	/// Keys and values of the storage of the best block starting with `prefix`.
+	pub async fn storage_pairs<T: Decode>(
+		&self,
+		prefix: &[u8],
+	) -> sc_cli::Result<Vec<(Vec<u8>, T)>> {
+		let pairs: Vec<(Bytes, Bytes)> =
+			self.call("state_getPairs", vec![json!(Bytes(prefix.to_vec()))]).await?;
+		pairs
+			.into_iter()
+			.map(|(key, value)| match T::decode(&mut &value[..]) {
+				Ok(value) => Ok((key.0, value)),
+				Err(e) =>
+					Err(format!("Unable to decode the storage at {}: {}", hex(&key), e).into()),
+			})
+			.collect()
+	}
+}
+
+/// Hex representation of `bytes`, prefixed with `0x`.
+pub fn hex(bytes: &[u8]) -> String {
+	format!("0x{}", sp_core::hexdisplay::HexDisplay::from(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use jsonrpc_core::IoHandler;
+	use jsonrpc_http_server::{Server, ServerBuilder};
+
+	fn serve() -> Server {
+		let mut io = IoHandler::new();
+		io.add_sync_method("state_getStorage", |params: Params| {
+			let (key,): (Bytes,) = params.parse()?;
+			Ok(match &key[..] {
+				b"value" => json!(Bytes(42u32.encode())),
+				b"invalid" => json!(Bytes(vec![1])),
+				_ => Value::Null,
+			})
+		});
+		io.add_sync_method("state_getPairs", |params: Params| {
+			let (prefix,): (Bytes,) = params.parse()?;
+			let pairs: Vec<_> = (1u32..=2)
+				.map(|n| (Bytes([&prefix[..], &n.encode()].concat()), Bytes(n.encode())))
+				.collect();
+			Ok(json!(pairs))
+		});
+		ServerBuilder::new(io).start_http(&"127.0.0.1:0".parse().unwrap()).unwrap()
+	}
+
+	#[test]
+	fn storage_keys_are_hashed_like_frame() {
+		assert_eq!(
+			hex(&storage_key("System", "Account")),
+			"0x26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9"
+		);
+		let key = twox_64_concat(&7u32.encode());
+		assert_eq!(&key[..8], &twox_64(&7u32.encode())[..]);
+		assert_eq!(&key[8..], &7u32.encode()[..]);
+	}
+
+	#[test]
+	fn storage_is_decoded() {
+		let server = serve();
+		let url = format!("http://{}", server.address());
+		block_on(async {
+			let client = NodeClient::connect(&url).await.unwrap();
+			assert_eq!(client.storage::<u32>(b"value").await.unwrap(), Some(42));
+			assert_eq!(client.storage::<u32>(b"missing").await.unwrap(), None);
+			assert!(client.storage::<u32>(b"invalid").await.is_err());
+			assert_eq!(
+				client.storage_pairs::<u32>(b"map").await.unwrap(),
+				vec![
+					([&b"map"[..], &1u32.encode()].concat(), 1),
+					([&b"map"[..], &2u32.encode()].concat(), 2)
+				]
+			);
+		})
+		.unwrap();
+	}
+}
@@ -32,14 +32,21 @@
 
 use std::sync::Arc;
 
+use futures::channel::mpsc;
 use grandpa::{
 	FinalityProofProvider, GrandpaJustificationStream, SharedAuthoritySet, SharedVoterState,
 };
-use polkadex_primitives::{AccountId, Balance, Block, BlockNumber, Hash, Index};
-use sc_client_api::AuxStore;
+use polkadex_primitives::{
+	assets::AssetId, AccountId, Balance, Block, BlockNumber, Hash, Index, Moment,
+};
+use sc_client_api::{AuxStore, BlockBackend, BlockchainEvents, StorageProvider};
 use sc_consensus_babe::{Config, Epoch};
 use sc_consensus_babe_rpc::BabeRpcHandler;
 use sc_consensus_epochs::SharedEpochChanges;
+use sc_consensus_manual_seal::{
+	rpc::{ManualSeal, ManualSealApi},
+	EngineCommand,
+};
 use sc_finality_grandpa_rpc::GrandpaRpcHandler;
 use sc_rpc::SubscriptionTaskExecutor;
 pub use sc_rpc_api::DenyUnsafe;
@@ -51,6 +58,14 @@ use sp_consensus::SelectChain;
 use sp_consensus_babe::BabeApi;
 use sp_keystore::SyncCryptoStorePtr;
 
+pub mod account_events;
+pub mod assets;
+pub mod bridge;
+pub mod extrinsics;
+pub mod fee_tiers;
+pub mod staking;
+pub mod vesting;
+
 /// Light client extra dependencies.
 pub struct LightDeps<C, F, P> {
 	/// The client instance to use.
@@ -91,6 +106,10 @@ pub struct GrandpaDeps<B> {
 pub struct FullDeps<C, P, SC, B> {
 	/// The client instance to use.
 	pub client: Arc<C>,
+	/// The backend, to read the offchain database.
+	pub backend: Arc<B>,
+	/// Whether the runtime writes to the offchain database through offchain indexing.
+	pub offchain_indexing: bool,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
 	/// The SelectChain Strategy
@@ -103,6 +122,8 @@ pub struct FullDeps<C, P, SC, B> {
 	pub babe: BabeDeps,
 	/// GRANDPA specific dependencies.
 	pub grandpa: GrandpaDeps<B>,
+	/// Channel to the manual seal task of a development node, if blocks are sealed manually.
+	pub manual_seal: Option<mpsc::Sender<EngineCommand<Hash>>>,
 }
 
 /// A IO handler that uses all Full RPC extensions.
@@ -117,6 +138,9 @@ where
 		+ HeaderBackend<Block>
 		+ AuxStore
 		+ HeaderMetadata<Block, Error = BlockChainError>
+		+ BlockchainEvents<Block>
+		+ BlockBackend<Block>
+		+ StorageProvider<Block, B>
 		+ Sync
 		+ Send
 		+ 'static,
@@ -125,6 +149,11 @@ where
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BabeApi<Block>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: bridge::BridgeRuntimeApi<Block>,
+	C::Api: assets::AssetsRuntimeApi<Block, AccountId, AssetId, Balance>,
+	C::Api: staking::StakingRewardsRuntimeApi<Block, AccountId, Balance>,
+	C::Api: vesting::VestingRuntimeApi<Block, AccountId, BlockNumber, Balance, Moment>,
+	C::Api: fee_tiers::FeeTiersRuntimeApi<Block, AccountId, Balance>,
 	P: TransactionPool + 'static,
 	SC: SelectChain<Block> + 'static,
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
@@ -133,9 +162,27 @@ where
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	// use pallet_mmr_rpc::{MmrApi, Mmr};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use account_events::{AccountEventsApi, AccountEventsHandler};
+	use assets::{Assets, AssetsApi};
+	use bridge::{Bridge, BridgeApi};
+	use extrinsics::{Extrinsics, ExtrinsicsApi};
+	use fee_tiers::{FeeTiers, FeeTiersApi};
+	use staking::{StakingRewards, StakingRewardsApi};
+	use vesting::{Vesting, VestingApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
-	let FullDeps { client, pool, select_chain, chain_spec, deny_unsafe, babe, grandpa } = deps;
+	let FullDeps {
+		client,
+		backend,
+		offchain_indexing,
+		pool,
+		select_chain,
+		chain_spec,
+		deny_unsafe,
+		babe,
+		grandpa,
+		manual_seal,
+	} = deps;
 
 	let BabeDeps { keystore, babe_config, shared_epoch_changes } = babe;
 	let GrandpaDeps {
@@ -152,6 +199,20 @@ where
 	// These RPCs should use an asynchronous caller instead.
 
 	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
+	io.extend_with(BridgeApi::to_delegate(Bridge::new(client.clone())));
+	io.extend_with(AssetsApi::to_delegate(Assets::new(client.clone())));
+	io.extend_with(VestingApi::to_delegate(Vesting::new(client.clone())));
+	io.extend_with(StakingRewardsApi::to_delegate(StakingRewards::new(client.clone())));
+	io.extend_with(FeeTiersApi::to_delegate(FeeTiers::new(client.clone())));
+	io.extend_with(ExtrinsicsApi::to_delegate(Extrinsics::new(
+		client.clone(),
+		backend,
+		offchain_indexing,
+	)));
+	io.extend_with(AccountEventsApi::to_delegate(AccountEventsHandler::new(
+		client.clone(),
+		subscription_executor.clone(),
+	)));
 	io.extend_with(sc_consensus_babe_rpc::BabeApi::to_delegate(BabeRpcHandler::new(
 		client.clone(),
 		shared_epoch_changes.clone(),
@@ -168,6 +229,10 @@ where
 		finality_provider,
 	)));
 
+	if let Some(command_sink) = manual_seal {
+		io.extend_with(ManualSealApi::to_delegate(ManualSeal::new(command_sink)));
+	}
+
 	io.extend_with(sc_sync_state_rpc::SyncStateRpcApi::to_delegate(
 		sc_sync_state_rpc::SyncStateRpcHandler::new(
 			chain_spec,
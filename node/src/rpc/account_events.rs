@@ -0,0 +1,605 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC subscription pushing the decoded events that involve a single account.
+//!
+//! Events are read from `System::Events` of every new best block, or of every finalized block
+//! when the subscriber asks for finalized events only, and decoded with the native runtime.
+//! When the best chain is reorganized, the events of the retracted blocks are notified again
+//! with `retracted` set before the events of the enacted blocks.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Decode;
+use futures::{future, stream, FutureExt, SinkExt, StreamExt};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use log::warn;
+use node_polkadex_runtime::{Event, Runtime};
+use polkadex_primitives::{AccountId, Balance, Block, BlockNumber, Hash};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
+use sp_blockchain::{HeaderBackend, HeaderMetadata};
+use sp_core::{storage::StorageKey, H160};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+
+type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+/// Kind of events a subscriber can ask for.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EventCategory {
+	/// Changes of the native PDEX balance.
+	Balances,
+	/// PDEX minted by the ERC20 migration.
+	Migration,
+	/// Vesting schedules and unlocks.
+	Vesting,
+	/// Staking bonds, payouts and slashes.
+	Staking,
+}
+
+/// Filters of an account event subscription.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EventFilters {
+	/// Categories to notify, all of them if empty.
+	pub categories: Vec<EventCategory>,
+	/// Notify the events of finalized blocks instead of new best blocks.
+	pub finalized: bool,
+}
+
+impl EventFilters {
+	fn accepts(&self, category: EventCategory) -> bool {
+		self.categories.is_empty() || self.categories.contains(&category)
+	}
+}
+
+/// Decoded event involving the subscribed account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum AccountEventKind {
+	/// Native PDEX moved between two accounts.
+	#[serde(rename_all = "camelCase")]
+	Transfer { from: AccountId, to: AccountId, amount: NumberOrHex },
+	/// The account was created with the given balance.
+	Endowed { amount: NumberOrHex },
+	/// Native PDEX was deposited on the account.
+	Deposit { amount: NumberOrHex },
+	/// Native PDEX was reserved.
+	Reserved { amount: NumberOrHex },
+	/// Native PDEX was unreserved.
+	Unreserved { amount: NumberOrHex },
+	/// The account was reaped and its remaining balance lost.
+	DustLost { amount: NumberOrHex },
+	/// PDEX minted for tokens burnt on Ethereum by the ERC20 migration.
+	#[serde(rename_all = "camelCase")]
+	MigrationMint { ethereum_sender: H160, amount: NumberOrHex },
+	/// A vesting schedule was added to the account.
+	#[serde(rename_all = "camelCase")]
+	VestingScheduleAdded {
+		from: AccountId,
+		start: BlockNumber,
+		period: BlockNumber,
+		period_count: u32,
+		per_period: NumberOrHex,
+	},
+	/// Vested PDEX was unlocked.
+	VestingClaimed { amount: NumberOrHex },
+	/// PDEX was bonded.
+	Bonded { amount: NumberOrHex },
+	/// PDEX was unbonded.
+	Unbonded { amount: NumberOrHex },
+	/// Unbonded PDEX was withdrawn.
+	Withdrawn { amount: NumberOrHex },
+	/// A staking reward was paid out.
+	StakingReward { amount: NumberOrHex },
+	/// The stake was slashed.
+	StakingSlash { amount: NumberOrHex },
+}
+
+/// Event of a block involving the subscribed account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountEvent {
+	/// Index of the extrinsic that emitted the event, if any.
+	pub extrinsic_index: Option<u32>,
+	/// The decoded event.
+	pub event: AccountEventKind,
+}
+
+/// Events of a block involving the subscribed account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountEvents {
+	/// Hash of the block.
+	pub block_hash: Hash,
+	/// Number of the block.
+	pub block_number: BlockNumber,
+	/// Whether the block is finalized.
+	pub finalized: bool,
+	/// Whether the block left the best chain, so its events must be rolled back.
+	pub retracted: bool,
+	/// Events of the block involving the account, in emission order.
+	pub events: Vec<AccountEvent>,
+}
+
+fn amount(balance: Balance) -> NumberOrHex {
+	balance.into()
+}
+
+/// Returns the category and decoded form of `event` if it involves `account`.
+pub fn account_event(
+	event: &Event,
+	account: &AccountId,
+) -> Option<(EventCategory, AccountEventKind)> {
+	use AccountEventKind::*;
+	use EventCategory::*;
+
+	let is = |who: &AccountId| who == account;
+	match event {
+		Event::Balances(event) => {
+			let kind = match event {
+				pallet_balances::Event::Transfer(from, to, value) if is(from) || is(to) =>
+					Transfer { from: from.clone(), to: to.clone(), amount: amount(*value) },
+				pallet_balances::Event::Endowed(who, value) if is(who) =>
+					Endowed { amount: amount(*value) },
+				pallet_balances::Event::Deposit(who, value) if is(who) =>
+					Deposit { amount: amount(*value) },
+				pallet_balances::Event::Reserved(who, value) if is(who) =>
+					Reserved { amount: amount(*value) },
+				pallet_balances::Event::Unreserved(who, value) if is(who) =>
+					Unreserved { amount: amount(*value) },
+				pallet_balances::Event::DustLost(who, value) if is(who) =>
+					DustLost { amount: amount(*value) },
+				_ => return None,
+			};
+			Some((Balances, kind))
+		},
+		Event::ERC20PDEX(erc20_pdex_migration_pallet::RawEvent::NativePDEXMinted(
+			_,
+			sender,
+			recipient,
+			value,
+			_,
+		)) if is(recipient) => Some((
+			Migration,
+			MigrationMint { ethereum_sender: *sender, amount: amount(value.low_u128()) },
+		)),
+		Event::OrmlVesting(event) => {
+			let kind = match event {
+				orml_vesting::Event::VestingScheduleAdded(from, to, schedule) if is(to) =>
+					VestingScheduleAdded {
+						from: from.clone(),
+						start: schedule.start,
+						period: schedule.period,
+						period_count: schedule.period_count,
+						per_period: amount(schedule.per_period),
+					},
+				orml_vesting::Event::Claimed(who, value) if is(who) =>
+					VestingClaimed { amount: amount(*value) },
+				_ => return None,
+			};
+			Some((Vesting, kind))
+		},
+		Event::Staking(event) => {
+			let kind = match event {
+				pallet_staking::Event::Bonded(who, value) if is(who) =>
+					Bonded { amount: amount(*value) },
+				pallet_staking::Event::Unbonded(who, value) if is(who) =>
+					Unbonded { amount: amount(*value) },
+				pallet_staking::Event::Withdrawn(who, value) if is(who) =>
+					Withdrawn { amount: amount(*value) },
+				pallet_staking::Event::Reward(who, value) if is(who) =>
+					StakingReward { amount: amount(*value) },
+				pallet_staking::Event::Slash(who, value) if is(who) =>
+					StakingSlash { amount: amount(*value) },
+				_ => return None,
+			};
+			Some((Staking, kind))
+		},
+		_ => None,
+	}
+}
+
+/// Account events RPC methods.
+#[rpc]
+pub trait AccountEventsApi {
+	/// RPC metadata
+	type Metadata;
+
+	/// Subscribes to the events involving `account`, optionally restricted by `filters`.
+	#[pubsub(subscription = "account_events", subscribe, name = "account_subscribeEvents")]
+	fn subscribe_account_events(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<AccountEvents>,
+		account: AccountId,
+		filters: Option<EventFilters>,
+	);
+
+	/// Unsubscribes from account events.
+	#[pubsub(subscription = "account_events", unsubscribe, name = "account_unsubscribeEvents")]
+	fn unsubscribe_account_events(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool>;
+}
+
+/// Implements the [`AccountEventsApi`] RPC trait on top of the client's block notifications.
+pub struct AccountEventsHandler<C, B> {
+	client: Arc<C>,
+	manager: SubscriptionManager,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> AccountEventsHandler<C, B> {
+	/// Creates a new instance of the account events RPC handler.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+		AccountEventsHandler {
+			client,
+			manager: SubscriptionManager::new(Arc::new(executor)),
+			_marker: Default::default(),
+		}
+	}
+}
+
+/// Block whose events are notified.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct NotifiedBlock {
+	hash: Hash,
+	number: BlockNumber,
+	retracted: bool,
+}
+
+/// Returns the blocks leaving and joining the best chain when the best block moves from `from`
+/// to `to`: the retracted blocks from the newest, then the enacted blocks up to `to`.
+fn best_chain_route<C>(client: &C, from: Hash, to: Hash, number: BlockNumber) -> Vec<NotifiedBlock>
+where
+	C: HeaderMetadata<Block>,
+	C::Error: std::fmt::Debug,
+{
+	match sp_blockchain::tree_route(client, from, to) {
+		Ok(route) => route
+			.retracted()
+			.iter()
+			.map(|block| NotifiedBlock { hash: block.hash, number: block.number, retracted: true })
+			.chain(route.enacted().iter().map(|block| NotifiedBlock {
+				hash: block.hash,
+				number: block.number,
+				retracted: false,
+			}))
+			.collect(),
+		Err(e) => {
+			warn!("Unable to compute the tree route from {:?} to {:?}: {:?}", from, to, e);
+			vec![NotifiedBlock { hash: to, number, retracted: false }]
+		},
+	}
+}
+
+/// Returns the events of `records` involving `account` and accepted by `filters`.
+fn record_events(
+	records: &[EventRecord],
+	account: &AccountId,
+	filters: &EventFilters,
+) -> Vec<AccountEvent> {
+	records
+		.iter()
+		.filter_map(|record| {
+			let (category, event) = account_event(&record.event, account)?;
+			let extrinsic_index = match record.phase {
+				frame_system::Phase::ApplyExtrinsic(index) => Some(index),
+				_ => None,
+			};
+			filters.accepts(category).then(|| AccountEvent { extrinsic_index, event })
+		})
+		.collect()
+}
+
+/// Reads the events of `block` involving `account`.
+fn block_events<C, B>(
+	client: &C,
+	block: NotifiedBlock,
+	finalized: bool,
+	account: &AccountId,
+	filters: &EventFilters,
+) -> Option<AccountEvents>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	let key = StorageKey(frame_system::Events::<Runtime>::hashed_key().to_vec());
+	let data = client.storage(&BlockId::Hash(block.hash), &key).ok().flatten()?;
+	let records = Vec::<EventRecord>::decode(&mut &data.0[..]).ok()?;
+	let events = record_events(&records, account, filters);
+	(!events.is_empty()).then(|| AccountEvents {
+		block_hash: block.hash,
+		block_number: block.number,
+		finalized,
+		retracted: block.retracted,
+		events,
+	})
+}
+
+impl<C, B> AccountEventsApi for AccountEventsHandler<C, B>
+where
+	C: BlockchainEvents<Block>
+		+ HeaderBackend<Block>
+		+ HeaderMetadata<Block, Error = sp_blockchain::Error>
+		+ StorageProvider<Block, B>
+		+ Send
+		+ Sync
+		+ 'static,
+	B: Backend<Block> + Send + Sync + 'static,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn subscribe_account_events(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<AccountEvents>,
+		account: AccountId,
+		filters: Option<EventFilters>,
+	) {
+		let filters = filters.unwrap_or_default();
+		let client = self.client.clone();
+
+		// Finality notifications may skip blocks, so every block since the last notified one
+		// is read. Import notifications may skip blocks or switch forks, so the best chain is
+		// walked from the last notified best block.
+		let blocks = if filters.finalized {
+			let mut last_finalized = client.info().finalized_number;
+			let client = client.clone();
+			client
+				.finality_notification_stream()
+				.flat_map(move |notification| {
+					let number = *notification.header.number();
+					let blocks: Vec<_> = (last_finalized + 1..number)
+						.filter_map(|n| client.hash(n).ok().flatten().map(|hash| (hash, n)))
+						.chain(std::iter::once((notification.hash, number)))
+						.map(|(hash, number)| NotifiedBlock { hash, number, retracted: false })
+						.collect();
+					last_finalized = number;
+					stream::iter(blocks)
+				})
+				.boxed()
+		} else {
+			let mut last_best = client.info().best_hash;
+			let client = client.clone();
+			client
+				.import_notification_stream()
+				.filter(|notification| future::ready(notification.is_new_best))
+				.flat_map(move |notification| {
+					let number = *notification.header.number();
+					let blocks = best_chain_route(&*client, last_best, notification.hash, number);
+					last_best = notification.hash;
+					stream::iter(blocks)
+				})
+				.boxed()
+		};
+
+		let finalized = filters.finalized;
+		let stream = blocks.filter_map(move |block| {
+			future::ready(block_events(&*client, block, finalized, &account, &filters))
+		});
+
+		self.manager.add(subscriber, |sink| {
+			stream
+				.map(|events| Ok(Ok::<_, jsonrpc_core::Error>(events)))
+				.forward(sink.sink_map_err(|e| warn!("Error sending account events: {:?}", e)))
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_account_events(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool> {
+		Ok(self.manager.cancel(id))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_blockchain::CachedHeaderMetadata;
+	use sp_core::U256;
+	use sp_keyring::AccountKeyring;
+	use sp_runtime::traits::Block as BlockT;
+	use std::collections::HashMap;
+
+	type Header = <Block as BlockT>::Header;
+
+	struct Headers(HashMap<Hash, Header>);
+
+	impl Headers {
+		fn new(headers: &[&Header]) -> Self {
+			Headers(headers.iter().map(|header| (header.hash(), (*header).clone())).collect())
+		}
+	}
+
+	impl HeaderMetadata<Block> for Headers {
+		type Error = sp_blockchain::Error;
+
+		fn header_metadata(&self, hash: Hash) -> Result<CachedHeaderMetadata<Block>, Self::Error> {
+			self.0
+				.get(&hash)
+				.map(CachedHeaderMetadata::from)
+				.ok_or_else(|| sp_blockchain::Error::UnknownBlock(format!("{:?}", hash)))
+		}
+
+		fn insert_header_metadata(&self, _: Hash, _: CachedHeaderMetadata<Block>) {}
+
+		fn remove_header_metadata(&self, _: Hash) {}
+	}
+
+	fn header(parent: &Header, fork: u8) -> Header {
+		Header::new(
+			parent.number() + 1,
+			Default::default(),
+			Hash::repeat_byte(fork),
+			parent.hash(),
+			Default::default(),
+		)
+	}
+
+	fn record(phase: frame_system::Phase, event: Event) -> EventRecord {
+		EventRecord { phase, event, topics: vec![] }
+	}
+
+	#[test]
+	fn transfers_involve_sender_and_recipient() {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let bob = AccountKeyring::Bob.to_account_id();
+		let charlie = AccountKeyring::Charlie.to_account_id();
+		let event =
+			Event::Balances(pallet_balances::Event::Transfer(alice.clone(), bob.clone(), 10));
+
+		let expected = (
+			EventCategory::Balances,
+			AccountEventKind::Transfer { from: alice.clone(), to: bob.clone(), amount: amount(10) },
+		);
+		assert_eq!(account_event(&event, &alice), Some(expected.clone()));
+		assert_eq!(account_event(&event, &bob), Some(expected));
+		assert_eq!(account_event(&event, &charlie), None);
+	}
+
+	#[test]
+	fn migration_mints_involve_the_recipient() {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let sender = H160::repeat_byte(1);
+		let event = Event::ERC20PDEX(erc20_pdex_migration_pallet::RawEvent::NativePDEXMinted(
+			H160::repeat_byte(2),
+			sender,
+			alice.clone(),
+			U256::from(7),
+			0,
+		));
+
+		assert_eq!(
+			account_event(&event, &alice),
+			Some((
+				EventCategory::Migration,
+				AccountEventKind::MigrationMint { ethereum_sender: sender, amount: amount(7) },
+			))
+		);
+		assert_eq!(account_event(&event, &AccountKeyring::Bob.to_account_id()), None);
+	}
+
+	#[test]
+	fn vesting_and_staking_events_are_categorized() {
+		let alice = AccountKeyring::Alice.to_account_id();
+
+		assert_eq!(
+			account_event(
+				&Event::OrmlVesting(orml_vesting::Event::Claimed(alice.clone(), 3)),
+				&alice
+			),
+			Some((EventCategory::Vesting, AccountEventKind::VestingClaimed { amount: amount(3) }))
+		);
+		assert_eq!(
+			account_event(&Event::Staking(pallet_staking::Event::Reward(alice.clone(), 5)), &alice),
+			Some((EventCategory::Staking, AccountEventKind::StakingReward { amount: amount(5) }))
+		);
+		assert_eq!(
+			account_event(&Event::Staking(pallet_staking::Event::Slash(alice.clone(), 2)), &alice),
+			Some((EventCategory::Staking, AccountEventKind::StakingSlash { amount: amount(2) }))
+		);
+	}
+
+	#[test]
+	fn records_are_filtered_by_category() {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let bob = AccountKeyring::Bob.to_account_id();
+		let records = vec![
+			record(
+				frame_system::Phase::ApplyExtrinsic(1),
+				Event::Balances(pallet_balances::Event::Deposit(alice.clone(), 4)),
+			),
+			record(
+				frame_system::Phase::ApplyExtrinsic(2),
+				Event::Balances(pallet_balances::Event::Deposit(bob, 6)),
+			),
+			record(
+				frame_system::Phase::Initialization,
+				Event::Staking(pallet_staking::Event::Reward(alice.clone(), 5)),
+			),
+		];
+
+		let all = record_events(&records, &alice, &EventFilters::default());
+		assert_eq!(
+			all,
+			vec![
+				AccountEvent {
+					extrinsic_index: Some(1),
+					event: AccountEventKind::Deposit { amount: amount(4) },
+				},
+				AccountEvent {
+					extrinsic_index: None,
+					event: AccountEventKind::StakingReward { amount: amount(5) },
+				},
+			]
+		);
+
+		let filters = EventFilters { categories: vec![EventCategory::Staking], finalized: false };
+		assert_eq!(record_events(&records, &alice, &filters), all[1..].to_vec());
+
+		let filters = EventFilters { categories: vec![EventCategory::Vesting], finalized: false };
+		assert!(record_events(&records, &alice, &filters).is_empty());
+	}
+
+	#[test]
+	fn reorgs_retract_the_old_best_chain() {
+		let genesis = Header::new(
+			0,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let common = header(&genesis, 0);
+		let old_best = header(&common, 1);
+		let new_parent = header(&common, 2);
+		let new_best = header(&new_parent, 2);
+		let headers = Headers::new(&[&genesis, &common, &old_best, &new_parent, &new_best]);
+
+		let block = |header: &Header, retracted| NotifiedBlock {
+			hash: header.hash(),
+			number: *header.number(),
+			retracted,
+		};
+		assert_eq!(
+			best_chain_route(&headers, old_best.hash(), new_best.hash(), 3),
+			vec![block(&old_best, true), block(&new_parent, false), block(&new_best, false)]
+		);
+		// Blocks skipped by the import notifications are enacted too.
+		assert_eq!(
+			best_chain_route(&headers, common.hash(), new_best.hash(), 3),
+			vec![block(&new_parent, false), block(&new_best, false)]
+		);
+		// An unknown previous best block only notifies the new best block.
+		assert_eq!(
+			best_chain_route(&headers, Hash::repeat_byte(9), new_best.hash(), 3),
+			vec![block(&new_best, false)]
+		);
+	}
+}
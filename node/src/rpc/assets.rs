@@ -0,0 +1,161 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC returning the balances of every asset held by accounts.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+pub use polkadex_runtime_api::assets::AssetsApi as AssetsRuntimeApi;
+use polkadex_primitives::{assets::AssetId, AccountId, Balance, Block, Hash};
+use polkadex_runtime_api::assets::AssetBalance;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::generic::BlockId;
+
+/// Error code of runtime API failures.
+const RUNTIME_ERROR: i64 = 1;
+/// Error code of batches exceeding [`MAX_BATCH_SIZE`].
+const BATCH_TOO_LARGE: i64 = 2;
+
+/// Maximum number of accounts of a batch query.
+pub const MAX_BATCH_SIZE: usize = 1_000;
+
+/// Balance of a single asset held by an account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetBalanceInfo {
+	/// The asset.
+	pub asset: AssetId,
+	/// Balance that can be transferred, including frozen funds.
+	pub free: NumberOrHex,
+	/// Balance reserved by pallets such as staking or identity.
+	pub reserved: NumberOrHex,
+	/// Part of the free balance that cannot be withdrawn because of locks.
+	pub frozen: NumberOrHex,
+	/// Part of the frozen balance locked by vesting schedules.
+	pub vesting_locked: NumberOrHex,
+}
+
+impl From<(AssetId, AssetBalance<Balance>)> for AssetBalanceInfo {
+	fn from((asset, balance): (AssetId, AssetBalance<Balance>)) -> Self {
+		AssetBalanceInfo {
+			asset,
+			free: balance.free.into(),
+			reserved: balance.reserved.into(),
+			frozen: balance.frozen.into(),
+			vesting_locked: balance.vesting_locked.into(),
+		}
+	}
+}
+
+/// Balances of every asset held by an account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountBalances {
+	/// The account.
+	pub account: AccountId,
+	/// Balances of the account, starting with the native asset.
+	pub balances: Vec<AssetBalanceInfo>,
+}
+
+/// Assets RPC methods.
+#[rpc]
+pub trait AssetsApi<BlockHash> {
+	/// Returns the balances of every asset held by `account`.
+	#[rpc(name = "assets_accountBalances")]
+	fn account_balances(
+		&self,
+		account: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Vec<AssetBalanceInfo>>;
+
+	/// Returns the balances of every asset held by each of `accounts`, in the same order.
+	///
+	/// At most [`MAX_BATCH_SIZE`] accounts can be queried at once.
+	#[rpc(name = "assets_accountsBalances")]
+	fn accounts_balances(
+		&self,
+		accounts: Vec<AccountId>,
+		at: Option<BlockHash>,
+	) -> Result<Vec<AccountBalances>>;
+}
+
+/// Implements the [`AssetsApi`] RPC trait on top of the runtime API.
+pub struct Assets<C> {
+	client: Arc<C>,
+}
+
+impl<C> Assets<C> {
+	/// Creates a new instance of the Assets RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Assets { client }
+	}
+}
+
+fn runtime_error(e: sp_api::ApiError) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Unable to query the asset balances.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C> AssetsApi<Hash> for Assets<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: AssetsRuntimeApi<Block, AccountId, AssetId, Balance>,
+{
+	fn account_balances(
+		&self,
+		account: AccountId,
+		at: Option<Hash>,
+	) -> Result<Vec<AssetBalanceInfo>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let balances =
+			self.client.runtime_api().account_balances(&at, account).map_err(runtime_error)?;
+		Ok(balances.into_iter().map(Into::into).collect())
+	}
+
+	fn accounts_balances(
+		&self,
+		accounts: Vec<AccountId>,
+		at: Option<Hash>,
+	) -> Result<Vec<AccountBalances>> {
+		if accounts.len() > MAX_BATCH_SIZE {
+			return Err(RpcError {
+				code: ErrorCode::ServerError(BATCH_TOO_LARGE),
+				message: format!("At most {} accounts can be queried at once.", MAX_BATCH_SIZE),
+				data: None,
+			})
+		}
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let balances =
+			self.client.runtime_api().accounts_balances(&at, accounts).map_err(runtime_error)?;
+		Ok(balances
+			.into_iter()
+			.map(|(account, balances)| AccountBalances {
+				account,
+				balances: balances.into_iter().map(Into::into).collect(),
+			})
+			.collect())
+	}
+}
@@ -0,0 +1,140 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC reporting the status of the Ethereum bridge.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+pub use polkadex_runtime_api::bridge::BridgeApi as BridgeRuntimeApi;
+use polkadex_runtime_api::bridge::EthereumHeaderId;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, H256};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Error code of runtime API failures.
+const RUNTIME_ERROR: i64 = 1;
+/// Error code of bridge storage that is missing or does not decode.
+const STORAGE_ERROR: i64 = 2;
+
+/// Ethereum block known to the light client.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EthereumBlock {
+	/// Block number.
+	pub number: u64,
+	/// Block hash.
+	pub hash: H256,
+}
+
+impl From<EthereumHeaderId> for EthereumBlock {
+	fn from(id: EthereumHeaderId) -> Self {
+		EthereumBlock { number: id.number, hash: id.hash }
+	}
+}
+
+/// Last processed message of an inbound channel.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InboundChannelStatus {
+	/// Ethereum contract the channel accepts messages from.
+	pub source_channel: H160,
+	/// Nonce of the last processed message.
+	pub nonce: u64,
+}
+
+/// Status of the Ethereum bridge at a given block.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeStatus {
+	/// Latest finalized Ethereum block.
+	pub finalized: EthereumBlock,
+	/// Best imported Ethereum block.
+	pub best: EthereumBlock,
+	/// Status of the inbound channels.
+	pub inbound_channels: Vec<InboundChannelStatus>,
+	/// Address of the ERC20 PDEX migration contract.
+	pub erc20_pdex_address: H160,
+}
+
+/// Bridge RPC methods.
+#[rpc]
+pub trait BridgeApi<BlockHash> {
+	/// Returns the state of the Ethereum light client and inbound channels.
+	#[rpc(name = "bridge_status")]
+	fn bridge_status(&self, at: Option<BlockHash>) -> Result<BridgeStatus>;
+}
+
+/// Implements the [`BridgeApi`] RPC trait on top of the runtime API.
+pub struct Bridge<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Bridge<C, B> {
+	/// Creates a new instance of the Bridge RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Bridge { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block> BridgeApi<<Block as BlockT>::Hash> for Bridge<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: BridgeRuntimeApi<Block>,
+{
+	fn bridge_status(&self, at: Option<<Block as BlockT>::Hash>) -> Result<BridgeStatus> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let runtime_error = |e: sp_api::ApiError| RpcError {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to query the bridge status.".into(),
+			data: Some(format!("{:?}", e).into()),
+		};
+		let storage_error = |item: &str| RpcError {
+			code: ErrorCode::ServerError(STORAGE_ERROR),
+			message: format!("Unable to decode the bridge's {}.", item),
+			data: Some("The storage layout of the bridge pallets may have changed.".into()),
+		};
+
+		Ok(BridgeStatus {
+			finalized: api
+				.finalized_block(&at)
+				.map_err(runtime_error)?
+				.ok_or_else(|| storage_error("finalized block"))?
+				.into(),
+			best: api
+				.best_block(&at)
+				.map_err(runtime_error)?
+				.ok_or_else(|| storage_error("best block"))?
+				.into(),
+			inbound_channels: api
+				.inbound_nonces(&at)
+				.map_err(runtime_error)?
+				.ok_or_else(|| storage_error("inbound channels"))?
+				.into_iter()
+				.map(|(source_channel, nonce)| InboundChannelStatus { source_channel, nonce })
+				.collect(),
+			erc20_pdex_address: api.erc20_pdex_address(&at).map_err(runtime_error)?,
+		})
+	}
+}
@@ -0,0 +1,172 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! RPC looking up where an extrinsic was included by its hash.
+//!
+//! Reads the records the runtime writes through offchain indexing, so it only knows of
+//! extrinsics imported while the node ran with offchain indexing enabled, and fails on nodes
+//! running without `--enable-offchain-indexing true`.
+
+use std::sync::Arc;
+
+use codec::Decode;
+use extrinsic_index_pallet::{storage_key, ExtrinsicRecord};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use polkadex_primitives::{Balance, Block, BlockNumber, Hash};
+use sc_client_api::{Backend, BlockBackend};
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_core::offchain::{OffchainStorage, STORAGE_PREFIX};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{BlakeTwo256, Hash as HashT},
+	DispatchError,
+};
+
+/// Error code of failures to read the chain or the offchain database.
+const BACKEND_ERROR: i64 = 1;
+/// Error code of lookups on a node running without offchain indexing.
+const INDEXING_DISABLED: i64 = 2;
+
+fn backend_error(message: &str, e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(BACKEND_ERROR),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+/// Inclusion status of an extrinsic.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum InclusionStatus {
+	/// Included in a block of the best chain that is not finalized yet.
+	InBlock,
+	/// Included in a finalized block.
+	Finalized,
+}
+
+/// Where an extrinsic was included, how it dispatched and the fee it paid.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtrinsicStatus {
+	/// Inclusion status of the extrinsic.
+	pub status: InclusionStatus,
+	/// Number of the including block.
+	pub block_number: BlockNumber,
+	/// Hash of the including block.
+	pub block_hash: Hash,
+	/// Index of the extrinsic in the block.
+	pub index: u32,
+	/// Whether the dispatch succeeded.
+	pub success: bool,
+	/// Error of a failed dispatch.
+	pub error: Option<DispatchError>,
+	/// Fee paid for the extrinsic, including the tip.
+	pub fee: NumberOrHex,
+}
+
+/// Extrinsic lookup RPC methods.
+#[rpc]
+pub trait ExtrinsicsApi<ExtrinsicHash> {
+	/// Returns where the extrinsic with the given hash was included in the best chain, or
+	/// `null` if it was not.
+	#[rpc(name = "extrinsic_status")]
+	fn extrinsic_status(&self, hash: ExtrinsicHash) -> Result<Option<ExtrinsicStatus>>;
+}
+
+/// Implements the [`ExtrinsicsApi`] RPC trait on top of the offchain database.
+pub struct Extrinsics<C, B> {
+	client: Arc<C>,
+	backend: Arc<B>,
+	indexing_enabled: bool,
+}
+
+impl<C, B> Extrinsics<C, B> {
+	/// Creates a new instance of the Extrinsics RPC handler.
+	pub fn new(client: Arc<C>, backend: Arc<B>, indexing_enabled: bool) -> Self {
+		Extrinsics { client, backend, indexing_enabled }
+	}
+}
+
+impl<C, B> ExtrinsicsApi<Hash> for Extrinsics<C, B>
+where
+	C: HeaderBackend<Block> + BlockBackend<Block> + Send + Sync + 'static,
+	B: Backend<Block> + Send + Sync + 'static,
+{
+	fn extrinsic_status(&self, hash: Hash) -> Result<Option<ExtrinsicStatus>> {
+		if !self.indexing_enabled {
+			return Err(RpcError {
+				code: ErrorCode::ServerError(INDEXING_DISABLED),
+				message: "Offchain indexing is disabled, restart the node with \
+					`--enable-offchain-indexing true` to look up extrinsics."
+					.into(),
+				data: None,
+			})
+		}
+		let storage = self.backend.offchain_storage().ok_or_else(|| RpcError {
+			code: ErrorCode::ServerError(BACKEND_ERROR),
+			message: "The offchain database is not available.".into(),
+			data: None,
+		})?;
+		let record = match storage.get(STORAGE_PREFIX, &storage_key(hash.as_ref())) {
+			Some(record) => ExtrinsicRecord::<BlockNumber, Balance>::decode(&mut &record[..])
+				.map_err(|e| backend_error("Unable to decode the extrinsic record.", e))?,
+			None => return Ok(None),
+		};
+
+		// Records of retracted blocks stay in the offchain database, so a record only counts
+		// if the best chain block at its height holds the extrinsic.
+		let block_hash = match self
+			.client
+			.hash(record.block)
+			.map_err(|e| backend_error("Unable to read the block hash.", e))?
+		{
+			Some(block_hash) => block_hash,
+			None => return Ok(None),
+		};
+		let included = self
+			.client
+			.block_body(&BlockId::hash(block_hash))
+			.map_err(|e| backend_error("Unable to read the block body.", e))?
+			.and_then(|body| {
+				body.get(record.index as usize)
+					.map(|extrinsic| BlakeTwo256::hash_of(extrinsic) == hash)
+			})
+			.unwrap_or(false);
+		if !included {
+			return Ok(None)
+		}
+
+		let status = if record.block <= self.client.info().finalized_number {
+			InclusionStatus::Finalized
+		} else {
+			InclusionStatus::InBlock
+		};
+		Ok(Some(ExtrinsicStatus {
+			status,
+			block_number: record.block,
+			block_hash,
+			index: record.index,
+			success: record.result.is_ok(),
+			error: record.result.err(),
+			fee: record.fee.into(),
+		}))
+	}
+}
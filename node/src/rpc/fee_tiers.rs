@@ -0,0 +1,124 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! RPC reporting the trading fee tier of an account.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use polkadex_primitives::{AccountId, Balance, Block, Hash};
+pub use polkadex_runtime_api::fee_tiers::FeeTiersApi as FeeTiersRuntimeApi;
+use polkadex_runtime_api::fee_tiers::{FeeTierInfo, NextFeeTier as RuntimeNextFeeTier};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{generic::BlockId, Perbill};
+
+/// Error code of runtime API failures.
+const RUNTIME_ERROR: i64 = 1;
+
+/// A fee tier above the current tier of an account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NextFeeTier {
+	/// Index of the tier.
+	pub tier: u32,
+	/// Stake required to reach the tier.
+	pub min_stake: NumberOrHex,
+	/// Discount of the tier on trading fees.
+	pub discount: Perbill,
+	/// Stake missing to reach the tier.
+	pub remaining: NumberOrHex,
+}
+
+impl From<RuntimeNextFeeTier<Balance>> for NextFeeTier {
+	fn from(next: RuntimeNextFeeTier<Balance>) -> Self {
+		NextFeeTier {
+			tier: next.tier,
+			min_stake: next.min_stake.into(),
+			discount: next.discount,
+			remaining: next.remaining.into(),
+		}
+	}
+}
+
+/// Fee tier of an account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountFeeTier {
+	/// Stake counted towards the tier.
+	pub staked: NumberOrHex,
+	/// Index of the current tier, if the account reached one.
+	pub tier: Option<u32>,
+	/// Discount of the current tier on trading fees.
+	pub discount: Perbill,
+	/// The next tier, if the account is not in the highest tier yet.
+	pub next: Option<NextFeeTier>,
+}
+
+impl From<FeeTierInfo<Balance>> for AccountFeeTier {
+	fn from(info: FeeTierInfo<Balance>) -> Self {
+		AccountFeeTier {
+			staked: info.staked.into(),
+			tier: info.tier,
+			discount: info.discount,
+			next: info.next.map(Into::into),
+		}
+	}
+}
+
+/// Fee tier RPC methods.
+#[rpc]
+pub trait FeeTiersApi<BlockHash> {
+	/// Returns the trading fee tier of `account` and the stake it needs to reach the next
+	/// tier.
+	#[rpc(name = "feeTiers_accountTier")]
+	fn account_tier(&self, account: AccountId, at: Option<BlockHash>) -> Result<AccountFeeTier>;
+}
+
+/// Implements the [`FeeTiersApi`] RPC trait on top of the runtime API.
+pub struct FeeTiers<C> {
+	client: Arc<C>,
+}
+
+impl<C> FeeTiers<C> {
+	/// Creates a new instance of the FeeTiers RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		FeeTiers { client }
+	}
+}
+
+impl<C> FeeTiersApi<Hash> for FeeTiers<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: FeeTiersRuntimeApi<Block, AccountId, Balance>,
+{
+	fn account_tier(&self, account: AccountId, at: Option<Hash>) -> Result<AccountFeeTier> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client
+			.runtime_api()
+			.fee_tier(&at, account)
+			.map(Into::into)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(RUNTIME_ERROR),
+				message: "Unable to query the fee tier.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+}
@@ -0,0 +1,167 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC estimating staking rewards.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use polkadex_primitives::{AccountId, Balance, Block, Hash};
+pub use polkadex_runtime_api::staking::StakingRewardsApi as StakingRewardsRuntimeApi;
+use polkadex_runtime_api::staking::{RewardEstimate, UnclaimedPayout};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::U256;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{generic::BlockId, Perbill};
+
+/// Error code of runtime API failures.
+const RUNTIME_ERROR: i64 = 1;
+/// Error code of stakes that do not fit in a balance.
+const INVALID_STAKE: i64 = 2;
+
+/// Projected return of staking behind a validator of the active era.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorReturn {
+	/// Stash of the validator.
+	pub validator: AccountId,
+	/// Commission the validator takes from its rewards.
+	pub commission: Perbill,
+	/// Stake exposed by the validator in the active era.
+	pub total_stake: NumberOrHex,
+	/// Projected reward of the stake per era.
+	pub era_reward: NumberOrHex,
+	/// Projected yearly reward relative to the stake.
+	pub annual_return: Perbill,
+}
+
+/// Projected staking rewards of a stake.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RewardsEstimate {
+	/// Projected payout of an era to all validators at the current staked ratio.
+	pub era_payout: NumberOrHex,
+	/// Share of the total issuance that is staked in the active era.
+	pub staked_ratio: Perbill,
+	/// Projected return behind every validator of the active era.
+	pub validators: Vec<ValidatorReturn>,
+}
+
+impl From<RewardEstimate<AccountId, Balance>> for RewardsEstimate {
+	fn from(estimate: RewardEstimate<AccountId, Balance>) -> Self {
+		RewardsEstimate {
+			era_payout: estimate.era_payout.into(),
+			staked_ratio: estimate.staked_ratio,
+			validators: estimate
+				.validators
+				.into_iter()
+				.map(|validator| ValidatorReturn {
+					validator: validator.validator,
+					commission: validator.commission,
+					total_stake: validator.total_stake.into(),
+					era_reward: validator.era_reward.into(),
+					annual_return: validator.annual_return,
+				})
+				.collect(),
+		}
+	}
+}
+
+/// Reward of an era that was not paid out yet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Payout {
+	/// The era.
+	pub era: u32,
+	/// Stash of the validator whose payout includes the reward.
+	pub validator: AccountId,
+	/// Reward of the account.
+	pub amount: NumberOrHex,
+}
+
+impl From<UnclaimedPayout<AccountId, Balance>> for Payout {
+	fn from(payout: UnclaimedPayout<AccountId, Balance>) -> Self {
+		Payout { era: payout.era, validator: payout.validator, amount: payout.amount.into() }
+	}
+}
+
+/// Staking rewards RPC methods.
+#[rpc]
+pub trait StakingRewardsApi<BlockHash> {
+	/// Returns the projected rewards of nominating each validator of the active era with
+	/// `stake`.
+	#[rpc(name = "staking_estimateRewards")]
+	fn estimate_rewards(&self, stake: NumberOrHex, at: Option<BlockHash>) -> Result<RewardsEstimate>;
+
+	/// Returns the rewards of `account` that were not paid out yet.
+	#[rpc(name = "staking_unclaimedPayouts")]
+	fn unclaimed_payouts(&self, account: AccountId, at: Option<BlockHash>) -> Result<Vec<Payout>>;
+}
+
+/// Implements the [`StakingRewardsApi`] RPC trait on top of the runtime API.
+pub struct StakingRewards<C> {
+	client: Arc<C>,
+}
+
+impl<C> StakingRewards<C> {
+	/// Creates a new instance of the StakingRewards RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		StakingRewards { client }
+	}
+}
+
+fn runtime_error(e: sp_api::ApiError) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Unable to query the staking rewards.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C> StakingRewardsApi<Hash> for StakingRewards<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: StakingRewardsRuntimeApi<Block, AccountId, Balance>,
+{
+	fn estimate_rewards(&self, stake: NumberOrHex, at: Option<Hash>) -> Result<RewardsEstimate> {
+		let stake = stake.into_u256();
+		if stake > U256::from(Balance::max_value()) {
+			return Err(RpcError {
+				code: ErrorCode::ServerError(INVALID_STAKE),
+				message: "The stake exceeds the maximum balance.".into(),
+				data: None,
+			})
+		}
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client
+			.runtime_api()
+			.estimate_rewards(&at, stake.low_u128())
+			.map(Into::into)
+			.map_err(runtime_error)
+	}
+
+	fn unclaimed_payouts(&self, account: AccountId, at: Option<Hash>) -> Result<Vec<Payout>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let payouts =
+			self.client.runtime_api().unclaimed_payouts(&at, account).map_err(runtime_error)?;
+		Ok(payouts.into_iter().map(Into::into).collect())
+	}
+}
@@ -0,0 +1,159 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC reporting the vesting schedules of an account.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use polkadex_primitives::{AccountId, Balance, Block, BlockNumber, Hash, Moment};
+pub use polkadex_runtime_api::vesting::VestingApi as VestingRuntimeApi;
+use polkadex_runtime_api::vesting::{
+	Unlock as RuntimeUnlock, VestingInfo, VestingScheduleInfo as RuntimeScheduleInfo,
+};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::generic::BlockId;
+
+/// Error code of runtime API failures.
+const RUNTIME_ERROR: i64 = 1;
+
+/// Block at which vested funds unlock, with its expected time.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Unlock {
+	/// Block number of the unlock.
+	pub block: BlockNumber,
+	/// Expected Unix time of the unlock in milliseconds.
+	pub time: Moment,
+}
+
+impl From<RuntimeUnlock<BlockNumber, Moment>> for Unlock {
+	fn from(unlock: RuntimeUnlock<BlockNumber, Moment>) -> Self {
+		Unlock { block: unlock.block, time: unlock.time }
+	}
+}
+
+/// A vesting schedule and the amount it vested so far.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VestingSchedule {
+	/// Block the schedule starts at.
+	pub start: BlockNumber,
+	/// Number of blocks between two unlocks.
+	pub period: BlockNumber,
+	/// Number of unlocks.
+	pub period_count: u32,
+	/// Amount unlocked every period.
+	pub per_period: NumberOrHex,
+	/// Total amount of the schedule.
+	pub total: NumberOrHex,
+	/// Amount vested to date.
+	pub vested: NumberOrHex,
+	/// Next unlock of the schedule, if it is not fully vested.
+	pub next_unlock: Option<Unlock>,
+}
+
+impl From<RuntimeScheduleInfo<BlockNumber, Balance, Moment>> for VestingSchedule {
+	fn from(schedule: RuntimeScheduleInfo<BlockNumber, Balance, Moment>) -> Self {
+		VestingSchedule {
+			start: schedule.start,
+			period: schedule.period,
+			period_count: schedule.period_count,
+			per_period: schedule.per_period.into(),
+			total: schedule.total.into(),
+			vested: schedule.vested.into(),
+			next_unlock: schedule.next_unlock.map(Into::into),
+		}
+	}
+}
+
+/// Vesting state of an account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountVesting {
+	/// Vesting schedules of the account.
+	pub schedules: Vec<VestingSchedule>,
+	/// Total amount of all schedules.
+	pub total: NumberOrHex,
+	/// Amount vested to date.
+	pub vested: NumberOrHex,
+	/// Amount of the stored schedules already claimed. Fully vested schedules are dropped once
+	/// claimed.
+	pub claimed: NumberOrHex,
+	/// Amount vested but not claimed yet.
+	pub claimable: NumberOrHex,
+	/// Next unlock of any schedule.
+	pub next_unlock: Option<Unlock>,
+}
+
+impl From<VestingInfo<BlockNumber, Balance, Moment>> for AccountVesting {
+	fn from(info: VestingInfo<BlockNumber, Balance, Moment>) -> Self {
+		AccountVesting {
+			schedules: info.schedules.into_iter().map(Into::into).collect(),
+			total: info.total.into(),
+			vested: info.vested.into(),
+			claimed: info.claimed.into(),
+			claimable: info.claimable.into(),
+			next_unlock: info.next_unlock.map(Into::into),
+		}
+	}
+}
+
+/// Vesting RPC methods.
+#[rpc]
+pub trait VestingApi<BlockHash> {
+	/// Returns the vesting schedules of `account` and the amounts they vested, claimed and
+	/// unlock next.
+	#[rpc(name = "vesting_accountVesting")]
+	fn account_vesting(&self, account: AccountId, at: Option<BlockHash>) -> Result<AccountVesting>;
+}
+
+/// Implements the [`VestingApi`] RPC trait on top of the runtime API.
+pub struct Vesting<C> {
+	client: Arc<C>,
+}
+
+impl<C> Vesting<C> {
+	/// Creates a new instance of the Vesting RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Vesting { client }
+	}
+}
+
+impl<C> VestingApi<Hash> for Vesting<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: VestingRuntimeApi<Block, AccountId, BlockNumber, Balance, Moment>,
+{
+	fn account_vesting(&self, account: AccountId, at: Option<Hash>) -> Result<AccountVesting> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client
+			.runtime_api()
+			.vesting(&at, account)
+			.map(Into::into)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(RUNTIME_ERROR),
+				message: "Unable to query the vesting schedules.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+}
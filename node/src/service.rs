@@ -20,25 +20,31 @@
 
 //! Service implementation. Specialized wrapper over substrate service.
 
-use crate::rpc as node_rpc;
-use futures::prelude::*;
+use crate::{eth_relayer, indexer, metrics, rpc as node_rpc};
+use futures::{channel::mpsc, prelude::*, stream};
 use node_executor::ExecutorDispatch;
 use node_polkadex_runtime::RuntimeApi;
-use polkadex_primitives::Block;
+use polkadex_primitives::{Block, BlockNumber, Hash};
 use sc_client_api::{ExecutorProvider, RemoteBackend};
+use sc_consensus_manual_seal::{
+	consensus::babe::{BabeConsensusDataProvider, SlotTimestampProvider},
+	EngineCommand, ManualSealParams,
+};
 use sc_executor::NativeElseWasmExecutor;
 use sc_network::{Event, NetworkService};
 use sc_service::{
-	config::Configuration, error::Error as ServiceError, RpcExtensionBuilder, RpcHandlers,
-	TaskManager,
+	config::Configuration, error::Error as ServiceError, KeystoreContainer, NetworkStarter,
+	RpcExtensionBuilder, RpcHandlers, TaskManager,
 };
+use sc_transaction_pool_api::TransactionPool;
 use sp_runtime::traits::Block as BlockT;
-use std::sync::Arc;
+use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
 
 use sc_consensus_babe::SlotProportion;
 use sc_telemetry::{Telemetry, TelemetryWorker};
 
-type FullClient =
+/// The client of a full node.
+pub type FullClient =
 	sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispatch>>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
@@ -66,6 +72,15 @@ type PartialComponents = sc_service::PartialComponents<
 >;
 
 pub fn new_partial(config: &Configuration) -> Result<PartialComponents, ServiceError> {
+	new_partial_base(config, None)
+}
+
+/// Creates the partial components, importing blocks sealed by the manual seal task that
+/// `manual_seal` commands instead of verifying BABE blocks when it is given.
+fn new_partial_base(
+	config: &Configuration,
+	manual_seal: Option<mpsc::Sender<EngineCommand<Hash>>>,
+) -> Result<PartialComponents, ServiceError> {
 	let telemetry = config
 		.telemetry_endpoints
 		.clone()
@@ -121,31 +136,39 @@ pub fn new_partial(config: &Configuration) -> Result<PartialComponents, ServiceE
 	)?;
 
 	let slot_duration = babe_link.config().slot_duration();
-	let import_queue = sc_consensus_babe::import_queue(
-		babe_link.clone(),
-		block_import.clone(),
-		Some(Box::new(justification_import)),
-		client.clone(),
-		select_chain.clone(),
-		move |_, ()| async move {
-			let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+	let import_queue = if manual_seal.is_some() {
+		sc_consensus_manual_seal::import_queue(
+			Box::new(block_import.clone()),
+			&task_manager.spawn_essential_handle(),
+			config.prometheus_registry(),
+		)
+	} else {
+		sc_consensus_babe::import_queue(
+			babe_link.clone(),
+			block_import.clone(),
+			Some(Box::new(justification_import)),
+			client.clone(),
+			select_chain.clone(),
+			move |_, ()| async move {
+				let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
 
-			let slot =
-				sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_duration(
-					*timestamp,
-					slot_duration,
-				);
+				let slot =
+					sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_duration(
+						*timestamp,
+						slot_duration,
+					);
 
-			let uncles =
-				sp_authorship::InherentDataProvider::<<Block as BlockT>::Header>::check_inherents();
+				let uncles =
+					sp_authorship::InherentDataProvider::<<Block as BlockT>::Header>::check_inherents();
 
-			Ok((timestamp, slot, uncles))
-		},
-		&task_manager.spawn_essential_handle(),
-		config.prometheus_registry(),
-		sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
-		telemetry.as_ref().map(|x| x.handle()),
-	)?;
+				Ok((timestamp, slot, uncles))
+			},
+			&task_manager.spawn_essential_handle(),
+			config.prometheus_registry(),
+			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
+			telemetry.as_ref().map(|x| x.handle()),
+		)?
+	};
 
 	let import_setup = (block_import, grandpa_link, babe_link);
 
@@ -166,18 +189,23 @@ pub fn new_partial(config: &Configuration) -> Result<PartialComponents, ServiceE
 		let shared_epoch_changes = babe_link.epoch_changes().clone();
 
 		let client = client.clone();
+		let backend = backend.clone();
 		let pool = transaction_pool.clone();
 		let select_chain = select_chain.clone();
 		let keystore = keystore_container.sync_keystore();
 		let chain_spec = config.chain_spec.cloned_box();
+		let offchain_indexing = config.offchain_worker.indexing_enabled;
 
 		let rpc_extensions_builder = move |deny_unsafe, subscription_executor| {
 			let deps = node_rpc::FullDeps {
 				client: client.clone(),
+				backend: backend.clone(),
+				offchain_indexing,
 				pool: pool.clone(),
 				select_chain: select_chain.clone(),
 				chain_spec: chain_spec.cloned_box(),
 				deny_unsafe,
+				manual_seal: manual_seal.clone(),
 				babe: node_rpc::BabeDeps {
 					babe_config: babe_config.clone(),
 					shared_epoch_changes: shared_epoch_changes.clone(),
@@ -210,6 +238,115 @@ pub fn new_partial(config: &Configuration) -> Result<PartialComponents, ServiceE
 	})
 }
 
+/// Components of a full node consumed by [`spawn_node_tasks`].
+struct NodeTasksParams<'a> {
+	config: Configuration,
+	client: Arc<FullClient>,
+	backend: Arc<FullBackend>,
+	task_manager: &'a mut TaskManager,
+	import_queue: sc_consensus::DefaultImportQueue<Block, FullClient>,
+	keystore_container: &'a KeystoreContainer,
+	transaction_pool: Arc<sc_transaction_pool::FullPool<Block, FullClient>>,
+	rpc_extensions_builder: Box<dyn RpcExtensionBuilder<Output = node_rpc::IoHandler> + Send>,
+	telemetry: Option<&'a mut Telemetry>,
+	/// Serves GRANDPA warp sync proofs from this authority set when given.
+	grandpa_authority_set: Option<grandpa::SharedAuthoritySet<Hash, BlockNumber>>,
+	eth_relayer: Option<eth_relayer::RelayerConfig>,
+	indexer_db: Option<PathBuf>,
+}
+
+/// Builds the network and spawns the tasks full and manual seal nodes have in common: RPC,
+/// offchain workers, the Ethereum relayer, metrics and the indexer.
+///
+/// The network is started by the caller once its consensus tasks are spawned.
+fn spawn_node_tasks(
+	params: NodeTasksParams,
+) -> Result<(Arc<NetworkService<Block, <Block as BlockT>::Hash>>, NetworkStarter), ServiceError> {
+	let NodeTasksParams {
+		config,
+		client,
+		backend,
+		task_manager,
+		import_queue,
+		keystore_container,
+		transaction_pool,
+		rpc_extensions_builder,
+		telemetry,
+		grandpa_authority_set,
+		eth_relayer,
+		indexer_db,
+	} = params;
+
+	let warp_sync = grandpa_authority_set.map(|authority_set| {
+		Arc::new(grandpa::warp_proof::NetworkProvider::new(backend.clone(), authority_set))
+			as Arc<_>
+	});
+
+	let (network, system_rpc_tx, network_starter) =
+		sc_service::build_network(sc_service::BuildNetworkParams {
+			config: &config,
+			client: client.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			import_queue,
+			on_demand: None,
+			block_announce_validator_builder: None,
+			warp_sync,
+		})?;
+
+	if config.offchain_worker.enabled {
+		sc_service::build_offchain_workers(
+			&config,
+			task_manager.spawn_handle(),
+			client.clone(),
+			network.clone(),
+		);
+	}
+
+	let prometheus_registry = config.prometheus_registry().cloned();
+
+	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+		config,
+		backend,
+		client: client.clone(),
+		keystore: keystore_container.sync_keystore(),
+		network: network.clone(),
+		rpc_extensions_builder,
+		transaction_pool: transaction_pool.clone(),
+		task_manager: &mut *task_manager,
+		on_demand: None,
+		remote_blockchain: None,
+		system_rpc_tx,
+		telemetry,
+	})?;
+
+	if let Some(relayer_config) = eth_relayer {
+		let submitter = eth_relayer::TransactionSubmitter::new(
+			client.clone(),
+			transaction_pool,
+			keystore_container.sync_keystore(),
+		)
+		.map_err(ServiceError::Other)?;
+		task_manager
+			.spawn_handle()
+			.spawn("eth-relayer", eth_relayer::run(relayer_config, submitter));
+	}
+
+	if let Some(registry) = prometheus_registry {
+		task_manager
+			.spawn_handle()
+			.spawn("polkadex-metrics", metrics::run(client.clone(), registry));
+	}
+
+	if let Some(path) = indexer_db {
+		task_manager
+			.spawn_handle()
+			.spawn_blocking("polkadex-indexer", indexer::run(client, path));
+	}
+
+	Ok((network, network_starter))
+}
+
 pub struct NewFullBase {
 	pub task_manager: TaskManager,
 	pub client: Arc<FullClient>,
@@ -220,6 +357,8 @@ pub struct NewFullBase {
 /// Creates a full service from the configuration.
 pub fn new_full_base(
 	mut config: Configuration,
+	eth_relayer: Option<eth_relayer::RelayerConfig>,
+	indexer_db: Option<PathBuf>,
 	with_startup_data: impl FnOnce(
 		&sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>,
 		&sc_consensus_babe::BabeLink<Block>,
@@ -251,32 +390,6 @@ pub fn new_full_base(
 		),
 	);
 
-	let warp_sync = Arc::new(grandpa::warp_proof::NetworkProvider::new(
-		backend.clone(),
-		import_setup.1.shared_authority_set().clone(),
-	));
-
-	let (network, system_rpc_tx, network_starter) =
-		sc_service::build_network(sc_service::BuildNetworkParams {
-			config: &config,
-			client: client.clone(),
-			transaction_pool: transaction_pool.clone(),
-			spawn_handle: task_manager.spawn_handle(),
-			import_queue,
-			on_demand: None,
-			block_announce_validator_builder: None,
-			warp_sync: Some(warp_sync),
-		})?;
-
-	if config.offchain_worker.enabled {
-		sc_service::build_offchain_workers(
-			&config,
-			task_manager.spawn_handle(),
-			client.clone(),
-			network.clone(),
-		);
-	}
-
 	let role = config.role.clone();
 	let force_authoring = config.force_authoring;
 	let backoff_authoring_blocks =
@@ -285,19 +398,19 @@ pub fn new_full_base(
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
-	let _rpc_handlers = sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+	let (network, network_starter) = spawn_node_tasks(NodeTasksParams {
 		config,
-		backend,
 		client: client.clone(),
-		keystore: keystore_container.sync_keystore(),
-		network: network.clone(),
-		rpc_extensions_builder,
-		transaction_pool: transaction_pool.clone(),
+		backend,
 		task_manager: &mut task_manager,
-		on_demand: None,
-		remote_blockchain: None,
-		system_rpc_tx,
+		import_queue,
+		keystore_container: &keystore_container,
+		transaction_pool: transaction_pool.clone(),
+		rpc_extensions_builder,
 		telemetry: telemetry.as_mut(),
+		grandpa_authority_set: Some(import_setup.1.shared_authority_set().clone()),
+		eth_relayer,
+		indexer_db,
 	})?;
 
 	let (block_import, grandpa_link, babe_link) = import_setup;
@@ -432,8 +545,159 @@ pub fn new_full_base(
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
-	new_full_base(config, |_, _| ()).map(|NewFullBase { task_manager, .. }| task_manager)
+pub fn new_full(
+	config: Configuration,
+	eth_relayer: Option<eth_relayer::RelayerConfig>,
+	indexer_db: Option<PathBuf>,
+) -> Result<TaskManager, ServiceError> {
+	new_full_base(config, eth_relayer, indexer_db, |_, _| ())
+		.map(|NewFullBase { task_manager, .. }| task_manager)
+}
+
+/// Block production mode of a development node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sealing {
+	/// Seal a block as soon as a transaction enters the pool.
+	Instant,
+	/// Seal blocks on `engine_createBlock` calls only.
+	Manual,
+	/// Seal a block every given number of milliseconds.
+	Interval(u64),
+}
+
+impl FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(Sealing::Instant),
+			"manual" => Ok(Sealing::Manual),
+			_ => s
+				.strip_prefix("interval=")
+				.and_then(|millis| millis.parse().ok())
+				.filter(|millis| *millis > 0)
+				.map(Sealing::Interval)
+				.ok_or_else(|| {
+					format!("Invalid sealing `{}`, expected instant, manual or interval=<ms>", s)
+				}),
+		}
+	}
+}
+
+/// Builds a new service for a development node, producing blocks with manual seal instead of
+/// BABE and GRANDPA.
+///
+/// The node keeps the runtime and RPC set of a full node and additionally serves the
+/// `engine_createBlock` and `engine_finalizeBlock` RPCs. Blocks sealed by the `instant` and
+/// `interval` modes are finalized right away.
+pub fn new_manual_seal(
+	mut config: Configuration,
+	sealing: Sealing,
+	eth_relayer: Option<eth_relayer::RelayerConfig>,
+	indexer_db: Option<PathBuf>,
+) -> Result<TaskManager, ServiceError> {
+	let (command_sink, rpc_commands) = mpsc::channel(1024);
+	let sc_service::PartialComponents {
+		client,
+		backend,
+		mut task_manager,
+		import_queue,
+		keystore_container,
+		select_chain,
+		transaction_pool,
+		other: (rpc_extensions_builder, import_setup, _, mut telemetry),
+	} = new_partial_base(&config, Some(command_sink))?;
+
+	let prometheus_registry = config.prometheus_registry().cloned();
+
+	let (_, network_starter) = spawn_node_tasks(NodeTasksParams {
+		config,
+		client: client.clone(),
+		backend,
+		task_manager: &mut task_manager,
+		import_queue,
+		keystore_container: &keystore_container,
+		transaction_pool: transaction_pool.clone(),
+		rpc_extensions_builder,
+		telemetry: telemetry.as_mut(),
+		grandpa_authority_set: None,
+		eth_relayer,
+		indexer_db,
+	})?;
+
+	let (block_import, _, babe_link) = import_setup;
+
+	let proposer = sc_basic_authorship::ProposerFactory::new(
+		task_manager.spawn_handle(),
+		client.clone(),
+		transaction_pool.clone(),
+		prometheus_registry.as_ref(),
+		telemetry.as_ref().map(|x| x.handle()),
+	);
+
+	let consensus_data_provider = BabeConsensusDataProvider::new(
+		client.clone(),
+		keystore_container.sync_keystore(),
+		babe_link.epoch_changes().clone(),
+		babe_link.config().genesis_authorities.clone(),
+	)
+	.map_err(|e| ServiceError::Other(e.to_string()))?;
+
+	let seal_and_finalize = |create_empty| EngineCommand::SealNewBlock {
+		create_empty,
+		finalize: true,
+		parent_hash: None,
+		sender: None,
+	};
+	let commands_stream = match sealing {
+		Sealing::Manual => rpc_commands.boxed(),
+		Sealing::Instant => stream::select(
+			rpc_commands,
+			transaction_pool
+				.import_notification_stream()
+				.map(move |_| seal_and_finalize(false)),
+		)
+		.boxed(),
+		Sealing::Interval(millis) => stream::select(
+			rpc_commands,
+			stream::unfold((), move |()| async move {
+				futures_timer::Delay::new(Duration::from_millis(millis)).await;
+				Some((seal_and_finalize(true), ()))
+			}),
+		)
+		.boxed(),
+	};
+
+	let client_clone = client.clone();
+	let manual_seal = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+		block_import,
+		env: proposer,
+		client: client.clone(),
+		pool: transaction_pool,
+		commands_stream,
+		select_chain,
+		consensus_data_provider: Some(Box::new(consensus_data_provider)),
+		create_inherent_data_providers: move |parent, ()| {
+			let client_clone = client_clone.clone();
+			async move {
+				let uncles = sc_consensus_uncles::create_uncles_inherent_data_provider(
+					&*client_clone,
+					parent,
+				)?;
+
+				let timestamp = SlotTimestampProvider::new(client_clone.clone())?;
+
+				let slot =
+					sp_consensus_babe::inherents::InherentDataProvider::new(timestamp.slot());
+
+				Ok((timestamp, slot, uncles))
+			}
+		},
+	});
+	task_manager.spawn_essential_handle().spawn_blocking("manual-seal", manual_seal);
+
+	network_starter.start_network();
+	Ok(task_manager)
 }
 
 type LightClientConfig = (
@@ -648,6 +912,8 @@ mod tests {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. } =
 					new_full_base(
 						config,
+						None,
+						None,
 						|block_import: &sc_consensus_babe::BabeBlockImport<Block, _, _>,
 						 babe_link: &sc_consensus_babe::BabeLink<Block>| {
 							setup_handles = Some((block_import.clone(), babe_link.clone()));
@@ -795,7 +1061,8 @@ mod tests {
 				let check_era = frame_system::CheckEra::from(Era::Immortal);
 				let check_nonce = frame_system::CheckNonce::from(index);
 				let check_weight = frame_system::CheckWeight::new();
-				let payment = pallet_transaction_payment::ChargeTransactionPayment::from(0);
+				let payment = trading_fees_pallet::ChargeTradingFee::from(0);
+				let index_extrinsic = extrinsic_index_pallet::IndexExtrinsic::new();
 				let extra = (
 					check_spec_version,
 					check_tx_version,
@@ -804,11 +1071,12 @@ mod tests {
 					check_nonce,
 					check_weight,
 					payment,
+					index_extrinsic,
 				);
 				let raw_payload = SignedPayload::from_raw(
 					function,
 					extra,
-					(spec_version, transaction_version, genesis_hash, genesis_hash, (), (), ()),
+					(spec_version, transaction_version, genesis_hash, genesis_hash, (), (), (), ()),
 				);
 				let signature = raw_payload.using_encoded(|payload| signer.sign(payload));
 				let (function, extra, _) = raw_payload.deconstruct();
@@ -826,7 +1094,7 @@ mod tests {
 			crate::chain_spec::tests::integration_test_config_with_two_authorities(),
 			|config| {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. } =
-					new_full_base(config, |_, _| ())?;
+					new_full_base(config, None, None, |_, _| ())?;
 				Ok(sc_service_test::TestNetComponents::new(
 					task_manager,
 					client,
@@ -0,0 +1,332 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `session-keys` subcommands, managing the session keys of a validator.
+//!
+//! All session keys are derived from a single secret URI, each under the hard junction of its
+//! name: `<suri>//babe`, `<suri>//grandpa`, `<suri>//im_online` and
+//! `<suri>//authority_discovery`.
+
+use std::{
+	io::{self, BufRead, Write},
+	sync::Arc,
+};
+
+use crate::{
+	chain_spec,
+	genesis::parse_account,
+	node_client::{block_on, hex, storage_key, twox_64_concat, NodeClient},
+};
+use codec::Encode;
+use grandpa_primitives::AuthorityId as GrandpaId;
+use node_polkadex_runtime::{Call, SessionKeys};
+use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
+use polkadex_primitives::AccountId;
+use sc_cli::{CliConfiguration, KeystoreParams, SharedParams, SubstrateCli};
+use sc_keystore::LocalKeystore;
+use sc_service::{config::KeystoreConfig, BasePath};
+use serde_json::json;
+use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
+use sp_consensus_babe::AuthorityId as BabeId;
+use sp_core::{
+	crypto::{key_types, KeyTypeId, Ss58Codec},
+	sr25519, Pair, Public,
+};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use structopt::StructOpt;
+
+/// Derivation junction of every session key, by key type.
+const JUNCTIONS: [(KeyTypeId, &str); 4] = [
+	(key_types::BABE, "babe"),
+	(key_types::GRANDPA, "grandpa"),
+	(key_types::IM_ONLINE, "im_online"),
+	(key_types::AUTHORITY_DISCOVERY, "authority_discovery"),
+];
+
+/// The `session-keys` subcommands.
+#[derive(Debug, StructOpt)]
+pub enum SessionKeysCmd {
+	/// Derive the session keys from a seed and print the `set_keys` payload.
+	Generate(GenerateCmd),
+	/// Insert the session keys derived from a seed into a local keystore.
+	Insert(InsertCmd),
+	/// Check that a keystore holds the session keys registered on-chain for a stash.
+	Verify(VerifyCmd),
+}
+
+/// The `session-keys generate` command.
+#[derive(Debug, StructOpt)]
+pub struct GenerateCmd {
+	/// Secret URI to derive the keys from. A new secret phrase is generated and printed when
+	/// it is omitted.
+	#[structopt(long)]
+	pub suri: Option<String>,
+}
+
+/// The `session-keys insert` command.
+#[derive(Debug, StructOpt)]
+pub struct InsertCmd {
+	/// Secret URI to derive the keys from. It is read from the standard input when omitted,
+	/// which keeps it out of the shell history.
+	#[structopt(long)]
+	pub suri: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+/// The `session-keys verify` command.
+#[derive(Debug, StructOpt)]
+pub struct VerifyCmd {
+	/// Stash account of the validator.
+	#[structopt(long, parse(try_from_str = parse_account))]
+	pub stash: AccountId,
+
+	/// HTTP JSON-RPC endpoint of a node to read the registered keys from.
+	#[structopt(long, default_value = "http://localhost:9933")]
+	pub url: String,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+impl CliConfiguration for InsertCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+}
+
+impl CliConfiguration for VerifyCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+}
+
+fn key_suri(suri: &str, key_type: KeyTypeId) -> String {
+	let junction = JUNCTIONS
+		.iter()
+		.find(|(id, _)| *id == key_type)
+		.map(|(_, junction)| junction)
+		.expect("session keys only have the key types of JUNCTIONS; qed");
+	format!("{}//{}", suri, junction)
+}
+
+fn derive<T>(suri: &str, key_type: KeyTypeId) -> sc_cli::Result<T>
+where
+	T: Public,
+	T::Pair: Pair<Public = T>,
+{
+	T::Pair::from_string(&key_suri(suri, key_type), None)
+		.map(|pair| pair.public())
+		.map_err(|e| format!("Invalid secret URI: {:?}", e).into())
+}
+
+/// Session keys derived from the secret URI `suri`.
+pub fn session_keys(suri: &str) -> sc_cli::Result<SessionKeys> {
+	Ok(chain_spec::session_keys(
+		derive::<GrandpaId>(suri, key_types::GRANDPA)?,
+		derive::<BabeId>(suri, key_types::BABE)?,
+		derive::<ImOnlineId>(suri, key_types::IM_ONLINE)?,
+		derive::<AuthorityDiscoveryId>(suri, key_types::AUTHORITY_DISCOVERY)?,
+	))
+}
+
+fn read_suri(suri: &Option<String>) -> sc_cli::Result<String> {
+	if let Some(suri) = suri {
+		return Ok(suri.clone())
+	}
+	eprint!("Secret URI: ");
+	io::stderr().flush()?;
+	let mut suri = String::new();
+	io::stdin().lock().read_line(&mut suri)?;
+	Ok(suri.trim_end_matches(&['\r', '\n'][..]).into())
+}
+
+/// Keystore selected by the shared and keystore parameters, as the node would open it.
+fn open_keystore<C: SubstrateCli>(
+	cli: &C,
+	shared_params: &SharedParams,
+	keystore_params: &KeystoreParams,
+) -> sc_cli::Result<SyncCryptoStorePtr> {
+	let base_path = shared_params
+		.base_path()
+		.unwrap_or_else(|| BasePath::from_project("", "", &C::executable_name()));
+	let chain_spec = cli.load_spec(&shared_params.chain_id(shared_params.is_dev()))?;
+	match keystore_params.keystore_config(&base_path.config_dir(chain_spec.id()))? {
+		(_, KeystoreConfig::Path { path, password }) => {
+			let keystore = LocalKeystore::open(&path, password)
+				.map_err(|e| format!("Unable to open the keystore {}: {}", path.display(), e))?;
+			Ok(Arc::new(keystore))
+		},
+		_ => Err("Only local keystores are supported".into()),
+	}
+}
+
+impl GenerateCmd {
+	/// Run the session-keys generate command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let (suri, phrase) = match &self.suri {
+			Some(suri) => (suri.clone(), None),
+			None => {
+				let (_, phrase, _) = sr25519::Pair::generate_with_phrase(None);
+				(phrase.clone(), Some(phrase))
+			},
+		};
+		let keys = session_keys(&suri)?;
+		let call = Call::Session(pallet_session::Call::set_keys(keys.clone(), Vec::new()));
+
+		let mut output = json!({
+			"babe": hex(keys.babe.as_ref()),
+			"grandpa": hex(keys.grandpa.as_ref()),
+			"imOnline": hex(keys.im_online.as_ref()),
+			"authorityDiscovery": hex(keys.authority_discovery.as_ref()),
+			"keys": hex(&keys.encode()),
+			"proof": "0x",
+			"call": hex(&call.encode()),
+		});
+		if let Some(phrase) = phrase {
+			output["secretPhrase"] = phrase.into();
+		}
+		let output = serde_json::to_string_pretty(&output)
+			.map_err(|e| format!("Unable to print the session keys: {}", e))?;
+		println!("{}", output);
+		Ok(())
+	}
+}
+
+/// Inserts the session keys derived from `suri` into `keystore` and returns them.
+fn insert_keys(keystore: &dyn SyncCryptoStore, suri: &str) -> sc_cli::Result<SessionKeys> {
+	let keys = session_keys(suri)?;
+	for (public, key_type) in keys.clone().into_raw_public_keys() {
+		let key_suri = key_suri(suri, key_type);
+		SyncCryptoStore::insert_unknown(keystore, key_type, &key_suri, &public)
+			.map_err(|_| format!("Unable to insert the {} key", key_type_name(key_type)))?;
+	}
+	Ok(keys)
+}
+
+impl InsertCmd {
+	/// Run the session-keys insert command.
+	pub fn run<C: SubstrateCli>(&self, cli: &C) -> sc_cli::Result<()> {
+		let suri = read_suri(&self.suri)?;
+		let keystore = open_keystore(cli, &self.shared_params, &self.keystore_params)?;
+		let keys = insert_keys(&*keystore, &suri)?;
+		println!("Inserted the session keys {}", hex(&keys.encode()));
+		Ok(())
+	}
+}
+
+fn key_type_name(key_type: KeyTypeId) -> String {
+	String::from_utf8_lossy(&key_type.0).into_owned()
+}
+
+/// Storage key of the session keys of `validator` in `Session::NextKeys`.
+pub fn next_keys_key(validator: &AccountId) -> Vec<u8> {
+	[storage_key("Session", "NextKeys"), twox_64_concat(&validator.encode())].concat()
+}
+
+impl VerifyCmd {
+	async fn registered_keys(&self) -> sc_cli::Result<Option<SessionKeys>> {
+		NodeClient::connect(&self.url).await?.storage(&next_keys_key(&self.stash)).await
+	}
+
+	/// Run the session-keys verify command.
+	pub fn run<C: SubstrateCli>(&self, cli: &C) -> sc_cli::Result<()> {
+		let keystore = open_keystore(cli, &self.shared_params, &self.keystore_params)?;
+		let keys = block_on(self.registered_keys())??.ok_or_else(|| {
+			format!("{} has no session keys registered", self.stash.to_ss58check())
+		})?;
+
+		let mut missing = 0;
+		for (public, key_type) in keys.into_raw_public_keys() {
+			let found = SyncCryptoStore::has_keys(&*keystore, &[(public.clone(), key_type)]);
+			if !found {
+				missing += 1;
+			}
+			println!(
+				"{} {}: {}",
+				key_type_name(key_type),
+				hex(&public),
+				if found { "found" } else { "missing" }
+			);
+		}
+		match missing {
+			0 => {
+				println!("The keystore holds all session keys of {}", self.stash.to_ss58check());
+				Ok(())
+			},
+			missing => Err(format!(
+				"The keystore misses {} session keys of {}",
+				missing,
+				self.stash.to_ss58check()
+			)
+			.into()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn raw_keys(keys: &SessionKeys) -> Vec<(Vec<u8>, KeyTypeId)> {
+		keys.clone().into_raw_public_keys()
+	}
+
+	#[test]
+	fn inserted_keys_are_the_generated_keys() {
+		let dir = tempfile::tempdir().unwrap();
+		let keystore = LocalKeystore::open(dir.path(), None).unwrap();
+		let suri = "//Validator";
+
+		let inserted = insert_keys(&keystore, suri).unwrap();
+		let generated = session_keys(suri).unwrap();
+		assert_eq!(inserted, generated);
+		for key in raw_keys(&generated) {
+			assert!(
+				SyncCryptoStore::has_keys(&keystore, &[key.clone()]),
+				"{} key",
+				key_type_name(key.1)
+			);
+		}
+		assert!(SyncCryptoStore::has_keys(&keystore, &raw_keys(&generated)));
+
+		let other = session_keys("//Other").unwrap();
+		assert!(raw_keys(&other)
+			.into_iter()
+			.all(|key| !SyncCryptoStore::has_keys(&keystore, &[key])));
+	}
+}
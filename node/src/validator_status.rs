@@ -0,0 +1,591 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `validator-status` subcommand, reporting the health of a validator from the JSON-RPC
+//! API of its node before `ImOnline` reports and slashes reveal problems.
+//!
+//! The unsafe RPC methods `author_hasKey`, `author_hasSessionKeys` and `babe_epochAuthorship`
+//! are only served to local connections by default, so the command should run on the validator
+//! machine.
+//!
+//! Keys registered with `set_keys` only take effect two sessions later, so the BABE and GRANDPA
+//! checks use the keys of the validator in the current authority sets rather than the keys
+//! registered in `Session::NextKeys`.
+
+use std::collections::HashMap;
+
+use crate::{
+	check_genesis::Report,
+	genesis::parse_account,
+	node_client::{block_on, hex, storage_key, twox_64_concat, NodeClient},
+	session_keys::next_keys_key,
+};
+use codec::{Decode, Encode};
+use grandpa_primitives::{
+	AuthorityId as GrandpaId, VersionedAuthorityList, GRANDPA_AUTHORITIES_KEY,
+};
+use node_polkadex_runtime::SessionKeys;
+use pallet_staking::{Exposure, UnappliedSlash};
+use polkadex_primitives::{AccountId, Balance, BlockNumber};
+use serde::Deserialize;
+use serde_json::json;
+use sp_consensus_babe::AuthorityId as BabeId;
+use sp_core::crypto::Ss58Codec;
+use sp_staking::offence::OffenceDetails;
+use structopt::StructOpt;
+
+/// Offence report as stored by the `Offences` pallet.
+type OffenceReport = OffenceDetails<AccountId, (AccountId, Exposure<AccountId, Balance>)>;
+
+/// Keys a validator of the current session authors and finalizes blocks with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AuthorityKeys {
+	babe: BabeId,
+	grandpa: GrandpaId,
+}
+
+/// Participation of a GRANDPA key in a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GrandpaVote {
+	/// The key is not a voter of the current authority set.
+	NotAuthority,
+	/// The key neither prevoted nor precommitted.
+	Missing,
+	/// The key prevoted, but did not precommit yet.
+	Prevoted,
+	/// The key prevoted and precommitted.
+	Voted,
+}
+
+/// The `validator-status` command.
+#[derive(Debug, StructOpt)]
+pub struct ValidatorStatusCmd {
+	/// Stash account of the validator.
+	#[structopt(long, parse(try_from_str = parse_account))]
+	pub stash: AccountId,
+
+	/// HTTP JSON-RPC endpoint of the validator node.
+	#[structopt(long, default_value = "http://localhost:9933")]
+	pub url: String,
+}
+
+/// Slots claimable by a BABE key in the current epoch, as returned by `babe_epochAuthorship`.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+struct EpochAuthorship {
+	#[serde(default)]
+	primary: Vec<u64>,
+	#[serde(default)]
+	secondary: Vec<u64>,
+	#[serde(default)]
+	secondary_vrf: Vec<u64>,
+}
+
+/// Votes of a GRANDPA round, as returned by `grandpa_roundState`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Votes {
+	missing: Vec<GrandpaId>,
+}
+
+/// A GRANDPA round, as returned by `grandpa_roundState`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RoundState {
+	round: u32,
+	prevotes: Votes,
+	precommits: Votes,
+}
+
+/// State of the GRANDPA voter, as returned by `grandpa_roundState`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportedRoundStates {
+	set_id: u32,
+	best: RoundState,
+}
+
+/// Block header number, as returned by `chain_getHeader`.
+#[derive(Debug, Deserialize)]
+struct Header {
+	#[serde(deserialize_with = "deserialize_number")]
+	number: BlockNumber,
+}
+
+fn deserialize_number<'de, D: serde::Deserializer<'de>>(d: D) -> Result<BlockNumber, D::Error> {
+	let number = String::deserialize(d)?;
+	BlockNumber::from_str_radix(number.trim_start_matches("0x"), 16)
+		.map_err(serde::de::Error::custom)
+}
+
+impl EpochAuthorship {
+	/// First slot after `slot` that may be claimed.
+	fn next_claim(&self, slot: u64) -> Option<u64> {
+		self.primary
+			.iter()
+			.chain(self.secondary.iter())
+			.chain(self.secondary_vrf.iter())
+			.filter(|claim| **claim > slot)
+			.min()
+			.copied()
+	}
+}
+
+impl RoundState {
+	/// Participation of `key` in the round, where `authorities` is the current GRANDPA set.
+	///
+	/// The round state only lists the voters that are missing, so a key outside of the set
+	/// would otherwise look as if it voted.
+	fn vote(&self, authorities: &[GrandpaId], key: &GrandpaId) -> GrandpaVote {
+		if !authorities.contains(key) {
+			return GrandpaVote::NotAuthority
+		}
+		match (self.prevotes.missing.contains(key), self.precommits.missing.contains(key)) {
+			(false, false) => GrandpaVote::Voted,
+			(false, true) => GrandpaVote::Prevoted,
+			_ => GrandpaVote::Missing,
+		}
+	}
+}
+
+/// Keys of the validator at `index` in the current session.
+///
+/// The BABE and GRANDPA authority sets follow the order of `Session::Validators` as long as
+/// they were enacted in the same session, which is checked through their lengths.
+fn authority_keys(
+	index: usize,
+	validators: usize,
+	babe: &[(BabeId, u64)],
+	grandpa: &[(GrandpaId, u64)],
+) -> Option<AuthorityKeys> {
+	if babe.len() != validators || grandpa.len() != validators {
+		return None
+	}
+	Some(AuthorityKeys { babe: babe.get(index)?.0.clone(), grandpa: grandpa.get(index)?.0.clone() })
+}
+
+impl ValidatorStatusCmd {
+	/// Run the validator-status command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let mut report = Report::default();
+		block_on(self.check(&mut report))??;
+
+		for finding in report.0.iter() {
+			println!("{}", finding);
+		}
+		match report.errors() {
+			0 => Ok(()),
+			errors => Err(format!(
+				"Found {} problems with validator {}",
+				errors,
+				self.stash.to_ss58check()
+			)
+			.into()),
+		}
+	}
+
+	async fn check(&self, report: &mut Report) -> sc_cli::Result<()> {
+		let client = NodeClient::connect(&self.url).await?;
+		let stash = self.stash.to_ss58check();
+
+		match client.storage::<SessionKeys>(&next_keys_key(&self.stash)).await? {
+			Some(keys) => self.check_registered_keys(&client, &keys, report).await,
+			None => {
+				report.error(format!("{} has no session keys registered", stash));
+				return Ok(())
+			},
+		}
+		match self.check_session(&client, report).await? {
+			Some(keys) => {
+				self.check_babe(&client, &keys, report).await;
+				self.check_grandpa(&client, &keys, report).await;
+			},
+			None => println!("BABE and GRANDPA: skipped, no keys in the current authority sets"),
+		}
+		self.check_offences(&client, report).await
+	}
+
+	/// Reports whether the node holds the keys registered for the upcoming sessions.
+	async fn check_registered_keys(
+		&self,
+		client: &NodeClient,
+		keys: &SessionKeys,
+		report: &mut Report,
+	) {
+		match client
+			.call::<bool>("author_hasSessionKeys", vec![json!(hex(&keys.encode()))])
+			.await
+		{
+			Ok(true) => println!("Node keystore: holds the registered session keys"),
+			Ok(false) => report.error("The node does not hold the registered session keys".into()),
+			Err(e) => report.warning(format!("Unable to check the node keystore: {}", e)),
+		}
+	}
+
+	/// Reports whether the validator validates the current and the next session, along with
+	/// the heartbeat of the current session, and returns its keys in the current session.
+	async fn check_session(
+		&self,
+		client: &NodeClient,
+		report: &mut Report,
+	) -> sc_cli::Result<Option<AuthorityKeys>> {
+		let stash = self.stash.to_ss58check();
+		let session: u32 = client
+			.storage(&storage_key("Session", "CurrentIndex"))
+			.await?
+			.unwrap_or_default();
+		let validators: Vec<AccountId> =
+			client.storage(&storage_key("Session", "Validators")).await?.unwrap_or_default();
+		let queued: Vec<(AccountId, SessionKeys)> =
+			client.storage(&storage_key("Session", "QueuedKeys")).await?.unwrap_or_default();
+
+		let authority_index = validators.iter().position(|validator| *validator == self.stash);
+		match authority_index {
+			Some(_) => println!("Session {}: {} is validating", session, stash),
+			None => report.warning(format!("{} is not validating session {}", stash, session)),
+		}
+		match queued.iter().find(|(validator, _)| *validator == self.stash) {
+			Some((_, queued_keys)) => {
+				println!("Session {}: {} is validating", session + 1, stash);
+				self.check_keystore(
+					client,
+					"next session",
+					queued_keys.babe.as_ref(),
+					"babe",
+					report,
+				)
+				.await;
+				self.check_keystore(
+					client,
+					"next session",
+					queued_keys.grandpa.as_ref(),
+					"gran",
+					report,
+				)
+				.await;
+			},
+			None => report.warning(format!("{} is not validating session {}", stash, session + 1)),
+		}
+
+		let authority_index = match authority_index {
+			Some(authority_index) => authority_index,
+			None => return Ok(None),
+		};
+		self.check_heartbeat(client, session, authority_index as u32, report).await?;
+
+		let babe: Vec<(BabeId, u64)> =
+			client.storage(&storage_key("Babe", "Authorities")).await?.unwrap_or_default();
+		let grandpa: Vec<(GrandpaId, u64)> = client
+			.storage::<VersionedAuthorityList>(GRANDPA_AUTHORITIES_KEY)
+			.await?
+			.map(Into::into)
+			.unwrap_or_default();
+		let keys = authority_keys(authority_index, validators.len(), &babe, &grandpa);
+		match &keys {
+			Some(keys) => {
+				self.check_keystore(client, "current session", keys.babe.as_ref(), "babe", report)
+					.await;
+				self.check_keystore(
+					client,
+					"current session",
+					keys.grandpa.as_ref(),
+					"gran",
+					report,
+				)
+				.await;
+			},
+			None => report.warning(format!(
+				"The BABE and GRANDPA authority sets do not match the validators of session {}",
+				session
+			)),
+		}
+		Ok(keys)
+	}
+
+	/// Reports whether the node keystore holds the `key_type` key `public` of `session`.
+	async fn check_keystore(
+		&self,
+		client: &NodeClient,
+		session: &str,
+		public: &[u8],
+		key_type: &str,
+		report: &mut Report,
+	) {
+		match client
+			.call::<bool>("author_hasKey", vec![json!(hex(public)), json!(key_type)])
+			.await
+		{
+			Ok(true) => {},
+			Ok(false) => report.error(format!(
+				"The node does not hold the {} key of the {}: {}",
+				key_type,
+				session,
+				hex(public)
+			)),
+			Err(e) => report.warning(format!("Unable to check the node keystore: {}", e)),
+		}
+	}
+
+	async fn check_heartbeat(
+		&self,
+		client: &NodeClient,
+		session: u32,
+		authority_index: u32,
+		report: &mut Report,
+	) -> sc_cli::Result<()> {
+		let session_key = twox_64_concat(&session.encode());
+		let heartbeat_key = [
+			storage_key("ImOnline", "ReceivedHeartbeats"),
+			session_key.clone(),
+			twox_64_concat(&authority_index.encode()),
+		]
+		.concat();
+		let authored_key = [
+			storage_key("ImOnline", "AuthoredBlocks"),
+			session_key,
+			twox_64_concat(&self.stash.encode()),
+		]
+		.concat();
+
+		let heartbeat: Option<Vec<u8>> = client.storage(&heartbeat_key).await?;
+		let authored: u32 = client.storage(&authored_key).await?.unwrap_or_default();
+		if heartbeat.is_some() {
+			println!("Heartbeat: received in session {}", session);
+			return Ok(())
+		}
+		if authored > 0 {
+			println!("Heartbeat: none yet, but {} blocks authored this session", authored);
+			return Ok(())
+		}
+
+		let heartbeat_after: BlockNumber = client
+			.storage(&storage_key("ImOnline", "HeartbeatAfter"))
+			.await?
+			.unwrap_or_default();
+		let best: Header = client.call("chain_getHeader", Vec::new()).await?;
+		if best.number < heartbeat_after {
+			println!("Heartbeat: not due before block {}", heartbeat_after);
+		} else {
+			report.error(format!(
+				"No heartbeat and no authored block in session {}, the validator will be \
+				 reported offline at the end of the session",
+				session
+			));
+		}
+		Ok(())
+	}
+
+	/// Reports the slots the BABE key of the validator may claim in the current epoch.
+	async fn check_babe(&self, client: &NodeClient, keys: &AuthorityKeys, report: &mut Report) {
+		let authorship = match client
+			.call::<HashMap<BabeId, EpochAuthorship>>("babe_epochAuthorship", Vec::new())
+			.await
+		{
+			Ok(authorship) => authorship,
+			Err(e) => return report.warning(format!("Unable to read the BABE slot claims: {}", e)),
+		};
+		let slot: u64 = match client.storage(&storage_key("Babe", "CurrentSlot")).await {
+			Ok(slot) => slot.unwrap_or_default(),
+			Err(e) => return report.warning(format!("Unable to read the BABE slot: {}", e)),
+		};
+		match authorship.get(&keys.babe) {
+			Some(claims) => {
+				let primary = claims.primary.len();
+				let secondary = claims.secondary.len() + claims.secondary_vrf.len();
+				match claims.next_claim(slot) {
+					Some(next) => println!(
+						"BABE: {} primary and {} secondary slots this epoch, next in slot {}",
+						primary, secondary, next
+					),
+					None if primary + secondary == 0 =>
+						report.warning("The BABE key can claim no slot this epoch".into()),
+					None => println!(
+						"BABE: {} primary and {} secondary slots this epoch, none left",
+						primary, secondary
+					),
+				}
+			},
+			None => report.error("The node cannot claim slots with the BABE key".into()),
+		}
+	}
+
+	/// Reports whether the GRANDPA key of the validator voted in the best round.
+	async fn check_grandpa(&self, client: &NodeClient, keys: &AuthorityKeys, report: &mut Report) {
+		let state = match client.call::<ReportedRoundStates>("grandpa_roundState", Vec::new()).await
+		{
+			Ok(state) => state,
+			Err(e) => return report.warning(format!("Unable to read the GRANDPA round: {}", e)),
+		};
+		let authorities: Vec<(GrandpaId, u64)> =
+			match client.storage::<VersionedAuthorityList>(GRANDPA_AUTHORITIES_KEY).await {
+				Ok(authorities) => authorities.map(Into::into).unwrap_or_default(),
+				Err(e) => return report.warning(format!("Unable to read the GRANDPA set: {}", e)),
+			};
+		let set_id: u64 = match client.storage(&storage_key("Grandpa", "CurrentSetId")).await {
+			Ok(set_id) => set_id.unwrap_or_default(),
+			Err(e) => return report.warning(format!("Unable to read the GRANDPA set: {}", e)),
+		};
+		if u64::from(state.set_id) != set_id {
+			return report.warning(format!(
+				"The GRANDPA voter is on set {} while the chain enacted set {}",
+				state.set_id, set_id
+			))
+		}
+
+		let authorities: Vec<GrandpaId> = authorities.into_iter().map(|(id, _)| id).collect();
+		let round = state.best.round;
+		match state.best.vote(&authorities, &keys.grandpa) {
+			GrandpaVote::Voted =>
+				println!("GRANDPA: voted in round {} of set {}", round, state.set_id),
+			GrandpaVote::Prevoted => println!(
+				"GRANDPA: prevoted in round {} of set {}, no precommit yet",
+				round, state.set_id
+			),
+			GrandpaVote::Missing => report.warning(format!(
+				"No GRANDPA prevote in round {} of set {} yet",
+				round, state.set_id
+			)),
+			GrandpaVote::NotAuthority =>
+				report.error(format!("The GRANDPA key is not a voter of set {}", state.set_id)),
+		}
+	}
+
+	/// Reports the offences and unapplied slashes against the stash.
+	async fn check_offences(&self, client: &NodeClient, report: &mut Report) -> sc_cli::Result<()> {
+		let reports: Vec<(Vec<u8>, OffenceReport)> =
+			client.storage_pairs(&storage_key("Offences", "Reports")).await?;
+		let offences =
+			reports.iter().filter(|(_, offence)| offence.offender.0 == self.stash).count();
+		if offences > 0 {
+			report.warning(format!("{} offences were reported against the stash", offences));
+		}
+
+		let prefix = storage_key("Staking", "UnappliedSlashes");
+		let slashes: Vec<(Vec<u8>, Vec<UnappliedSlash<AccountId, Balance>>)> =
+			client.storage_pairs(&prefix).await?;
+		for (key, slashes) in slashes {
+			// The era follows the prefix and its `Twox64Concat` hash.
+			let era: u32 = key
+				.get(prefix.len() + 8..)
+				.and_then(|mut era| Decode::decode(&mut era).ok())
+				.unwrap_or_default();
+			for slash in slashes.iter().filter(|slash| slash.validator == self.stash) {
+				report.error(format!("A slash of {} is pending in era {}", slash.own, era));
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_keyring::{Ed25519Keyring, Sr25519Keyring};
+
+	fn grandpa(keyring: Ed25519Keyring) -> GrandpaId {
+		keyring.public().into()
+	}
+
+	fn babe(keyring: Sr25519Keyring) -> BabeId {
+		keyring.public().into()
+	}
+
+	fn round(prevotes: Vec<GrandpaId>, precommits: Vec<GrandpaId>) -> RoundState {
+		RoundState {
+			round: 1,
+			prevotes: Votes { missing: prevotes },
+			precommits: Votes { missing: precommits },
+		}
+	}
+
+	#[test]
+	fn grandpa_votes_require_set_membership() {
+		let authorities = vec![grandpa(Ed25519Keyring::Alice), grandpa(Ed25519Keyring::Bob)];
+		let alice = grandpa(Ed25519Keyring::Alice);
+		let bob = grandpa(Ed25519Keyring::Bob);
+
+		let state = round(vec![bob.clone()], vec![bob.clone()]);
+		assert_eq!(state.vote(&authorities, &alice), GrandpaVote::Voted);
+		assert_eq!(state.vote(&authorities, &bob), GrandpaVote::Missing);
+		// keys outside of the set are never listed as missing.
+		assert_eq!(
+			state.vote(&authorities, &grandpa(Ed25519Keyring::Charlie)),
+			GrandpaVote::NotAuthority
+		);
+
+		let state = round(vec![], vec![alice.clone()]);
+		assert_eq!(state.vote(&authorities, &alice), GrandpaVote::Prevoted);
+	}
+
+	#[test]
+	fn authority_keys_follow_the_validator_order() {
+		let babe_set = vec![(babe(Sr25519Keyring::Alice), 1), (babe(Sr25519Keyring::Bob), 1)];
+		let grandpa_set =
+			vec![(grandpa(Ed25519Keyring::Alice), 1), (grandpa(Ed25519Keyring::Bob), 1)];
+
+		assert_eq!(
+			authority_keys(1, 2, &babe_set, &grandpa_set),
+			Some(AuthorityKeys {
+				babe: babe(Sr25519Keyring::Bob),
+				grandpa: grandpa(Ed25519Keyring::Bob)
+			})
+		);
+		// sets enacted for another validator set cannot be matched by position.
+		assert_eq!(authority_keys(1, 3, &babe_set, &grandpa_set), None);
+		assert_eq!(authority_keys(1, 2, &babe_set, &grandpa_set[..1]), None);
+	}
+
+	#[test]
+	fn next_claim_is_the_first_slot_left() {
+		let claims =
+			EpochAuthorship { primary: vec![10, 30], secondary: vec![5], secondary_vrf: vec![20] };
+		assert_eq!(claims.next_claim(0), Some(5));
+		assert_eq!(claims.next_claim(10), Some(20));
+		assert_eq!(claims.next_claim(30), None);
+	}
+
+	#[test]
+	fn rpc_responses_are_parsed() {
+		let alice = grandpa(Ed25519Keyring::Alice);
+		let state: ReportedRoundStates = serde_json::from_value(json!({
+			"setId": 3,
+			"best": {
+				"round": 7,
+				"totalWeight": 2,
+				"thresholdWeight": 2,
+				"prevotes": { "currentWeight": 1, "missing": [] },
+				"precommits": { "currentWeight": 1, "missing": [alice.to_ss58check()] }
+			},
+			"background": []
+		}))
+		.unwrap();
+		assert_eq!((state.set_id, state.best.round), (3, 7));
+		assert_eq!(state.best.precommits.missing, vec![alice]);
+
+		let key = babe(Sr25519Keyring::Alice).to_ss58check();
+		let authorship: HashMap<BabeId, EpochAuthorship> =
+			serde_json::from_value(json!({ key: { "primary": [4], "secondary_vrf": [9] } }))
+				.unwrap();
+		assert_eq!(
+			authorship[&babe(Sr25519Keyring::Alice)],
+			EpochAuthorship { primary: vec![4], secondary: vec![], secondary_vrf: vec![9] }
+		);
+
+		let header: Header = serde_json::from_value(json!({ "number": "0x1a" })).unwrap();
+		assert_eq!(header.number, 26);
+	}
+}
@@ -0,0 +1,349 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Runs the Ethereum relayer against a mock Ethereum JSON-RPC server.
+
+use std::sync::{
+	atomic::{AtomicU64, Ordering},
+	Arc, Mutex,
+};
+
+use futures::future::BoxFuture;
+use jsonrpc_core::{IoHandler, Params, Value};
+use jsonrpc_http_server::{Server, ServerBuilder};
+use polkadex_node::eth_relayer::{
+	ethereum::EthereumClient,
+	receipt_proof::{self, Log, Receipt},
+	BridgeState, Error, RelayCall, Relayer, Submitter,
+};
+use polkadex_runtime_api::bridge::EthereumHeaderId;
+use serde_json::json;
+use sp_core::{hashing::keccak_256, H160, H256};
+
+const CHANNEL: H160 = H160([0x11; 20]);
+
+#[derive(Clone, Default)]
+struct MockSubmitter(Arc<Mutex<Vec<RelayCall>>>, Option<BridgeState>);
+
+impl Submitter for MockSubmitter {
+	fn submit(&self, call: RelayCall) -> BoxFuture<'static, Result<(), Error>> {
+		self.0.lock().unwrap().push(call);
+		Box::pin(async { Ok(()) })
+	}
+
+	fn bridge_state(&self, source_channel: H160) -> Option<BridgeState> {
+		assert_eq!(source_channel, CHANNEL);
+		self.1.clone()
+	}
+}
+
+fn block_hash(chain: &Chain, number: u64) -> H256 {
+	let mut hash = H256::repeat_byte(number as u8 + 1);
+	if number >= chain.fork_from.load(Ordering::SeqCst) {
+		hash.0[0] = 0xff;
+	}
+	hash
+}
+
+fn tx_hash(number: u64, index: u64) -> H256 {
+	H256::from(keccak_256(&[number as u8, index as u8]))
+}
+
+/// ABI encoded data of a `Message(address source, uint64 nonce, bytes payload)` event.
+fn message_data(nonce: u64) -> Vec<u8> {
+	let mut data = vec![0u8; 160];
+	data[12..32].copy_from_slice(&[0x22; 20]);
+	data[56..64].copy_from_slice(&nonce.to_be_bytes());
+	data[95] = 0x60;
+	data[127] = 3;
+	data[128..131].copy_from_slice(&[1, 2, 3]);
+	data
+}
+
+fn message(nonce: u64) -> Log {
+	Log { address: CHANNEL, topics: vec![H256::repeat_byte(0xaa)], data: message_data(nonce) }
+}
+
+/// Receipts of the mock chain: block 1 holds two transactions, the second one emitting
+/// message 1 from the channel contract, and both transactions of block 3 emit a message.
+fn receipts(number: u64) -> Vec<Receipt> {
+	let receipt = |gas, logs| Receipt {
+		tx_type: 0,
+		status: 1,
+		cumulative_gas_used: gas,
+		logs_bloom: vec![0u8; 256],
+		logs,
+	};
+	match number {
+		1 => vec![
+			receipt(21_000, vec![]),
+			receipt(80_000, vec![message(1)]),
+		],
+		3 => vec![receipt(60_000, vec![message(2)]), receipt(120_000, vec![message(3)])],
+		_ => vec![],
+	}
+}
+
+fn encoded_receipts(number: u64) -> Vec<Vec<u8>> {
+	receipts(number).iter().map(receipt_proof::encode_receipt).collect()
+}
+
+fn rpc_log(number: u64, index: u64, log: &Log) -> Value {
+	json!({
+		"address": log.address,
+		"topics": log.topics,
+		"data": format!("0x{}", hex::encode(&log.data)),
+		"blockNumber": format!("0x{:x}", number),
+		"transactionIndex": format!("0x{:x}", index),
+	})
+}
+
+/// Mock Ethereum chain served over JSON-RPC.
+struct Chain {
+	head: AtomicU64,
+	/// Blocks from this height on are replaced by a fork.
+	fork_from: AtomicU64,
+	/// Length in bytes of the logs bloom of every block.
+	bloom_len: usize,
+}
+
+impl Chain {
+	fn new(head: u64) -> Arc<Self> {
+		Arc::new(Chain {
+			head: AtomicU64::new(head),
+			fork_from: AtomicU64::new(u64::MAX),
+			bloom_len: 256,
+		})
+	}
+
+	fn head(&self) -> u64 {
+		self.head.load(Ordering::SeqCst)
+	}
+}
+
+fn rpc_block(chain: &Chain, number: u64) -> Value {
+	let transactions: Vec<_> = (0..receipts(number).len() as u64).map(|i| tx_hash(number, i)).collect();
+	json!({
+		"hash": block_hash(chain, number),
+		"parentHash": if number == 0 { H256::zero() } else { block_hash(chain, number - 1) },
+		"number": format!("0x{:x}", number),
+		"timestamp": format!("0x{:x}", 1_631_299_273 + 15 * number),
+		"miner": H160::zero(),
+		"transactionsRoot": H256::zero(),
+		"sha3Uncles": H256::zero(),
+		"extraData": "0x",
+		"stateRoot": H256::zero(),
+		"receiptsRoot": receipt_proof::receipts_root(&encoded_receipts(number)),
+		"logsBloom": format!("0x{}", "00".repeat(chain.bloom_len)),
+		"gasUsed": "0x0",
+		"gasLimit": "0x7a1200",
+		"difficulty": "0x1",
+		"mixHash": H256::zero(),
+		"nonce": "0x0000000000000000",
+		"transactions": transactions,
+	})
+}
+
+fn mock_server(chain: Arc<Chain>) -> Server {
+	let mut io = IoHandler::default();
+	let c = chain.clone();
+	io.add_sync_method("eth_blockNumber", move |_| Ok(json!(format!("0x{:x}", c.head()))));
+	let c = chain.clone();
+	io.add_sync_method("eth_getBlockByNumber", move |params: Params| {
+		let (number, _): (String, bool) = params.parse()?;
+		let number = u64::from_str_radix(number.trim_start_matches("0x"), 16).unwrap();
+		Ok(rpc_block(&c, number))
+	});
+	let c = chain.clone();
+	io.add_sync_method("eth_getLogs", move |params: Params| {
+		let (filter,): (Value,) = params.parse()?;
+		let block = |key: &str| {
+			let number = filter[key].as_str().unwrap_or("0x0").trim_start_matches("0x");
+			u64::from_str_radix(number, 16).unwrap()
+		};
+		let logs: Vec<_> = (block("fromBlock")..=block("toBlock").min(c.head()))
+			.flat_map(|number| {
+				receipts(number)
+					.into_iter()
+					.enumerate()
+					.flat_map(move |(index, receipt)| {
+						receipt
+							.logs
+							.into_iter()
+							.map(move |log| rpc_log(number, index as u64, &log))
+					})
+					.collect::<Vec<_>>()
+			})
+			.collect();
+		Ok(Value::Array(logs))
+	});
+	io.add_sync_method("eth_getTransactionReceipt", move |params: Params| {
+		let (tx,): (H256,) = params.parse()?;
+		for number in 0..=chain.head() {
+			for (index, receipt) in receipts(number).iter().enumerate() {
+				if tx_hash(number, index as u64) == tx {
+					return Ok(json!({
+						"status": format!("0x{:x}", receipt.status),
+						"cumulativeGasUsed": format!("0x{:x}", receipt.cumulative_gas_used),
+						"logsBloom": format!("0x{}", hex::encode(&receipt.logs_bloom)),
+						"logs": receipt.logs.iter().map(|log| rpc_log(number, index as u64, log)).collect::<Vec<_>>(),
+					}))
+				}
+			}
+		}
+		Ok(Value::Null)
+	});
+
+	ServerBuilder::new(io)
+		.start_http(&"127.0.0.1:0".parse().unwrap())
+		.expect("Mock server starts")
+}
+
+#[tokio::test]
+async fn relays_headers_and_finalized_messages() {
+	let chain = Chain::new(2);
+	let server = mock_server(chain.clone());
+	let ethereum = EthereumClient::connect(&format!("http://{}", server.address()))
+		.await
+		.expect("Connects to the mock server");
+	let submitter = MockSubmitter::default();
+	let mut relayer = Relayer::new(ethereum, submitter.clone(), CHANNEL, 0);
+
+	relayer.poll().await.expect("Relays from the mock server");
+
+	let calls = submitter.0.lock().unwrap().clone();
+	assert_eq!(calls.len(), 4);
+	for (number, call) in calls.iter().take(3).enumerate() {
+		match call {
+			RelayCall::ImportHeader(header) => {
+				assert_eq!(header.number, number as u64);
+				assert_eq!(header.receipts_root, receipt_proof::receipts_root(&encoded_receipts(number as u64)));
+			},
+			other => panic!("Expected a header, got {:?}", other),
+		}
+	}
+	match &calls[3] {
+		RelayCall::SubmitMessage(message) => {
+			let log = &receipts(1)[1].logs[0];
+			assert_eq!(message.data, receipt_proof::encode_log(log));
+			assert_eq!(message.proof.block_hash, block_hash(&chain, 1));
+			assert_eq!(message.proof.tx_index, 1);
+			let (keys, nodes) = &message.proof.data;
+			assert_eq!(keys[0], receipt_proof::receipts_root(&encoded_receipts(1)).as_bytes().to_vec());
+			assert_eq!(keys.len(), nodes.len());
+		},
+		other => panic!("Expected a message, got {:?}", other),
+	}
+
+	// Nothing new on the Ethereum side: nothing is relayed twice.
+	relayer.poll().await.expect("Polls the mock server");
+	assert_eq!(submitter.0.lock().unwrap().len(), 4);
+
+	server.close();
+}
+
+#[tokio::test]
+async fn rejects_malformed_logs_bloom() {
+	let server = mock_server(Arc::new(Chain {
+		head: AtomicU64::new(0),
+		fork_from: AtomicU64::new(u64::MAX),
+		bloom_len: 255,
+	}));
+	let ethereum = EthereumClient::connect(&format!("http://{}", server.address()))
+		.await
+		.expect("Connects to the mock server");
+	let submitter = MockSubmitter::default();
+	let mut relayer = Relayer::new(ethereum, submitter.clone(), CHANNEL, 0);
+
+	assert!(matches!(relayer.poll().await, Err(Error::InvalidHeader(0))));
+	assert!(submitter.0.lock().unwrap().is_empty());
+
+	server.close();
+}
+
+#[tokio::test]
+async fn follows_reorgs_from_the_common_ancestor() {
+	let chain = Chain::new(4);
+	let server = mock_server(chain.clone());
+	let ethereum = EthereumClient::connect(&format!("http://{}", server.address()))
+		.await
+		.expect("Connects to the mock server");
+	let submitter = MockSubmitter::default();
+	let mut relayer = Relayer::new(ethereum, submitter.clone(), CHANNEL, 0);
+	relayer.poll().await.expect("Relays from the mock server");
+	let relayed = submitter.0.lock().unwrap().len();
+
+	// Blocks 3 and 4 are replaced by a longer fork.
+	chain.fork_from.store(3, Ordering::SeqCst);
+	chain.head.store(5, Ordering::SeqCst);
+	relayer.poll().await.expect("Follows the fork");
+
+	let calls = submitter.0.lock().unwrap()[relayed..].to_vec();
+	let imported: Vec<_> = calls
+		.iter()
+		.map(|call| match call {
+			RelayCall::ImportHeader(header) => (header.number, header.parent_hash),
+			other => panic!("Expected a header, got {:?}", other),
+		})
+		.collect();
+	assert_eq!(
+		imported,
+		vec![
+			(3, block_hash(&chain, 2)),
+			(4, block_hash(&chain, 3)),
+			(5, block_hash(&chain, 4)),
+		],
+	);
+
+	server.close();
+}
+
+#[tokio::test]
+async fn resumes_after_the_last_accepted_message() {
+	let chain = Chain::new(5);
+	let server = mock_server(chain.clone());
+	let ethereum = EthereumClient::connect(&format!("http://{}", server.address()))
+		.await
+		.expect("Connects to the mock server");
+	let state = BridgeState {
+		best: EthereumHeaderId { number: 4, hash: block_hash(&chain, 4) },
+		oldest_header: 0,
+		accepted_nonce: 2,
+	};
+	let submitter = MockSubmitter(Default::default(), Some(state.clone()));
+	let mut relayer = Relayer::resume(ethereum, submitter.clone(), CHANNEL, state)
+		.await
+		.expect("Finds the last accepted message");
+
+	relayer.poll().await.expect("Relays from the mock server");
+
+	// Message 2 was accepted, message 3 of the same block was not.
+	let calls = submitter.0.lock().unwrap().clone();
+	assert_eq!(calls.len(), 2);
+	assert!(matches!(&calls[0], RelayCall::ImportHeader(header) if header.number == 5));
+	match &calls[1] {
+		RelayCall::SubmitMessage(message) => {
+			assert_eq!(message.data, receipt_proof::encode_log(&receipts(3)[1].logs[0]));
+			assert_eq!(message.proof.block_hash, block_hash(&chain, 3));
+			assert_eq!(message.proof.tx_index, 1);
+		},
+		other => panic!("Expected a message, got {:?}", other),
+	}
+
+	server.close();
+}
@@ -0,0 +1,83 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! Runs a development node sealing blocks on an interval.
+
+use std::{
+	process::{Child, Command, Stdio},
+	time::Duration,
+};
+
+use jsonrpc_core::Params;
+use jsonrpc_core_client::{transports::http, RawClient};
+use serde_json::{json, Value};
+
+const RPC_PORT: u16 = 45_933;
+
+/// Kills the node when the test ends, whatever the outcome.
+struct Node(Child);
+
+impl Drop for Node {
+	fn drop(&mut self) {
+		let _ = self.0.kill();
+		let _ = self.0.wait();
+	}
+}
+
+async fn call(client: &RawClient, method: &str, params: Value) -> Option<Value> {
+	let params = match params {
+		Value::Array(params) => Params::Array(params),
+		_ => Params::None,
+	};
+	client.call_method(method, params).await.ok()
+}
+
+/// Number of the block with hash `hash`, as known to the node.
+async fn block_number(client: &RawClient, hash: Value) -> Option<u64> {
+	let header = call(client, "chain_getHeader", json!([hash])).await?;
+	u64::from_str_radix(header["number"].as_str()?.trim_start_matches("0x"), 16).ok()
+}
+
+#[tokio::test]
+async fn interval_sealing_produces_finalized_blocks() {
+	let _node = Node(
+		Command::new(env!("CARGO_BIN_EXE_polkadex-node"))
+			.args(&["--dev", "--tmp", "--sealing", "interval=200", "--port", "0"])
+			.args(&["--rpc-port", &RPC_PORT.to_string(), "--no-prometheus", "--no-telemetry"])
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.spawn()
+			.expect("Starts the node"),
+	);
+
+	let mut finalized = 0;
+	for _ in 0..120 {
+		tokio::time::sleep(Duration::from_millis(500)).await;
+		let client =
+			match http::connect::<RawClient>(&format!("http://127.0.0.1:{}", RPC_PORT)).await {
+				Ok(client) => client,
+				Err(_) => continue,
+			};
+		if let Some(hash) = call(&client, "chain_getFinalizedHead", json!([])).await {
+			finalized = block_number(&client, hash).await.unwrap_or_default();
+		}
+		if finalized >= 2 {
+			break
+		}
+	}
+	assert!(finalized >= 2, "No sealed block was finalized, finalized head is #{}", finalized);
+}
@@ -0,0 +1,193 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # Dispatch Whitelist Pallet
+//!
+//! Governance managed whitelist for calls arriving from Ethereum through the bridge.
+//!
+//! Entries are keyed by the pallet index and call index of the SCALE encoded `Call`
+//! and may optionally be restricted to a single source contract. The pallet sits in
+//! front of the real message dispatcher: rejected messages never reach it and an
+//! event with the reason of the rejection is emitted instead.
+//!
+//! Chains that started before the whitelist existed get `InitialWhitelist` inserted by the
+//! runtime upgrade adding it, so that the calls they already accepted keep working.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use codec::{Decode, Encode};
+use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure};
+use frame_support::{pallet_prelude::*, RuntimeDebug};
+use snowbridge_core::MessageDispatch;
+use sp_core::H160;
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+/// Reason for which a bridge message was not dispatched.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum RejectionReason {
+	/// The payload is too short to carry a pallet and call index.
+	InvalidPayload,
+	/// The call is not present in the whitelist.
+	CallNotWhitelisted,
+	/// The call is whitelisted, but only for another source contract.
+	SourceNotAllowed,
+}
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Config: frame_system::Config {
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+	/// Identifier of the bridge message being dispatched
+	type MessageId: Parameter;
+	/// Dispatcher that accepted messages are forwarded to
+	type Dispatcher: MessageDispatch<Self, Self::MessageId>;
+	/// Origin allowed to change the whitelist
+	type WhitelistOrigin: EnsureOrigin<Self::Origin>;
+	/// Calls whitelisted by the runtime upgrade when the whitelist is empty
+	type InitialWhitelist: Get<Vec<(u8, u8, Option<H160>)>>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as DispatchWhitelist {
+		/// Whitelisted calls by (pallet index, call index), with an optional source contract
+		/// restriction. `Some(None)` allows the call from any source contract.
+		pub Whitelist get(fn whitelist):
+			double_map hasher(twox_64_concat) u8, hasher(twox_64_concat) u8 => Option<Option<H160>>;
+	}
+	add_extra_genesis {
+		config(whitelist): Vec<(u8, u8, Option<H160>)>;
+		build(|config: &GenesisConfig| {
+			for (pallet_index, call_index, source) in config.whitelist.iter() {
+				Whitelist::insert(pallet_index, call_index, source);
+			}
+		});
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		MessageId = <T as Config>::MessageId,
+	{
+		/// A call was added to the whitelist. [pallet_index, call_index, source]
+		CallWhitelisted(u8, u8, Option<H160>),
+		/// A call was removed from the whitelist. [pallet_index, call_index]
+		CallRemoved(u8, u8),
+		/// A bridge message was not dispatched. [message_id, source, reason]
+		DispatchRejected(MessageId, H160, RejectionReason),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Config> {
+		/// The call is not present in the whitelist.
+		NotWhitelisted,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		fn on_runtime_upgrade() -> Weight {
+			// Chains that started before this pallet was added have an empty whitelist.
+			if Whitelist::iter().next().is_some() {
+				return T::DbWeight::get().reads(1)
+			}
+			let whitelist = T::InitialWhitelist::get();
+			for (pallet_index, call_index, source) in whitelist.iter() {
+				Whitelist::insert(pallet_index, call_index, source);
+			}
+			T::DbWeight::get().reads_writes(1, whitelist.len() as Weight)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			ensure!(
+				T::InitialWhitelist::get().is_empty() || Whitelist::iter().next().is_some(),
+				"The whitelist is empty after the upgrade"
+			);
+			Ok(())
+		}
+
+		/// Allow the call identified by `pallet_index` and `call_index` to be dispatched from
+		/// Ethereum, optionally only when the message originates from `source`.
+		#[weight = T::DbWeight::get().writes(1)]
+		pub fn whitelist_call(origin, pallet_index: u8, call_index: u8, source: Option<H160>) -> DispatchResult {
+			T::WhitelistOrigin::ensure_origin(origin)?;
+
+			Whitelist::insert(pallet_index, call_index, source);
+			Self::deposit_event(RawEvent::CallWhitelisted(pallet_index, call_index, source));
+			Ok(())
+		}
+
+		/// Remove the call identified by `pallet_index` and `call_index` from the whitelist.
+		#[weight = T::DbWeight::get().reads_writes(1, 1)]
+		pub fn remove_call(origin, pallet_index: u8, call_index: u8) -> DispatchResult {
+			T::WhitelistOrigin::ensure_origin(origin)?;
+			ensure!(Whitelist::contains_key(pallet_index, call_index), Error::<T>::NotWhitelisted);
+
+			Whitelist::remove(pallet_index, call_index);
+			Self::deposit_event(RawEvent::CallRemoved(pallet_index, call_index));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Checks whether an encoded call may be dispatched on behalf of `source`.
+	///
+	/// The first two bytes of an encoded `Call` are its pallet index and call index.
+	pub fn check(source: Option<H160>, encoded_call: &[u8]) -> Result<(), RejectionReason> {
+		let (pallet_index, call_index) = match encoded_call {
+			[pallet_index, call_index, ..] => (*pallet_index, *call_index),
+			_ => return Err(RejectionReason::InvalidPayload),
+		};
+		match (Whitelist::get(pallet_index, call_index), source) {
+			(None, _) => Err(RejectionReason::CallNotWhitelisted),
+			(Some(Some(allowed)), Some(source)) if allowed != source =>
+				Err(RejectionReason::SourceNotAllowed),
+			_ => Ok(()),
+		}
+	}
+
+	/// Returns true if the call is whitelisted for at least one source contract.
+	pub fn is_whitelisted(encoded_call: &[u8]) -> bool {
+		Self::check(None, encoded_call).is_ok()
+	}
+}
+
+impl<T: Config> MessageDispatch<T, T::MessageId> for Module<T> {
+	fn dispatch(source: H160, id: T::MessageId, payload: &[u8]) {
+		match Self::check(Some(source), payload) {
+			Ok(()) => T::Dispatcher::dispatch(source, id, payload),
+			Err(reason) => Self::deposit_event(RawEvent::DispatchRejected(id, source, reason)),
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn successful_dispatch_event(id: T::MessageId) -> Option<<T as frame_system::Config>::Event> {
+		T::Dispatcher::successful_dispatch_event(id)
+	}
+}
@@ -0,0 +1,234 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use super::*;
+use crate as dispatch_whitelist;
+use frame_support::{
+	assert_noop, assert_ok, parameter_types,
+	traits::{Everything, OnRuntimeUpgrade},
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	BuildStorage,
+};
+use std::cell::RefCell;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		DispatchWhitelist: dispatch_whitelist::{Pallet, Call, Storage, Config, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub InitialWhitelist: Vec<(u8, u8, Option<H160>)> = vec![(7, 0, None), (7, 1, Some(contract(3)))];
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+thread_local! {
+	static DISPATCHED: RefCell<Vec<(H160, u64, Vec<u8>)>> = RefCell::new(Vec::new());
+}
+
+/// Records the messages that made it through the whitelist.
+pub struct RecordDispatch;
+
+impl MessageDispatch<Test, u64> for RecordDispatch {
+	fn dispatch(source: H160, id: u64, payload: &[u8]) {
+		DISPATCHED.with(|d| d.borrow_mut().push((source, id, payload.to_vec())));
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn successful_dispatch_event(_: u64) -> Option<Event> {
+		None
+	}
+}
+
+impl Config for Test {
+	type Event = Event;
+	type MessageId = u64;
+	type Dispatcher = RecordDispatch;
+	type WhitelistOrigin = EnsureRoot<u64>;
+	type InitialWhitelist = InitialWhitelist;
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut ext: sp_io::TestExternalities = GenesisConfig {
+		dispatch_whitelist: DispatchWhitelistConfig { whitelist: vec![(7, 0, None)] },
+		..Default::default()
+	}
+	.build_storage()
+	.unwrap()
+	.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn contract(n: u8) -> H160 {
+	H160::repeat_byte(n)
+}
+
+fn dispatched() -> Vec<(H160, u64, Vec<u8>)> {
+	DISPATCHED.with(|d| d.borrow_mut().drain(..).collect())
+}
+
+fn last_event() -> Event {
+	System::events().pop().expect("an event was deposited").event
+}
+
+#[test]
+fn whitelist_is_managed_by_governance() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(DispatchWhitelist::whitelist_call(Origin::signed(1), 8, 1, None), BadOrigin);
+		assert_noop!(DispatchWhitelist::remove_call(Origin::signed(1), 7, 0), BadOrigin);
+
+		assert_ok!(DispatchWhitelist::whitelist_call(Origin::root(), 8, 1, Some(contract(1))));
+		assert_eq!(DispatchWhitelist::whitelist(8, 1), Some(Some(contract(1))));
+		assert_eq!(
+			last_event(),
+			Event::DispatchWhitelist(RawEvent::CallWhitelisted(8, 1, Some(contract(1))))
+		);
+
+		assert_ok!(DispatchWhitelist::remove_call(Origin::root(), 8, 1));
+		assert_eq!(DispatchWhitelist::whitelist(8, 1), None);
+		assert_eq!(last_event(), Event::DispatchWhitelist(RawEvent::CallRemoved(8, 1)));
+		assert_noop!(
+			DispatchWhitelist::remove_call(Origin::root(), 8, 1),
+			Error::<Test>::NotWhitelisted
+		);
+	})
+}
+
+#[test]
+fn calls_are_matched_by_pallet_and_call_index() {
+	new_test_ext().execute_with(|| {
+		assert!(DispatchWhitelist::is_whitelisted(&[7, 0]));
+		assert!(DispatchWhitelist::is_whitelisted(&[7, 0, 42, 42]));
+		assert!(!DispatchWhitelist::is_whitelisted(&[7, 1]));
+		assert!(!DispatchWhitelist::is_whitelisted(&[0, 7]));
+		assert_eq!(DispatchWhitelist::check(None, &[7]), Err(RejectionReason::InvalidPayload));
+		assert_eq!(
+			DispatchWhitelist::check(Some(contract(1)), &[8, 0]),
+			Err(RejectionReason::CallNotWhitelisted)
+		);
+	})
+}
+
+#[test]
+fn source_contract_restriction_is_enforced() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DispatchWhitelist::whitelist_call(Origin::root(), 8, 1, Some(contract(1))));
+
+		assert_eq!(DispatchWhitelist::check(Some(contract(1)), &[8, 1]), Ok(()));
+		assert_eq!(
+			DispatchWhitelist::check(Some(contract(2)), &[8, 1]),
+			Err(RejectionReason::SourceNotAllowed)
+		);
+		// unrestricted entries accept any source contract.
+		assert_eq!(DispatchWhitelist::check(Some(contract(2)), &[7, 0]), Ok(()));
+		// a restricted call still counts as whitelisted for the call filter.
+		assert!(DispatchWhitelist::is_whitelisted(&[8, 1]));
+	})
+}
+
+#[test]
+fn whitelisted_messages_reach_the_dispatcher() {
+	new_test_ext().execute_with(|| {
+		DispatchWhitelist::dispatch(contract(1), 1, &[7, 0, 1]);
+
+		assert_eq!(dispatched(), vec![(contract(1), 1, vec![7, 0, 1])]);
+		assert!(System::events().is_empty());
+	})
+}
+
+#[test]
+fn rejected_messages_emit_the_reason() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DispatchWhitelist::whitelist_call(Origin::root(), 8, 1, Some(contract(1))));
+
+		let cases = vec![
+			(vec![7], contract(1), RejectionReason::InvalidPayload),
+			(vec![9, 0], contract(1), RejectionReason::CallNotWhitelisted),
+			(vec![8, 1], contract(2), RejectionReason::SourceNotAllowed),
+		];
+		for (id, (payload, source, reason)) in cases.into_iter().enumerate() {
+			DispatchWhitelist::dispatch(source, id as u64, &payload);
+			assert_eq!(
+				last_event(),
+				Event::DispatchWhitelist(RawEvent::DispatchRejected(id as u64, source, reason))
+			);
+		}
+		assert!(dispatched().is_empty());
+	})
+}
+
+#[test]
+fn upgrade_seeds_an_empty_whitelist() {
+	sp_io::TestExternalities::default().execute_with(|| {
+		assert!(!DispatchWhitelist::is_whitelisted(&[7, 0]));
+
+		DispatchWhitelist::on_runtime_upgrade();
+
+		assert_eq!(DispatchWhitelist::whitelist(7, 0), Some(None));
+		assert_eq!(DispatchWhitelist::whitelist(7, 1), Some(Some(contract(3))));
+	})
+}
+
+#[test]
+fn upgrade_keeps_an_existing_whitelist() {
+	new_test_ext().execute_with(|| {
+		DispatchWhitelist::on_runtime_upgrade();
+
+		assert_eq!(DispatchWhitelist::whitelist(7, 0), Some(None));
+		assert_eq!(DispatchWhitelist::whitelist(7, 1), None);
+	})
+}
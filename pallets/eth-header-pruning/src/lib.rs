@@ -0,0 +1,180 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # Ethereum Header Pruning Pallet
+//!
+//! Bounds the storage used by the Ethereum light client, which keeps every imported
+//! header forever.
+//!
+//! Only `HeadersToKeep` block heights below the latest finalized header are retained.
+//! Unfinalized headers are never touched and the window must be at least
+//! `DescendantsUntilFinalized` deep, so any header that may still become part of the
+//! canonical chain survives. At most `MaxPrunedPerBlock` heights are removed per block,
+//! including while draining the headers accumulated before the pallet was added.
+//!
+//! The light client storage is private, so it is accessed through raw storage using the
+//! layout of `snowbridge-ethereum-light-client`:
+//! - `FinalizedBlock: EthereumHeaderId`
+//! - `Headers: map hasher(identity) H256 => Option<StoredHeader>`
+//! - `HeadersByNumber: map hasher(twox_64_concat) u64 => Option<Vec<H256>>`
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use codec::Encode;
+use frame_support::{
+	decl_module, decl_storage,
+	pallet_prelude::*,
+	storage::migration::{get_storage_value, remove_storage_prefix, take_storage_item},
+	Twox64Concat,
+};
+use snowbridge_ethereum_light_client::EthereumHeaderId;
+use sp_core::H256;
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Config: frame_system::Config {
+	/// Storage prefix of the Ethereum light client pallet
+	type LightClientPrefix: Get<&'static [u8]>;
+	/// Number of descendants a header needs to be finalized by the light client
+	type DescendantsUntilFinalized: Get<u8>;
+	/// Number of block heights kept below the latest finalized header
+	type HeadersToKeep: Get<u64>;
+	/// Maximum number of block heights pruned in a single block
+	type MaxPrunedPerBlock: Get<u64>;
+	/// Number of the initial header of the light client, below which it stores no headers
+	type OldestHeader: Get<u64>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as EthereumHeaderPruning {
+		/// Lowest block height that may still have headers in the light client.
+		pub OldestUnpruned get(fn oldest_unpruned): Option<u64>;
+	}
+	add_extra_genesis {
+		build(|_config: &GenesisConfig| {
+			OldestUnpruned::put(Module::<T>::finalized_number());
+		});
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		/// Number of block heights kept below the latest finalized header.
+		const HeadersToKeep: u64 = T::HeadersToKeep::get();
+
+		/// Maximum number of block heights pruned in a single block.
+		const MaxPrunedPerBlock: u64 = T::MaxPrunedPerBlock::get();
+
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			Self::prune(T::MaxPrunedPerBlock::get())
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			// Chains that started before this pallet was added have no pruning cursor yet.
+			if OldestUnpruned::exists() {
+				return T::DbWeight::get().reads(1)
+			}
+			Self::initialize_cursor()
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			let oldest = OldestUnpruned::get().ok_or("Pruning cursor not initialized")?;
+			ensure!(
+				Self::stored_heights().all(|number| number >= oldest),
+				"Headers below the pruning cursor would never be pruned"
+			);
+			Ok(())
+		}
+
+		fn integrity_test() {
+			assert!(
+				T::HeadersToKeep::get() >= T::DescendantsUntilFinalized::get() as u64,
+				"HeadersToKeep must cover DescendantsUntilFinalized"
+			);
+			assert!(T::MaxPrunedPerBlock::get() > 0, "MaxPrunedPerBlock must not be zero");
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Number of the latest header finalized by the light client.
+	pub fn finalized_number() -> u64 {
+		get_storage_value::<EthereumHeaderId>(T::LightClientPrefix::get(), b"FinalizedBlock", &[])
+			.map(|finalized| finalized.number)
+			.unwrap_or_default()
+	}
+
+	/// Block heights below this number are outside of the retention window.
+	pub fn prune_before() -> u64 {
+		Self::finalized_number().saturating_sub(T::HeadersToKeep::get())
+	}
+
+	/// Removes the headers at `number` and returns how many were removed.
+	fn prune_height(number: u64) -> u64 {
+		let prefix = T::LightClientPrefix::get();
+		let hashes =
+			take_storage_item::<u64, Vec<H256>, Twox64Concat>(prefix, b"HeadersByNumber", number)
+				.unwrap_or_default();
+		for hash in hashes.iter() {
+			remove_storage_prefix(prefix, b"Headers", &hash.encode());
+		}
+		hashes.len() as u64
+	}
+
+	/// Prunes at most `max` block heights below the retention window.
+	fn prune(max: u64) -> Weight {
+		let prune_before = Self::prune_before();
+		let oldest = match OldestUnpruned::get() {
+			Some(oldest) => oldest,
+			None => return T::DbWeight::get().reads(2),
+		};
+		let end = prune_before.min(oldest.saturating_add(max));
+		if end <= oldest {
+			return T::DbWeight::get().reads(2)
+		}
+
+		let removed: u64 = (oldest..end).map(Self::prune_height).sum();
+		OldestUnpruned::put(end);
+		T::DbWeight::get().reads_writes(2 + end - oldest, 1 + end - oldest + removed)
+	}
+
+	/// Block heights that currently have headers in the light client.
+	#[cfg(any(test, feature = "try-runtime"))]
+	fn stored_heights() -> impl Iterator<Item = u64> {
+		let prefix = T::LightClientPrefix::get();
+		frame_support::storage::migration::storage_key_iter::<u64, Vec<H256>, Twox64Concat>(
+			prefix,
+			b"HeadersByNumber",
+		)
+		.map(|(number, _hashes)| number)
+	}
+
+	/// Points the pruning cursor at the initial header of the light client, so that the
+	/// headers accumulated before pruning was enabled are drained by `on_initialize` at
+	/// `MaxPrunedPerBlock` heights per block, empty heights included. Searching the lowest
+	/// stored height instead would read every stored height in the upgrade block.
+	fn initialize_cursor() -> Weight {
+		OldestUnpruned::put(T::OldestHeader::get());
+		T::DbWeight::get().reads_writes(1, 1)
+	}
+}
@@ -0,0 +1,220 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use crate as eth_header_pruning;
+use frame_support::{
+	parameter_types,
+	storage::migration::put_storage_value,
+	traits::{Everything, OnInitialize, OnRuntimeUpgrade},
+	StorageHasher,
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	BuildStorage,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		EthereumHeaderPruning: eth_header_pruning::{Pallet, Storage, Config},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const LightClientPrefix: &'static [u8] = b"EthereumLightClient";
+	pub const DescendantsUntilFinalized: u8 = 3;
+	pub const HeadersToKeep: u64 = 16;
+	pub const MaxPrunedPerBlock: u64 = 2;
+	pub const OldestHeader: u64 = 9_990;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+impl Config for Test {
+	type LightClientPrefix = LightClientPrefix;
+	type DescendantsUntilFinalized = DescendantsUntilFinalized;
+	type HeadersToKeep = HeadersToKeep;
+	type MaxPrunedPerBlock = MaxPrunedPerBlock;
+	type OldestHeader = OldestHeader;
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	GenesisConfig::default().build_storage().unwrap().into()
+}
+
+fn header_hash(number: u64, fork: u8) -> H256 {
+	let mut hash = H256::from_low_u64_be(number);
+	hash.0[0] = fork;
+	hash
+}
+
+/// Imports the headers of `number` the way the light client stores them, finalizing the
+/// header `DescendantsUntilFinalized` heights below.
+fn import(number: u64, forks: u8) {
+	let hashes: Vec<H256> = (0..forks).map(|fork| header_hash(number, fork)).collect();
+	for hash in hashes.iter() {
+		put_storage_value(LightClientPrefix::get(), b"Headers", &hash.encode(), vec![0u8; 500]);
+	}
+	put_storage_value(
+		LightClientPrefix::get(),
+		b"HeadersByNumber",
+		&Twox64Concat::hash(&number.encode()),
+		hashes,
+	);
+	let finalized = number.saturating_sub(DescendantsUntilFinalized::get() as u64);
+	put_storage_value(
+		LightClientPrefix::get(),
+		b"FinalizedBlock",
+		&[],
+		EthereumHeaderId { number: finalized, hash: header_hash(finalized, 0) },
+	);
+}
+
+fn has_header(number: u64, fork: u8) -> bool {
+	let hash = header_hash(number, fork);
+	get_storage_value::<Vec<u8>>(LightClientPrefix::get(), b"Headers", &hash.encode()).is_some()
+}
+
+fn stored_heights() -> Vec<u64> {
+	let mut heights: Vec<_> = EthereumHeaderPruning::stored_heights().collect();
+	heights.sort_unstable();
+	heights
+}
+
+#[test]
+fn storage_stays_bounded() {
+	new_test_ext().execute_with(|| {
+		OldestUnpruned::put(0);
+		let window = HeadersToKeep::get() + DescendantsUntilFinalized::get() as u64 + 1;
+		for number in 0..1_000 {
+			import(number, 2);
+			EthereumHeaderPruning::on_initialize(number + 1);
+			assert!(stored_heights().len() as u64 <= window);
+		}
+
+		let heights = stored_heights();
+		let finalized = 999 - DescendantsUntilFinalized::get() as u64;
+		assert_eq!(heights.first(), Some(&(finalized - HeadersToKeep::get())));
+		assert_eq!(heights.last(), Some(&999));
+		assert!(!has_header(900, 0) && !has_header(900, 1));
+		assert!(has_header(999, 0) && has_header(999, 1));
+	});
+}
+
+#[test]
+fn headers_within_window_are_kept() {
+	new_test_ext().execute_with(|| {
+		OldestUnpruned::put(0);
+		for number in 0..=HeadersToKeep::get() {
+			import(number, 1);
+		}
+		EthereumHeaderPruning::on_initialize(1);
+		assert_eq!(stored_heights().len() as u64, HeadersToKeep::get() + 1);
+	});
+}
+
+#[test]
+fn finalized_number_decodes_light_client_header_id() {
+	new_test_ext().execute_with(|| {
+		import(10_000, 1);
+		assert_eq!(EthereumHeaderPruning::finalized_number(), 10_000 - 3);
+	});
+}
+
+#[test]
+fn migration_drains_accumulated_headers_over_blocks() {
+	new_test_ext().execute_with(|| {
+		OldestUnpruned::kill();
+		for number in 10_000..10_200 {
+			import(number, 1);
+		}
+		EthereumHeaderPruning::on_runtime_upgrade();
+
+		// The migration only seeds the cursor, nothing is removed yet.
+		assert_eq!(OldestUnpruned::get(), Some(OldestHeader::get()));
+		assert_eq!(stored_heights().len(), 200);
+
+		// The migration only runs once.
+		import(10_200, 1);
+		EthereumHeaderPruning::on_runtime_upgrade();
+		assert_eq!(OldestUnpruned::get(), Some(OldestHeader::get()));
+
+		let prune_before = EthereumHeaderPruning::prune_before();
+		let mut block = 1;
+		while OldestUnpruned::get() < Some(prune_before) {
+			let stored = stored_heights().len() as u64;
+			let oldest = OldestUnpruned::get().unwrap();
+			EthereumHeaderPruning::on_initialize(block);
+			assert!(stored - stored_heights().len() as u64 <= MaxPrunedPerBlock::get());
+			// Empty heights below the stored headers count against the limit as well.
+			assert!(OldestUnpruned::get().unwrap() - oldest <= MaxPrunedPerBlock::get());
+			block += 1;
+		}
+		assert_eq!(stored_heights().first(), Some(&prune_before));
+		assert!(!has_header(10_000, 0));
+	});
+}
+
+#[test]
+fn migration_starts_at_the_oldest_header() {
+	new_test_ext().execute_with(|| {
+		OldestUnpruned::kill();
+		EthereumHeaderPruning::on_runtime_upgrade();
+		assert_eq!(OldestUnpruned::get(), Some(OldestHeader::get()));
+
+		// Nothing is pruned until the retention window moves past the oldest header.
+		import(OldestHeader::get() + HeadersToKeep::get(), 1);
+		EthereumHeaderPruning::on_initialize(1);
+		assert_eq!(OldestUnpruned::get(), Some(OldestHeader::get()));
+	});
+}
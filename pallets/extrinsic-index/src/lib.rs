@@ -0,0 +1,165 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # Extrinsic Index Pallet
+//!
+//! Lets nodes look extrinsics up by hash without scanning blocks.
+//!
+//! Once an extrinsic is dispatched, the [`IndexExtrinsic`] signed extension writes an
+//! [`ExtrinsicRecord`] of it to the offchain database through offchain indexing, under the
+//! [`storage_key`] of the extrinsic hash. Inherents and unsigned extrinsics pass through signed
+//! extensions as well, so they are recorded too. Nothing is kept in the runtime storage, and
+//! nothing is written unless the node runs with offchain indexing enabled.
+//!
+//! Offchain indexing is not fork aware. A record may describe a block that was retracted
+//! later, so readers have to check it against the canonical chain.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_module,
+	pallet_prelude::*,
+	weights::{DispatchInfo, PostDispatchInfo},
+};
+use sp_runtime::{
+	traits::{DispatchInfoOf, Dispatchable, Hash, PostDispatchInfoOf, SignedExtension},
+	DispatchResult,
+};
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+/// Prefix of the offchain storage keys of extrinsic records.
+pub const STORAGE_PREFIX: &[u8] = b"polkadex/extrinsic/";
+
+/// Offchain storage key of the record of the extrinsic with the given hash.
+pub fn storage_key(hash: &[u8]) -> Vec<u8> {
+	[STORAGE_PREFIX, hash].concat()
+}
+
+/// Where an extrinsic was included and what its dispatch cost.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct ExtrinsicRecord<BlockNumber, Balance> {
+	/// Number of the including block.
+	pub block: BlockNumber,
+	/// Index of the extrinsic in the block.
+	pub index: u32,
+	/// Result of the dispatch.
+	pub result: DispatchResult,
+	/// Fee paid for the extrinsic, including the tip.
+	pub fee: Balance,
+}
+
+/// Fee paid by an applied extrinsic.
+pub trait ComputeFee<Balance> {
+	/// Fee paid by the encoded `extrinsic`, dispatched with the actual weight of `info`.
+	fn compute_fee(extrinsic: &[u8], info: &DispatchInfo) -> Balance;
+}
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Config: frame_system::Config {
+	/// Balance type of the fees
+	type Balance: Parameter;
+	/// Computes the fee paid by an extrinsic
+	type ComputeFee: ComputeFee<Self::Balance>;
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {}
+}
+
+impl<T: Config> Module<T> {
+	/// Weight of recording a single extrinsic: reading its data and writing its record.
+	pub fn record_weight() -> Weight {
+		T::DbWeight::get().reads_writes(1, 1)
+	}
+
+	/// Record of the extrinsic being applied, dispatched as described by `info` and
+	/// `post_info` with `result`, along with its hash.
+	pub fn record(
+		info: &DispatchInfo,
+		post_info: &PostDispatchInfo,
+		result: &DispatchResult,
+	) -> (T::Hash, ExtrinsicRecord<T::BlockNumber, T::Balance>) {
+		let block = frame_system::Pallet::<T>::block_number();
+		let index = frame_system::Pallet::<T>::extrinsic_index().unwrap_or_default();
+		let extrinsic = frame_system::Pallet::<T>::extrinsic_data(index);
+		let actual = DispatchInfo {
+			weight: post_info.calc_actual_weight(info),
+			pays_fee: post_info.pays_fee(info),
+			..*info
+		};
+		let fee = T::ComputeFee::compute_fee(&extrinsic, &actual);
+		let record = ExtrinsicRecord { block, index, result: *result, fee };
+		(T::Hashing::hash(&extrinsic), record)
+	}
+}
+
+/// Records every dispatched extrinsic in the offchain database.
+///
+/// Encodes to nothing and signs nothing, so transactions encode the same with it.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct IndexExtrinsic<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> IndexExtrinsic<T> {
+	/// Create a new extension.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config + Send + Sync> sp_std::fmt::Debug for IndexExtrinsic<T> {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "IndexExtrinsic")
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for IndexExtrinsic<T>
+where
+	<T as frame_system::Config>::Call:
+		Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+{
+	const IDENTIFIER: &'static str = "IndexExtrinsic";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::Call;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn post_dispatch(
+		_pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		_len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		let (hash, record) = Module::<T>::record(info, post_info, result);
+		sp_io::offchain_index::set(&storage_key(hash.as_ref()), &record.encode());
+		frame_system::Pallet::<T>::register_extra_weight_unchecked(
+			Module::<T>::record_weight(),
+			DispatchClass::Mandatory,
+		);
+		Ok(())
+	}
+}
@@ -0,0 +1,173 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use crate as extrinsic_index;
+use frame_support::{
+	parameter_types,
+	traits::Everything,
+	weights::{Pays, RuntimeDbWeight, Weight},
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	BuildStorage, DispatchError,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		ExtrinsicIndex: extrinsic_index::{Pallet},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const DbWeight: RuntimeDbWeight = RuntimeDbWeight { read: 10, write: 100 };
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = DbWeight;
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+/// Charges one unit per byte and per unit of weight.
+pub struct LengthAndWeightFee;
+
+impl ComputeFee<u64> for LengthAndWeightFee {
+	fn compute_fee(extrinsic: &[u8], info: &DispatchInfo) -> u64 {
+		extrinsic.len() as u64 + info.weight
+	}
+}
+
+impl Config for Test {
+	type Balance = u64;
+	type ComputeFee = LengthAndWeightFee;
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	GenesisConfig::default().build_storage().unwrap().into()
+}
+
+/// Applies the encoded `extrinsic` the way the executive does, passing it through
+/// `IndexExtrinsic` once dispatched.
+fn apply(extrinsic: Vec<u8>, weight: Weight, actual_weight: Weight, success: bool) -> H256 {
+	let hash = BlakeTwo256::hash(&extrinsic);
+	let len = extrinsic.len();
+	System::note_extrinsic(extrinsic);
+	let info = DispatchInfo { weight, ..Default::default() };
+	let post_info = PostDispatchInfo { actual_weight: Some(actual_weight), pays_fee: Pays::Yes };
+	let result = if success { Ok(()) } else { Err(DispatchError::Other("failed")) };
+	IndexExtrinsic::<Test>::post_dispatch((), &info, &post_info, len, &result).unwrap();
+	let result = result
+		.map(|_| post_info)
+		.map_err(|error| sp_runtime::DispatchErrorWithPostInfo { post_info, error });
+	System::note_applied_extrinsic(&result, info);
+	hash
+}
+
+fn stored_record(
+	ext: &mut sp_io::TestExternalities,
+	hash: H256,
+) -> Option<ExtrinsicRecord<u64, u64>> {
+	ext.persist_offchain_overlay();
+	let record = ext.offchain_db().get(&storage_key(hash.as_ref()))?;
+	Some(Decode::decode(&mut &record[..]).unwrap())
+}
+
+#[test]
+fn applied_extrinsics_are_recorded() {
+	let mut ext = new_test_ext();
+	let (transfer, failed) = ext.execute_with(|| {
+		System::set_block_number(7);
+		System::note_finished_initialize();
+		(apply(vec![1; 10], 100, 100, true), apply(vec![2; 20], 100, 100, false))
+	});
+
+	assert_eq!(
+		stored_record(&mut ext, transfer),
+		Some(ExtrinsicRecord { block: 7, index: 0, result: Ok(()), fee: 110 })
+	);
+	assert_eq!(
+		stored_record(&mut ext, failed),
+		Some(ExtrinsicRecord {
+			block: 7,
+			index: 1,
+			result: Err(DispatchError::Other("failed")),
+			fee: 120,
+		})
+	);
+	assert_eq!(stored_record(&mut ext, H256::zero()), None);
+}
+
+#[test]
+fn fee_uses_the_actual_weight() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		System::note_finished_initialize();
+		System::note_extrinsic(vec![1; 10]);
+		let info = DispatchInfo { weight: 1_000, ..Default::default() };
+		let post_info = PostDispatchInfo { actual_weight: Some(50), pays_fee: Pays::Yes };
+
+		let (hash, record) = ExtrinsicIndex::record(&info, &post_info, &Ok(()));
+		assert_eq!(hash, BlakeTwo256::hash(&[1; 10]));
+		assert_eq!(record.fee, 60);
+	});
+}
+
+#[test]
+fn recording_is_charged_per_extrinsic() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		System::note_finished_initialize();
+		apply(vec![1; 10], 0, 0, true);
+		apply(vec![2; 10], 0, 0, true);
+
+		let weight = System::block_weight();
+		assert_eq!(*weight.get(DispatchClass::Mandatory), 2 * ExtrinsicIndex::record_weight());
+	});
+}
@@ -0,0 +1,157 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # Fee Tiers Pallet
+//!
+//! Discounts the trading fees of accounts by the amount of PDEX they stake.
+//!
+//! Governance sets a list of [`FeeTier`]s, ordered by the minimum stake. An account is in
+//! the highest tier whose minimum stake it reaches, and pays the flat fees of trading calls
+//! reduced by the discount of that tier. Where the stake is read from is left to the
+//! runtime through [`StakedBalance`].
+//!
+//! A discount of 100% makes trading calls free, so they fall under the free trade allowance
+//! of the trading fees pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure, pallet_prelude::*,
+	traits::EnsureOrigin,
+};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, Saturating},
+	DispatchResult, Perbill,
+};
+use sp_std::prelude::*;
+use trading_fees_pallet::FeeDiscount;
+
+#[cfg(test)]
+mod tests;
+
+/// A discount on trading fees for accounts staking at least `min_stake`.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct FeeTier<Balance> {
+	/// Stake required to reach the tier.
+	pub min_stake: Balance,
+	/// Discount on the flat fees of trading calls.
+	pub discount: Perbill,
+}
+
+/// Amount an account stakes towards its fee tier.
+pub trait StakedBalance<AccountId, Balance> {
+	/// Stake of `who`.
+	fn staked(who: &AccountId) -> Balance;
+}
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Config: frame_system::Config {
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+	/// Balance type of stakes and fees
+	type Balance: Parameter + AtLeast32BitUnsigned + Copy + Default;
+	/// Source of the stake of an account
+	type Staked: StakedBalance<Self::AccountId, Self::Balance>;
+	/// Maximum number of tiers
+	type MaxTiers: Get<u32>;
+	/// Origin allowed to change the tiers
+	type UpdateOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as FeeTiers {
+		/// Fee tiers, ordered by increasing minimum stake.
+		pub Tiers get(fn tiers): Vec<FeeTier<T::Balance>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		Balance = <T as Config>::Balance,
+	{
+		/// The fee tiers were replaced. [tiers]
+		TiersSet(Vec<FeeTier<Balance>>),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Config> {
+		/// More tiers than `MaxTiers` were given.
+		TooManyTiers,
+		/// The minimum stakes of the tiers do not strictly increase.
+		TiersNotSorted,
+		/// A tier gives a smaller discount than the tier below it.
+		DiscountDecreases,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// Maximum number of tiers.
+		const MaxTiers: u32 = T::MaxTiers::get();
+
+		fn deposit_event() = default;
+
+		/// Replace the fee tiers. Tiers have to be ordered by strictly increasing minimum
+		/// stake, and a tier may not give a smaller discount than the tier below it.
+		#[weight = T::DbWeight::get().writes(1)]
+		pub fn set_tiers(origin, tiers: Vec<FeeTier<T::Balance>>) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(tiers.len() <= T::MaxTiers::get() as usize, Error::<T>::TooManyTiers);
+			for pair in tiers.windows(2) {
+				ensure!(pair[0].min_stake < pair[1].min_stake, Error::<T>::TiersNotSorted);
+				ensure!(pair[0].discount <= pair[1].discount, Error::<T>::DiscountDecreases);
+			}
+
+			Tiers::<T>::put(&tiers);
+			Self::deposit_event(RawEvent::TiersSet(tiers));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// The tier reached with `staked` and the tier above it, each with its index.
+	pub fn tiers_around(
+		staked: T::Balance,
+	) -> (Option<(u32, FeeTier<T::Balance>)>, Option<(u32, FeeTier<T::Balance>)>) {
+		let tiers = Self::tiers();
+		let reached = tiers.iter().take_while(|tier| tier.min_stake <= staked).count();
+		let tier = |index: usize| tiers.get(index).map(|tier| (index as u32, *tier));
+		(reached.checked_sub(1).and_then(tier), tier(reached))
+	}
+
+	/// Discount `who` gets on trading fees.
+	pub fn discount(who: &T::AccountId) -> Perbill {
+		Self::tiers_around(T::Staked::staked(who))
+			.0
+			.map(|(_, tier)| tier.discount)
+			.unwrap_or_default()
+	}
+}
+
+impl<T: Config> FeeDiscount<T::AccountId, T::Balance> for Module<T> {
+	fn discounted_fee(who: &T::AccountId, fee: T::Balance) -> T::Balance {
+		fee.saturating_sub(Self::discount(who) * fee)
+	}
+}
@@ -0,0 +1,153 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use super::*;
+use crate as fee_tiers;
+use frame_support::{assert_noop, assert_ok, parameter_types, traits::Everything};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	BuildStorage,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		FeeTiers: fee_tiers::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaxTiers: u32 = 3;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+/// Every account stakes as much as its id.
+pub struct StakeIsAccountId;
+
+impl StakedBalance<u64, u64> for StakeIsAccountId {
+	fn staked(who: &u64) -> u64 {
+		*who
+	}
+}
+
+impl Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type Staked = StakeIsAccountId;
+	type MaxTiers = MaxTiers;
+	type UpdateOrigin = EnsureRoot<u64>;
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	GenesisConfig::default().build_storage().unwrap().into()
+}
+
+fn tier(min_stake: u64, discount: u32) -> FeeTier<u64> {
+	FeeTier { min_stake, discount: Perbill::from_percent(discount) }
+}
+
+fn default_tiers() -> Vec<FeeTier<u64>> {
+	vec![tier(100, 10), tier(1_000, 25), tier(10_000, 100)]
+}
+
+#[test]
+fn tiers_are_validated() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(FeeTiers::set_tiers(Origin::signed(1), default_tiers()), BadOrigin);
+		assert_noop!(
+			FeeTiers::set_tiers(Origin::root(), vec![tier(1, 1); 4]),
+			Error::<Test>::TooManyTiers
+		);
+		assert_noop!(
+			FeeTiers::set_tiers(Origin::root(), vec![tier(100, 10), tier(100, 20)]),
+			Error::<Test>::TiersNotSorted
+		);
+		assert_noop!(
+			FeeTiers::set_tiers(Origin::root(), vec![tier(100, 20), tier(1_000, 10)]),
+			Error::<Test>::DiscountDecreases
+		);
+		assert_ok!(FeeTiers::set_tiers(Origin::root(), default_tiers()));
+		assert_eq!(FeeTiers::tiers(), default_tiers());
+	});
+}
+
+#[test]
+fn accounts_reach_the_highest_tier_of_their_stake() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(FeeTiers::set_tiers(Origin::root(), default_tiers()));
+
+		assert_eq!(FeeTiers::tiers_around(99), (None, Some((0, tier(100, 10)))));
+		assert_eq!(
+			FeeTiers::tiers_around(100),
+			(Some((0, tier(100, 10))), Some((1, tier(1_000, 25))))
+		);
+		assert_eq!(
+			FeeTiers::tiers_around(9_999),
+			(Some((1, tier(1_000, 25))), Some((2, tier(10_000, 100))))
+		);
+		assert_eq!(FeeTiers::tiers_around(20_000), (Some((2, tier(10_000, 100))), None));
+	});
+}
+
+#[test]
+fn fees_are_discounted_by_tier() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(FeeTiers::discounted_fee(&1_000, 200), 200);
+
+		assert_ok!(FeeTiers::set_tiers(Origin::root(), default_tiers()));
+		assert_eq!(FeeTiers::discounted_fee(&50, 200), 200);
+		assert_eq!(FeeTiers::discounted_fee(&100, 200), 180);
+		assert_eq!(FeeTiers::discounted_fee(&1_000, 200), 150);
+		assert_eq!(FeeTiers::discounted_fee(&10_000, 200), 0);
+	});
+}
@@ -0,0 +1,466 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # IDO Pallet
+//!
+//! Runs token sales for projects that bridged their tokens into `orml_tokens`.
+//!
+//! A project opens a sale by depositing the tokens it sells together with the [`SaleTerms`].
+//! Between `start` and `end` accounts with a verified identity contribute the raise asset,
+//! which is PDEX or a stable asset allowed by governance, within the per-account contribution
+//! caps.
+//!
+//! Once the sale ended, governance either releases it or cancels it:
+//! - A released sale pays the accepted raise and the unsold tokens to the project. Every
+//!   contributor then gets tokens in proportion to its contribution. When the sale was
+//!   oversubscribed only `raise_target` is accepted, pro-rata, and the rest is refunded. Tokens
+//!   vest like `orml_vesting` schedules, an equal part after every period.
+//! - A cancelled sale, or a sale that did not reach `min_raise`, refunds all contributions.
+//!
+//! Rounding always favours the sale account, so its funds cover every claim and refund.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	pallet_prelude::*,
+	traits::{Contains, EnsureOrigin},
+	transactional, PalletId,
+};
+use frame_system::ensure_signed;
+use orml_traits::MultiCurrency;
+use sp_runtime::{
+	helpers_128bit::multiply_by_rational,
+	traits::{AccountIdConversion, AtLeast32BitUnsigned, Saturating, Zero},
+	DispatchResult, SaturatedConversion,
+};
+
+#[cfg(test)]
+mod tests;
+
+pub type SaleId = u32;
+
+pub type AssetIdOf<T> =
+	<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::CurrencyId;
+pub type BalanceOf<T> =
+	<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::Balance;
+pub type SaleOf<T> = Sale<
+	<T as frame_system::Config>::AccountId,
+	AssetIdOf<T>,
+	BalanceOf<T>,
+	<T as frame_system::Config>::BlockNumber,
+>;
+
+/// Vesting of purchased tokens. An equal part of the tokens unlocks at the end of each of
+/// `period_count` periods of `period` blocks, counted from `start`.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct VestingSchedule<BlockNumber> {
+	pub start: BlockNumber,
+	pub period: BlockNumber,
+	pub period_count: u32,
+}
+
+impl<BlockNumber: AtLeast32BitUnsigned + Copy> VestingSchedule<BlockNumber> {
+	/// Part of `total` unlocked at block `now`.
+	pub fn vested<Balance: AtLeast32BitUnsigned>(
+		&self,
+		total: Balance,
+		now: BlockNumber,
+	) -> Balance {
+		if now < self.start {
+			return Zero::zero()
+		}
+		if self.period.is_zero() || self.period_count == 0 {
+			return total
+		}
+		let periods = ((now - self.start) / self.period).saturated_into::<u32>();
+		portion(total, periods.min(self.period_count).into(), self.period_count.into())
+	}
+}
+
+/// Terms a project sells its tokens on.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct SaleTerms<AssetId, Balance, BlockNumber> {
+	/// Asset sold
+	pub token: AssetId,
+	/// Amount of `token` sold
+	pub tokens_for_sale: Balance,
+	/// Asset contributions are made in
+	pub raise_asset: AssetId,
+	/// Raise at which all tokens are sold; contributions above it are refunded pro-rata
+	pub raise_target: Balance,
+	/// Raise below which the sale fails and all contributions are refunded
+	pub min_raise: Balance,
+	/// Smallest total contribution of an account
+	pub min_contribution: Balance,
+	/// Largest total contribution of an account
+	pub max_contribution: Balance,
+	/// First block contributions are accepted in
+	pub start: BlockNumber,
+	/// Block from which contributions are no longer accepted
+	pub end: BlockNumber,
+	/// Vesting of the purchased tokens
+	pub vesting: VestingSchedule<BlockNumber>,
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum SaleStatus {
+	/// Waiting for contributions or for governance to release or cancel it.
+	Pending,
+	/// Funds were paid to the project and tokens can be claimed.
+	Released,
+	/// Contributions are refunded.
+	Cancelled,
+}
+
+/// A token sale.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Sale<AccountId, AssetId, Balance, BlockNumber> {
+	/// Account the tokens are sold for
+	pub project: AccountId,
+	pub terms: SaleTerms<AssetId, Balance, BlockNumber>,
+	/// Sum of all contributions
+	pub raised: Balance,
+	pub status: SaleStatus,
+}
+
+impl<AccountId, AssetId, Balance: AtLeast32BitUnsigned + Copy, BlockNumber>
+	Sale<AccountId, AssetId, Balance, BlockNumber>
+{
+	/// Part of the raise kept by the project.
+	pub fn accepted(&self) -> Balance {
+		self.raised.min(self.terms.raise_target)
+	}
+
+	/// Tokens bought by all contributors together.
+	pub fn sold(&self) -> Balance {
+		portion(self.terms.tokens_for_sale, self.accepted(), self.terms.raise_target)
+	}
+
+	/// Tokens bought with `contribution`, and the part of it that is refunded.
+	pub fn allocation(&self, contribution: Balance) -> (Balance, Balance) {
+		let raised = self.raised.max(self.terms.raise_target);
+		let tokens = portion(self.terms.tokens_for_sale, contribution, raised);
+		let refund = portion(contribution, raised - self.terms.raise_target, raised);
+		(tokens, refund)
+	}
+}
+
+/// `amount * part / total`, rounded down.
+fn portion<Balance: AtLeast32BitUnsigned>(
+	amount: Balance,
+	part: Balance,
+	total: Balance,
+) -> Balance {
+	multiply_by_rational(
+		amount.saturated_into(),
+		part.saturated_into(),
+		total.saturated_into::<u128>().max(1),
+	)
+	.unwrap_or_default()
+	.saturated_into()
+}
+
+/// Tokens bought in a released sale.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct Purchase<Balance> {
+	pub tokens: Balance,
+	pub claimed: Balance,
+}
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Config: frame_system::Config {
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+	/// Currencies tokens are sold and paid in
+	type Currency: MultiCurrency<Self::AccountId>;
+	/// Asset that can always be raised
+	type NativeAssetId: Get<AssetIdOf<Self>>;
+	/// Id deriving the accounts holding the funds of sales
+	type PalletId: Get<PalletId>;
+	/// Accounts with a verified identity, which may contribute to sales
+	type Verified: Contains<Self::AccountId>;
+	/// Origin allowed to release and cancel sales and to choose the raise assets
+	type GovernanceOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as Ido {
+		/// Id of the next sale.
+		pub NextSaleId get(fn next_sale_id): SaleId;
+		/// All sales.
+		pub Sales get(fn sale): map hasher(twox_64_concat) SaleId => Option<SaleOf<T>>;
+		/// Total contribution of every account to a sale.
+		pub Contributions get(fn contribution):
+			double_map hasher(twox_64_concat) SaleId, hasher(blake2_128_concat) T::AccountId
+			=> BalanceOf<T>;
+		/// Tokens bought by every account that claimed from a released sale.
+		pub Purchases get(fn purchase):
+			double_map hasher(twox_64_concat) SaleId, hasher(blake2_128_concat) T::AccountId
+			=> Option<Purchase<BalanceOf<T>>>;
+		/// Assets other than the native asset that sales may raise.
+		pub RaiseAssets get(fn raise_asset): map hasher(blake2_128_concat) AssetIdOf<T> => bool;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Config>::AccountId,
+		AssetId = AssetIdOf<T>,
+		Balance = BalanceOf<T>,
+	{
+		/// A sale was created. [sale, project]
+		SaleCreated(SaleId, AccountId),
+		/// An account contributed to a sale. [sale, who, amount]
+		Contributed(SaleId, AccountId, Balance),
+		/// A sale was released to its project. [sale, raised, unsold_tokens]
+		SaleReleased(SaleId, Balance, Balance),
+		/// A sale was cancelled. [sale]
+		SaleCancelled(SaleId),
+		/// Vested tokens and the refund of an oversubscription were claimed.
+		/// [sale, who, tokens, refund]
+		Claimed(SaleId, AccountId, Balance, Balance),
+		/// A contribution to a cancelled or failed sale was refunded. [sale, who, amount]
+		Refunded(SaleId, AccountId, Balance),
+		/// An asset was allowed or disallowed as a raise asset. [asset, allowed]
+		RaiseAssetSet(AssetId, bool),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Config> {
+		/// The sale does not exist.
+		UnknownSale,
+		/// Sales may not raise the asset.
+		RaiseAssetNotAllowed,
+		/// The caps of the sale are zero or contradict each other.
+		InvalidCaps,
+		/// The sale does not start in the future or ends before it starts.
+		InvalidPeriod,
+		/// The account has no verified identity.
+		NotVerified,
+		/// The sale does not accept contributions at this block.
+		SaleNotOpen,
+		/// The total contribution of the account would be below the minimum.
+		ContributionTooSmall,
+		/// The total contribution of the account would exceed the maximum.
+		ContributionTooLarge,
+		/// The sale is still running.
+		SaleNotEnded,
+		/// The sale was already released or cancelled.
+		SaleNotPending,
+		/// The sale raised less than its minimum.
+		MinRaiseNotReached,
+		/// The sale was not released.
+		SaleNotReleased,
+		/// The sale was neither cancelled nor failed.
+		SaleNotRefundable,
+		/// The account has nothing to claim from the sale.
+		NothingToClaim,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Allow or disallow sales to raise `asset`.
+		#[weight = T::DbWeight::get().writes(1)]
+		pub fn set_raise_asset(origin, asset: AssetIdOf<T>, allowed: bool) -> DispatchResult {
+			T::GovernanceOrigin::ensure_origin(origin)?;
+			if allowed {
+				RaiseAssets::<T>::insert(&asset, true);
+			} else {
+				RaiseAssets::<T>::remove(&asset);
+			}
+			Self::deposit_event(RawEvent::RaiseAssetSet(asset, allowed));
+			Ok(())
+		}
+
+		/// Sell tokens of the caller on `terms`. The tokens for sale are moved to the sale.
+		#[weight = T::DbWeight::get().reads_writes(5, 4)]
+		pub fn create_sale(
+			origin,
+			terms: SaleTerms<AssetIdOf<T>, BalanceOf<T>, T::BlockNumber>,
+		) -> DispatchResult {
+			let project = ensure_signed(origin)?;
+			let raise_asset = terms.raise_asset;
+			ensure!(
+				raise_asset == T::NativeAssetId::get() || Self::raise_asset(raise_asset),
+				Error::<T>::RaiseAssetNotAllowed
+			);
+			ensure!(
+				!terms.tokens_for_sale.is_zero() &&
+					!terms.raise_target.is_zero() &&
+					terms.min_raise <= terms.raise_target &&
+					terms.min_contribution <= terms.max_contribution,
+				Error::<T>::InvalidCaps
+			);
+			ensure!(
+				terms.start >= frame_system::Pallet::<T>::block_number() && terms.start < terms.end,
+				Error::<T>::InvalidPeriod
+			);
+
+			let id = Self::next_sale_id();
+			let account = Self::account_id(id);
+			T::Currency::transfer(terms.token, &project, &account, terms.tokens_for_sale)?;
+			NextSaleId::put(id + 1);
+			let sale = Sale {
+				project: project.clone(),
+				terms,
+				raised: Zero::zero(),
+				status: SaleStatus::Pending,
+			};
+			Sales::<T>::insert(id, sale);
+			Self::deposit_event(RawEvent::SaleCreated(id, project));
+			Ok(())
+		}
+
+		/// Contribute `amount` of the raise asset to a running sale.
+		#[weight = T::DbWeight::get().reads_writes(5, 4)]
+		pub fn contribute(origin, id: SaleId, amount: BalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(T::Verified::contains(&who), Error::<T>::NotVerified);
+			let mut sale = Self::sale(id).ok_or(Error::<T>::UnknownSale)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			let open = sale.terms.start <= now && now < sale.terms.end;
+			ensure!(sale.status == SaleStatus::Pending && open, Error::<T>::SaleNotOpen);
+			let contribution = Self::contribution(id, &who).saturating_add(amount);
+			ensure!(contribution >= sale.terms.min_contribution, Error::<T>::ContributionTooSmall);
+			ensure!(contribution <= sale.terms.max_contribution, Error::<T>::ContributionTooLarge);
+
+			T::Currency::transfer(sale.terms.raise_asset, &who, &Self::account_id(id), amount)?;
+			sale.raised = sale.raised.saturating_add(amount);
+			Sales::<T>::insert(id, sale);
+			Contributions::<T>::insert(id, &who, contribution);
+			Self::deposit_event(RawEvent::Contributed(id, who, amount));
+			Ok(())
+		}
+
+		/// Pay the accepted raise and the unsold tokens of an ended sale to its project, and
+		/// let contributors claim their tokens.
+		#[weight = T::DbWeight::get().reads_writes(5, 5)]
+		#[transactional]
+		pub fn release_sale(origin, id: SaleId) -> DispatchResult {
+			T::GovernanceOrigin::ensure_origin(origin)?;
+			let mut sale = Self::sale(id).ok_or(Error::<T>::UnknownSale)?;
+			ensure!(sale.status == SaleStatus::Pending, Error::<T>::SaleNotPending);
+			ensure!(
+				frame_system::Pallet::<T>::block_number() >= sale.terms.end,
+				Error::<T>::SaleNotEnded
+			);
+			ensure!(sale.raised >= sale.terms.min_raise, Error::<T>::MinRaiseNotReached);
+
+			let account = Self::account_id(id);
+			let raised = sale.accepted();
+			let unsold = sale.terms.tokens_for_sale.saturating_sub(sale.sold());
+			T::Currency::transfer(sale.terms.raise_asset, &account, &sale.project, raised)?;
+			T::Currency::transfer(sale.terms.token, &account, &sale.project, unsold)?;
+			sale.status = SaleStatus::Released;
+			Sales::<T>::insert(id, sale);
+			Self::deposit_event(RawEvent::SaleReleased(id, raised, unsold));
+			Ok(())
+		}
+
+		/// Cancel a sale that was not released yet, returning the tokens to the project and
+		/// refunding all contributions.
+		#[weight = T::DbWeight::get().reads_writes(3, 3)]
+		pub fn cancel_sale(origin, id: SaleId) -> DispatchResult {
+			T::GovernanceOrigin::ensure_origin(origin)?;
+			let mut sale = Self::sale(id).ok_or(Error::<T>::UnknownSale)?;
+			ensure!(sale.status == SaleStatus::Pending, Error::<T>::SaleNotPending);
+
+			T::Currency::transfer(
+				sale.terms.token,
+				&Self::account_id(id),
+				&sale.project,
+				sale.terms.tokens_for_sale,
+			)?;
+			sale.status = SaleStatus::Cancelled;
+			Sales::<T>::insert(id, sale);
+			Self::deposit_event(RawEvent::SaleCancelled(id));
+			Ok(())
+		}
+
+		/// Claim the vested tokens bought in a released sale. The first claim also refunds the
+		/// part of the contribution an oversubscription left unused.
+		#[weight = T::DbWeight::get().reads_writes(6, 5)]
+		#[transactional]
+		pub fn claim(origin, id: SaleId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let sale = Self::sale(id).ok_or(Error::<T>::UnknownSale)?;
+			ensure!(sale.status == SaleStatus::Released, Error::<T>::SaleNotReleased);
+
+			let account = Self::account_id(id);
+			let (mut purchase, refund) = match Self::purchase(id, &who) {
+				Some(purchase) => (purchase, Zero::zero()),
+				None => {
+					let contribution = Self::contribution(id, &who);
+					ensure!(!contribution.is_zero(), Error::<T>::NothingToClaim);
+					let (tokens, refund) = sale.allocation(contribution);
+					(Purchase { tokens, claimed: Zero::zero() }, refund)
+				},
+			};
+			let now = frame_system::Pallet::<T>::block_number();
+			let tokens =
+				sale.terms.vesting.vested(purchase.tokens, now).saturating_sub(purchase.claimed);
+			ensure!(!tokens.is_zero() || !refund.is_zero(), Error::<T>::NothingToClaim);
+
+			T::Currency::transfer(sale.terms.raise_asset, &account, &who, refund)?;
+			T::Currency::transfer(sale.terms.token, &account, &who, tokens)?;
+			purchase.claimed = purchase.claimed.saturating_add(tokens);
+			Purchases::<T>::insert(id, &who, purchase);
+			Self::deposit_event(RawEvent::Claimed(id, who, tokens, refund));
+			Ok(())
+		}
+
+		/// Refund the contribution to a cancelled sale, or to an ended sale that raised less
+		/// than its minimum.
+		#[weight = T::DbWeight::get().reads_writes(3, 3)]
+		pub fn refund(origin, id: SaleId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let sale = Self::sale(id).ok_or(Error::<T>::UnknownSale)?;
+			let failed = sale.status == SaleStatus::Pending &&
+				frame_system::Pallet::<T>::block_number() >= sale.terms.end &&
+				sale.raised < sale.terms.min_raise;
+			ensure!(
+				sale.status == SaleStatus::Cancelled || failed,
+				Error::<T>::SaleNotRefundable
+			);
+
+			let amount = Self::contribution(id, &who);
+			ensure!(!amount.is_zero(), Error::<T>::NothingToClaim);
+			T::Currency::transfer(sale.terms.raise_asset, &Self::account_id(id), &who, amount)?;
+			Contributions::<T>::remove(id, &who);
+			Self::deposit_event(RawEvent::Refunded(id, who, amount));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Account holding the tokens and contributions of sale `id`.
+	pub fn account_id(id: SaleId) -> T::AccountId {
+		T::PalletId::get().into_sub_account(id)
+	}
+}
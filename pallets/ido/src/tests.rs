@@ -0,0 +1,301 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use super::*;
+use crate as ido;
+use frame_support::{
+	assert_noop, assert_ok, parameter_types,
+	traits::{Everything, Nothing},
+};
+use frame_system::EnsureRoot;
+use orml_traits::parameter_type_with_key;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	BuildStorage,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Tokens: orml_tokens::{Pallet, Storage, Event<T>, Config<T>},
+		Ido: ido::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+const PDEX: u32 = 0;
+const TOKEN: u32 = 1;
+const USD: u32 = 2;
+const PROJECT: u64 = 10;
+const UNVERIFIED: u64 = 9;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaxLocks: u32 = 50;
+	pub const NativeAssetId: u32 = PDEX;
+	pub const IdoPalletId: PalletId = PalletId(*b"pdx/ido_");
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+parameter_type_with_key! {
+	pub ExistentialDeposits: |_currency_id: u32| -> u64 {
+		0
+	};
+}
+
+impl orml_tokens::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type Amount = i64;
+	type CurrencyId = u32;
+	type WeightInfo = ();
+	type ExistentialDeposits = ExistentialDeposits;
+	type OnDust = ();
+	type MaxLocks = MaxLocks;
+	type DustRemovalWhitelist = Nothing;
+}
+
+/// Every account but `UNVERIFIED` has a verified identity.
+pub struct AllButUnverified;
+
+impl Contains<u64> for AllButUnverified {
+	fn contains(who: &u64) -> bool {
+		*who != UNVERIFIED
+	}
+}
+
+impl Config for Test {
+	type Event = Event;
+	type Currency = Tokens;
+	type NativeAssetId = NativeAssetId;
+	type PalletId = IdoPalletId;
+	type Verified = AllButUnverified;
+	type GovernanceOrigin = EnsureRoot<u64>;
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = GenesisConfig::default().build_storage().unwrap();
+	let mut balances = vec![(PROJECT, TOKEN, 10_000)];
+	for who in [1, 2, UNVERIFIED].iter() {
+		balances.push((*who, PDEX, 10_000));
+		balances.push((*who, USD, 10_000));
+	}
+	orml_tokens::GenesisConfig::<Test> { balances }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn terms() -> SaleTerms<u32, u64, u64> {
+	SaleTerms {
+		token: TOKEN,
+		tokens_for_sale: 1_000,
+		raise_asset: PDEX,
+		raise_target: 1_000,
+		min_raise: 500,
+		min_contribution: 100,
+		max_contribution: 1_500,
+		start: 5,
+		end: 10,
+		vesting: VestingSchedule { start: 10, period: 10, period_count: 4 },
+	}
+}
+
+/// Create a sale on `terms()` and run it until it ends with the given contributions.
+fn ended_sale(contributions: &[(u64, u64)]) {
+	assert_ok!(Ido::create_sale(Origin::signed(PROJECT), terms()));
+	System::set_block_number(5);
+	for (who, amount) in contributions {
+		assert_ok!(Ido::contribute(Origin::signed(*who), 0, *amount));
+	}
+	System::set_block_number(10);
+}
+
+#[test]
+fn sales_are_validated() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Ido::create_sale(Origin::signed(PROJECT), SaleTerms { raise_asset: USD, ..terms() }),
+			Error::<Test>::RaiseAssetNotAllowed
+		);
+		assert_noop!(Ido::set_raise_asset(Origin::signed(1), USD, true), BadOrigin);
+		assert_ok!(Ido::set_raise_asset(Origin::root(), USD, true));
+		assert_noop!(
+			Ido::create_sale(Origin::signed(PROJECT), SaleTerms { min_raise: 1_001, ..terms() }),
+			Error::<Test>::InvalidCaps
+		);
+		assert_noop!(
+			Ido::create_sale(Origin::signed(PROJECT), SaleTerms { start: 0, ..terms() }),
+			Error::<Test>::InvalidPeriod
+		);
+
+		assert_ok!(Ido::create_sale(
+			Origin::signed(PROJECT),
+			SaleTerms { raise_asset: USD, ..terms() }
+		));
+		assert_eq!(Ido::next_sale_id(), 1);
+		assert_eq!(Tokens::free_balance(TOKEN, &PROJECT), 9_000);
+		assert_eq!(Tokens::free_balance(TOKEN, &Ido::account_id(0)), 1_000);
+	});
+}
+
+#[test]
+fn contributions_are_checked() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ido::create_sale(Origin::signed(PROJECT), terms()));
+		assert_noop!(Ido::contribute(Origin::signed(1), 0, 100), Error::<Test>::SaleNotOpen);
+
+		System::set_block_number(5);
+		assert_noop!(
+			Ido::contribute(Origin::signed(UNVERIFIED), 0, 100),
+			Error::<Test>::NotVerified
+		);
+		assert_noop!(
+			Ido::contribute(Origin::signed(1), 0, 99),
+			Error::<Test>::ContributionTooSmall
+		);
+		assert_ok!(Ido::contribute(Origin::signed(1), 0, 1_000));
+		assert_noop!(
+			Ido::contribute(Origin::signed(1), 0, 501),
+			Error::<Test>::ContributionTooLarge
+		);
+		assert_ok!(Ido::contribute(Origin::signed(1), 0, 500));
+		assert_eq!(Ido::contribution(0, 1), 1_500);
+		assert_eq!(Tokens::free_balance(PDEX, &1), 8_500);
+
+		System::set_block_number(10);
+		assert_noop!(Ido::contribute(Origin::signed(2), 0, 100), Error::<Test>::SaleNotOpen);
+	});
+}
+
+#[test]
+fn releasing_is_gated_by_governance() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Ido::create_sale(Origin::signed(PROJECT), terms()));
+		System::set_block_number(5);
+		assert_ok!(Ido::contribute(Origin::signed(1), 0, 600));
+		assert_noop!(Ido::release_sale(Origin::root(), 0), Error::<Test>::SaleNotEnded);
+
+		System::set_block_number(10);
+		assert_noop!(Ido::claim(Origin::signed(1), 0), Error::<Test>::SaleNotReleased);
+		assert_noop!(Ido::release_sale(Origin::signed(PROJECT), 0), BadOrigin);
+		assert_ok!(Ido::release_sale(Origin::root(), 0));
+		assert_noop!(Ido::release_sale(Origin::root(), 0), Error::<Test>::SaleNotPending);
+		assert_noop!(Ido::cancel_sale(Origin::root(), 0), Error::<Test>::SaleNotPending);
+
+		assert_eq!(Tokens::free_balance(PDEX, &PROJECT), 600);
+		assert_eq!(Tokens::free_balance(TOKEN, &PROJECT), 9_400);
+	});
+}
+
+#[test]
+fn oversubscribed_sales_are_allocated_pro_rata() {
+	new_test_ext().execute_with(|| {
+		ended_sale(&[(1, 1_500), (2, 500)]);
+		assert_ok!(Ido::release_sale(Origin::root(), 0));
+		assert_eq!(Tokens::free_balance(PDEX, &PROJECT), 1_000);
+		assert_eq!(Tokens::free_balance(TOKEN, &PROJECT), 9_000);
+
+		assert_ok!(Ido::claim(Origin::signed(1), 0));
+		assert_eq!(Ido::purchase(0, 1), Some(Purchase { tokens: 750, claimed: 0 }));
+		assert_eq!(Tokens::free_balance(PDEX, &1), 9_250);
+		assert_noop!(Ido::claim(Origin::signed(1), 0), Error::<Test>::NothingToClaim);
+
+		System::set_block_number(30);
+		assert_ok!(Ido::claim(Origin::signed(1), 0));
+		assert_ok!(Ido::claim(Origin::signed(2), 0));
+		assert_eq!(Tokens::free_balance(TOKEN, &1), 375);
+		assert_eq!(Tokens::free_balance(TOKEN, &2), 125);
+		assert_eq!(Tokens::free_balance(PDEX, &2), 9_750);
+
+		System::set_block_number(100);
+		assert_ok!(Ido::claim(Origin::signed(1), 0));
+		assert_eq!(Tokens::free_balance(TOKEN, &1), 750);
+		assert_noop!(Ido::claim(Origin::signed(UNVERIFIED), 0), Error::<Test>::NothingToClaim);
+	});
+}
+
+#[test]
+fn unsold_tokens_return_to_the_project() {
+	new_test_ext().execute_with(|| {
+		ended_sale(&[(1, 600)]);
+		assert_ok!(Ido::release_sale(Origin::root(), 0));
+		assert_eq!(Tokens::free_balance(TOKEN, &PROJECT), 9_400);
+
+		System::set_block_number(50);
+		assert_ok!(Ido::claim(Origin::signed(1), 0));
+		assert_eq!(Tokens::free_balance(TOKEN, &1), 600);
+		assert_eq!(Tokens::free_balance(PDEX, &1), 9_400);
+	});
+}
+
+#[test]
+fn failed_and_cancelled_sales_are_refunded() {
+	new_test_ext().execute_with(|| {
+		ended_sale(&[(1, 400)]);
+		assert_noop!(Ido::release_sale(Origin::root(), 0), Error::<Test>::MinRaiseNotReached);
+		assert_ok!(Ido::refund(Origin::signed(1), 0));
+		assert_eq!(Tokens::free_balance(PDEX, &1), 10_000);
+		assert_noop!(Ido::refund(Origin::signed(1), 0), Error::<Test>::NothingToClaim);
+
+		assert_ok!(Ido::create_sale(
+			Origin::signed(PROJECT),
+			SaleTerms { start: 10, end: 20, ..terms() }
+		));
+		assert_ok!(Ido::contribute(Origin::signed(2), 1, 800));
+		assert_noop!(Ido::refund(Origin::signed(2), 1), Error::<Test>::SaleNotRefundable);
+		assert_ok!(Ido::cancel_sale(Origin::root(), 1));
+		assert_eq!(Tokens::free_balance(TOKEN, &PROJECT), 9_000);
+		assert_ok!(Ido::refund(Origin::signed(2), 1));
+		assert_eq!(Tokens::free_balance(PDEX, &2), 10_000);
+	});
+}
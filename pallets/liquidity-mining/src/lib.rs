@@ -0,0 +1,390 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # Liquidity Mining Pallet
+//!
+//! Rewards liquidity providers and order book makers with PDEX from the treasury.
+//!
+//! Every market has two reward pools, one shared by liquidity providers in proportion to their
+//! LP shares and one shared by makers in proportion to their maker volume. Governance funds a
+//! pool by starting a campaign, which moves a budget from the treasury into the pallet account
+//! and pays `reward_per_block` until the budget runs out or the campaign is stopped.
+//!
+//! Rewards are tracked with an accumulator of the reward paid per share. It is advanced lazily
+//! whenever a pool is touched, and every account keeps a checkpoint of the accumulator from
+//! its last share change, so claiming costs the same no matter how many accounts share a
+//! pool. Blocks in which a pool has no shares pay nothing and leave the budget untouched.
+//!
+//! Shares are fed in by the trading pallets through [`RewardShares`]. LP shares are set to the
+//! current position, while maker volume adds up over the lifetime of the pool. Campaigns can
+//! only be started for pools listed in `ShareSources`, so that no budget is paid into a pool
+//! that nothing reports shares for.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	pallet_prelude::*,
+	traits::{Contains, Currency, EnsureOrigin, ExistenceRequirement},
+	transactional, PalletId,
+};
+use frame_system::ensure_signed;
+use sp_runtime::{
+	traits::{AccountIdConversion, Saturating, Zero},
+	DispatchResult, FixedPointNumber, FixedU128, SaturatedConversion,
+};
+
+#[cfg(test)]
+mod tests;
+
+pub type BalanceOf<T> =
+	<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// What the shares of a reward pool are measured in.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum RewardKind {
+	/// LP shares of the market's liquidity pool.
+	Liquidity,
+	/// Volume of maker orders filled on the market's order book.
+	MakerVolume,
+}
+
+/// A reward pool of a market.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct PoolId<MarketId> {
+	pub market: MarketId,
+	pub kind: RewardKind,
+}
+
+/// A funded reward campaign of a pool.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct Campaign<Balance, BlockNumber> {
+	/// Reward paid to the pool every block.
+	pub reward_per_block: Balance,
+	/// Funds left to pay out.
+	pub budget: Balance,
+	/// Reward paid per share since the pool was first funded.
+	pub reward_per_share: FixedU128,
+	/// Block up to which rewards were accrued.
+	pub last_update: BlockNumber,
+	/// Whether the campaign still pays rewards.
+	pub active: bool,
+}
+
+/// Rewards of an account in a pool.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct UserRewards<Balance> {
+	/// Reward per share of the pool when the account was last settled.
+	pub checkpoint: FixedU128,
+	/// Rewards accrued up to the checkpoint and not claimed yet.
+	pub pending: Balance,
+}
+
+/// Reports the shares accounts hold in reward pools.
+pub trait RewardShares<AccountId, MarketId, Balance> {
+	/// Set the LP shares `who` holds in the liquidity pool of `market`.
+	fn set_liquidity_shares(market: MarketId, who: &AccountId, shares: Balance);
+	/// Add maker volume `who` traded on the order book of `market`.
+	fn add_maker_volume(market: MarketId, who: &AccountId, volume: Balance);
+}
+
+/// Reinvests claimed rewards.
+pub trait Compound<AccountId, MarketId, Balance> {
+	/// Reinvest `amount` that `who` claimed from `pool`.
+	fn compound(who: &AccountId, pool: &PoolId<MarketId>, amount: Balance) -> DispatchResult;
+}
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Config: frame_system::Config {
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+	/// Currency rewards are paid in
+	type Currency: Currency<Self::AccountId>;
+	/// Identifier of a market
+	type MarketId: Parameter;
+	/// Id of the account holding the campaign budgets
+	type PalletId: Get<PalletId>;
+	/// Account campaigns are funded from and refunded to
+	type TreasuryAccount: Get<Self::AccountId>;
+	/// Where compounded rewards go
+	type Compound: Compound<Self::AccountId, Self::MarketId, BalanceOf<Self>>;
+	/// Pools whose shares are reported through `RewardShares`
+	type ShareSources: Contains<PoolId<Self::MarketId>>;
+	/// Origin allowed to start, stop and top up campaigns
+	type CampaignOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as LiquidityMining {
+		/// Campaign of every funded pool.
+		pub Campaigns get(fn campaign):
+			map hasher(blake2_128_concat) PoolId<T::MarketId>
+			=> Option<Campaign<BalanceOf<T>, T::BlockNumber>>;
+		/// Total shares of every pool.
+		pub TotalShares get(fn total_shares):
+			map hasher(blake2_128_concat) PoolId<T::MarketId> => BalanceOf<T>;
+		/// Shares of every account in a pool.
+		pub Shares get(fn shares):
+			double_map hasher(blake2_128_concat) PoolId<T::MarketId>,
+			hasher(blake2_128_concat) T::AccountId => BalanceOf<T>;
+		/// Rewards of every account in a pool.
+		pub Rewards get(fn rewards):
+			double_map hasher(blake2_128_concat) PoolId<T::MarketId>,
+			hasher(blake2_128_concat) T::AccountId => UserRewards<BalanceOf<T>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Config>::AccountId,
+		Balance = BalanceOf<T>,
+		Pool = PoolId<<T as Config>::MarketId>,
+	{
+		/// A campaign was started. [pool, reward_per_block, amount]
+		CampaignStarted(Pool, Balance, Balance),
+		/// A campaign was topped up. [pool, amount]
+		CampaignToppedUp(Pool, Balance),
+		/// A campaign was stopped and its remaining budget refunded. [pool, refund]
+		CampaignStopped(Pool, Balance),
+		/// Rewards were claimed. [who, pool, amount]
+		Claimed(AccountId, Pool, Balance),
+		/// Rewards were claimed and compounded. [who, pool, amount]
+		Compounded(AccountId, Pool, Balance),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Config> {
+		/// The pool already has an active campaign.
+		CampaignActive,
+		/// The pool has no active campaign.
+		CampaignNotActive,
+		/// The account has no rewards in the pool.
+		NothingToClaim,
+		/// Nothing reports the shares of the pool.
+		NoShareSource,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Start paying `reward_per_block` to `pool`, funded with `amount` from the treasury.
+		/// A stopped campaign is restarted with its accumulated rewards kept.
+		#[weight = T::DbWeight::get().reads_writes(4, 3)]
+		pub fn start_campaign(
+			origin,
+			pool: PoolId<T::MarketId>,
+			reward_per_block: BalanceOf<T>,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			T::CampaignOrigin::ensure_origin(origin)?;
+			ensure!(T::ShareSources::contains(&pool), Error::<T>::NoShareSource);
+			let mut campaign = Self::accrue(&pool).unwrap_or_default();
+			ensure!(!campaign.active, Error::<T>::CampaignActive);
+
+			Self::fund(amount)?;
+			campaign.reward_per_block = reward_per_block;
+			campaign.budget = campaign.budget.saturating_add(amount);
+			campaign.last_update = frame_system::Pallet::<T>::block_number();
+			campaign.active = true;
+			Campaigns::<T>::insert(&pool, campaign);
+			Self::deposit_event(RawEvent::CampaignStarted(pool, reward_per_block, amount));
+			Ok(())
+		}
+
+		/// Add `amount` from the treasury to the budget of the active campaign of `pool`.
+		#[weight = T::DbWeight::get().reads_writes(4, 3)]
+		pub fn top_up(origin, pool: PoolId<T::MarketId>, amount: BalanceOf<T>) -> DispatchResult {
+			T::CampaignOrigin::ensure_origin(origin)?;
+			let mut campaign = Self::accrue(&pool)
+				.filter(|campaign| campaign.active)
+				.ok_or(Error::<T>::CampaignNotActive)?;
+
+			Self::fund(amount)?;
+			campaign.budget = campaign.budget.saturating_add(amount);
+			Campaigns::<T>::insert(&pool, campaign);
+			Self::deposit_event(RawEvent::CampaignToppedUp(pool, amount));
+			Ok(())
+		}
+
+		/// Stop the campaign of `pool` and refund its remaining budget to the treasury.
+		/// Rewards accrued until now can still be claimed.
+		#[weight = T::DbWeight::get().reads_writes(4, 3)]
+		pub fn stop_campaign(origin, pool: PoolId<T::MarketId>) -> DispatchResult {
+			T::CampaignOrigin::ensure_origin(origin)?;
+			let mut campaign = Self::accrue(&pool)
+				.filter(|campaign| campaign.active)
+				.ok_or(Error::<T>::CampaignNotActive)?;
+
+			let refund = sp_std::mem::take(&mut campaign.budget);
+			T::Currency::transfer(
+				&Self::account_id(),
+				&T::TreasuryAccount::get(),
+				refund,
+				ExistenceRequirement::AllowDeath,
+			)?;
+			campaign.active = false;
+			Campaigns::<T>::insert(&pool, campaign);
+			Self::deposit_event(RawEvent::CampaignStopped(pool, refund));
+			Ok(())
+		}
+
+		/// Pay out the rewards of the caller in `pool`.
+		#[weight = T::DbWeight::get().reads_writes(6, 4)]
+		#[transactional]
+		pub fn claim(origin, pool: PoolId<T::MarketId>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let amount = Self::do_claim(&who, &pool)?;
+			Self::deposit_event(RawEvent::Claimed(who, pool, amount));
+			Ok(())
+		}
+
+		/// Pay out the rewards of the caller in `pool` and reinvest them.
+		#[weight = T::DbWeight::get().reads_writes(6, 4)]
+		#[transactional]
+		pub fn compound(origin, pool: PoolId<T::MarketId>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let amount = Self::do_claim(&who, &pool)?;
+			T::Compound::compound(&who, &pool, amount)?;
+			Self::deposit_event(RawEvent::Compounded(who, pool, amount));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Account holding the campaign budgets and unclaimed rewards.
+	pub fn account_id() -> T::AccountId {
+		T::PalletId::get().into_account()
+	}
+
+	/// Rewards `who` can claim from `pool`.
+	pub fn pending_rewards(pool: &PoolId<T::MarketId>, who: &T::AccountId) -> BalanceOf<T> {
+		let reward_per_share = Self::campaign(pool)
+			.map(|campaign| Self::accrued(pool, campaign).reward_per_share)
+			.unwrap_or_default();
+		Self::earned(pool, who, reward_per_share)
+	}
+
+	fn fund(amount: BalanceOf<T>) -> DispatchResult {
+		T::Currency::transfer(
+			&T::TreasuryAccount::get(),
+			&Self::account_id(),
+			amount,
+			ExistenceRequirement::KeepAlive,
+		)
+	}
+
+	/// `campaign` with the rewards of the blocks since its last update paid out.
+	fn accrued(
+		pool: &PoolId<T::MarketId>,
+		mut campaign: Campaign<BalanceOf<T>, T::BlockNumber>,
+	) -> Campaign<BalanceOf<T>, T::BlockNumber> {
+		let now = frame_system::Pallet::<T>::block_number();
+		let total_shares = Self::total_shares(pool);
+		if campaign.active && now > campaign.last_update && !total_shares.is_zero() {
+			let elapsed = (now - campaign.last_update).saturated_into::<u128>();
+			let reward = campaign
+				.reward_per_block
+				.saturating_mul(elapsed.saturated_into())
+				.min(campaign.budget);
+			campaign.budget -= reward;
+			campaign.reward_per_share =
+				campaign.reward_per_share.saturating_add(FixedU128::saturating_from_rational(
+					reward.saturated_into::<u128>(),
+					total_shares.saturated_into::<u128>(),
+				));
+		}
+		campaign.last_update = campaign.last_update.max(now);
+		campaign
+	}
+
+	/// Accrue the rewards of `pool` up to the current block.
+	fn accrue(pool: &PoolId<T::MarketId>) -> Option<Campaign<BalanceOf<T>, T::BlockNumber>> {
+		let campaign = Self::accrued(pool, Self::campaign(pool)?);
+		Campaigns::<T>::insert(pool, &campaign);
+		Some(campaign)
+	}
+
+	/// Rewards of `who` in `pool` once the reward per share reaches `reward_per_share`.
+	fn earned(
+		pool: &PoolId<T::MarketId>,
+		who: &T::AccountId,
+		reward_per_share: FixedU128,
+	) -> BalanceOf<T> {
+		let rewards = Self::rewards(pool, who);
+		let shares = Self::shares(pool, who).saturated_into::<u128>();
+		let earned = reward_per_share.saturating_sub(rewards.checkpoint).saturating_mul_int(shares);
+		rewards.pending.saturating_add(earned.saturated_into())
+	}
+
+	/// Move the rewards `who` earned in `pool` to its pending rewards. Has to happen before
+	/// the shares of `who` change.
+	fn settle(pool: &PoolId<T::MarketId>, who: &T::AccountId) -> BalanceOf<T> {
+		let reward_per_share =
+			Self::accrue(pool).map(|campaign| campaign.reward_per_share).unwrap_or_default();
+		let pending = Self::earned(pool, who, reward_per_share);
+		Rewards::<T>::insert(pool, who, UserRewards { checkpoint: reward_per_share, pending });
+		pending
+	}
+
+	fn do_claim(
+		who: &T::AccountId,
+		pool: &PoolId<T::MarketId>,
+	) -> Result<BalanceOf<T>, DispatchError> {
+		let amount = Self::settle(pool, who);
+		ensure!(!amount.is_zero(), Error::<T>::NothingToClaim);
+		T::Currency::transfer(&Self::account_id(), who, amount, ExistenceRequirement::AllowDeath)?;
+		Rewards::<T>::mutate(pool, who, |rewards| rewards.pending = Zero::zero());
+		Ok(amount)
+	}
+
+	fn set_shares(pool: PoolId<T::MarketId>, who: &T::AccountId, shares: BalanceOf<T>) {
+		Self::settle(&pool, who);
+		let previous =
+			Shares::<T>::mutate(&pool, who, |current| sp_std::mem::replace(current, shares));
+		TotalShares::<T>::mutate(&pool, |total| {
+			*total = total.saturating_sub(previous).saturating_add(shares)
+		});
+	}
+}
+
+impl<T: Config> RewardShares<T::AccountId, T::MarketId, BalanceOf<T>> for Module<T> {
+	fn set_liquidity_shares(market: T::MarketId, who: &T::AccountId, shares: BalanceOf<T>) {
+		Self::set_shares(PoolId { market, kind: RewardKind::Liquidity }, who, shares);
+	}
+
+	fn add_maker_volume(market: T::MarketId, who: &T::AccountId, volume: BalanceOf<T>) {
+		let pool = PoolId { market, kind: RewardKind::MakerVolume };
+		let shares = Self::shares(&pool, who).saturating_add(volume);
+		Self::set_shares(pool, who, shares);
+	}
+}
+
+impl<AccountId, MarketId, Balance> Compound<AccountId, MarketId, Balance> for () {
+	fn compound(_: &AccountId, _: &PoolId<MarketId>, _: Balance) -> DispatchResult {
+		Err(DispatchError::Other("Compounding is not supported"))
+	}
+}
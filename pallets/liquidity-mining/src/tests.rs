@@ -0,0 +1,309 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use super::*;
+use crate as liquidity_mining;
+use frame_support::{assert_noop, assert_ok, parameter_types, traits::Everything};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	BuildStorage,
+};
+use std::cell::RefCell;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		LiquidityMining: liquidity_mining::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+const TREASURY: u64 = 100;
+const MARKET: u32 = 7;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const ExistentialDeposit: u64 = 1;
+	pub const LiquidityMiningPalletId: PalletId = PalletId(*b"pdx/lqmn");
+	pub const TreasuryAccount: u64 = TREASURY;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+impl pallet_balances::Config for Test {
+	type Balance = u64;
+	type DustRemoval = ();
+	type Event = Event;
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type WeightInfo = ();
+}
+
+thread_local! {
+	static COMPOUNDED: RefCell<Vec<(u64, u64)>> = RefCell::new(Vec::new());
+}
+
+/// Records compounded rewards, refusing those of account 3.
+pub struct RecordCompound;
+
+impl Compound<u64, u32, u64> for RecordCompound {
+	fn compound(who: &u64, _: &PoolId<u32>, amount: u64) -> DispatchResult {
+		ensure!(*who != 3, DispatchError::Other("cannot compound"));
+		COMPOUNDED.with(|compounded| compounded.borrow_mut().push((*who, amount)));
+		Ok(())
+	}
+}
+
+/// Shares are reported for the pools of `MARKET` only.
+pub struct MarketShares;
+
+impl Contains<PoolId<u32>> for MarketShares {
+	fn contains(pool: &PoolId<u32>) -> bool {
+		pool.market == MARKET
+	}
+}
+
+impl Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type MarketId = u32;
+	type PalletId = LiquidityMiningPalletId;
+	type TreasuryAccount = TreasuryAccount;
+	type Compound = RecordCompound;
+	type ShareSources = MarketShares;
+	type CampaignOrigin = EnsureRoot<u64>;
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = GenesisConfig::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(TREASURY, 1_000_000), (1, 10), (2, 10), (3, 10)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn liquidity() -> PoolId<u32> {
+	PoolId { market: MARKET, kind: RewardKind::Liquidity }
+}
+
+fn maker_volume() -> PoolId<u32> {
+	PoolId { market: MARKET, kind: RewardKind::MakerVolume }
+}
+
+#[test]
+fn campaigns_are_funded_from_the_treasury() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			LiquidityMining::start_campaign(Origin::signed(1), liquidity(), 10, 1_000),
+			BadOrigin
+		);
+		assert_ok!(LiquidityMining::start_campaign(Origin::root(), liquidity(), 10, 1_000));
+		assert_noop!(
+			LiquidityMining::start_campaign(Origin::root(), liquidity(), 10, 1_000),
+			Error::<Test>::CampaignActive
+		);
+		assert_ok!(LiquidityMining::top_up(Origin::root(), liquidity(), 500));
+		assert_noop!(
+			LiquidityMining::top_up(Origin::root(), maker_volume(), 500),
+			Error::<Test>::CampaignNotActive
+		);
+
+		assert_eq!(Balances::free_balance(TREASURY), 998_500);
+		assert_eq!(Balances::free_balance(LiquidityMining::account_id()), 1_500);
+		assert_eq!(LiquidityMining::campaign(liquidity()).unwrap().budget, 1_500);
+	});
+}
+
+#[test]
+fn campaigns_require_a_share_source() {
+	new_test_ext().execute_with(|| {
+		let untracked = PoolId { market: MARKET + 1, kind: RewardKind::Liquidity };
+		assert_noop!(
+			LiquidityMining::start_campaign(Origin::root(), untracked, 10, 1_000),
+			Error::<Test>::NoShareSource
+		);
+		assert_eq!(Balances::free_balance(TREASURY), 1_000_000);
+	});
+}
+
+#[test]
+fn rewards_are_split_by_shares() {
+	new_test_ext().execute_with(|| {
+		LiquidityMining::set_liquidity_shares(MARKET, &1, 100);
+		LiquidityMining::set_liquidity_shares(MARKET, &2, 300);
+		assert_ok!(LiquidityMining::start_campaign(Origin::root(), liquidity(), 100, 10_000));
+
+		System::set_block_number(11);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &1), 250);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &2), 750);
+
+		assert_ok!(LiquidityMining::claim(Origin::signed(1), liquidity()));
+		assert_eq!(Balances::free_balance(1), 260);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &1), 0);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &2), 750);
+	});
+}
+
+#[test]
+fn share_changes_are_checkpointed() {
+	new_test_ext().execute_with(|| {
+		LiquidityMining::set_liquidity_shares(MARKET, &1, 100);
+		assert_ok!(LiquidityMining::start_campaign(Origin::root(), liquidity(), 100, 10_000));
+
+		System::set_block_number(5);
+		LiquidityMining::set_liquidity_shares(MARKET, &2, 100);
+		System::set_block_number(9);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &1), 600);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &2), 200);
+
+		LiquidityMining::set_liquidity_shares(MARKET, &1, 0);
+		System::set_block_number(13);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &1), 600);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &2), 600);
+	});
+}
+
+#[test]
+fn maker_volume_adds_up() {
+	new_test_ext().execute_with(|| {
+		LiquidityMining::add_maker_volume(MARKET, &1, 100);
+		LiquidityMining::add_maker_volume(MARKET, &2, 100);
+		LiquidityMining::add_maker_volume(MARKET, &2, 200);
+		assert_eq!(LiquidityMining::total_shares(maker_volume()), 400);
+		assert_ok!(LiquidityMining::start_campaign(Origin::root(), maker_volume(), 100, 10_000));
+
+		System::set_block_number(5);
+		assert_eq!(LiquidityMining::pending_rewards(&maker_volume(), &1), 100);
+		assert_eq!(LiquidityMining::pending_rewards(&maker_volume(), &2), 300);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &2), 0);
+	});
+}
+
+#[test]
+fn rewards_stop_with_the_budget() {
+	new_test_ext().execute_with(|| {
+		LiquidityMining::set_liquidity_shares(MARKET, &1, 100);
+		assert_ok!(LiquidityMining::start_campaign(Origin::root(), liquidity(), 100, 250));
+
+		System::set_block_number(11);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &1), 250);
+		assert_ok!(LiquidityMining::top_up(Origin::root(), liquidity(), 1_000));
+		System::set_block_number(12);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &1), 350);
+	});
+}
+
+#[test]
+fn stopping_refunds_the_remaining_budget() {
+	new_test_ext().execute_with(|| {
+		LiquidityMining::set_liquidity_shares(MARKET, &1, 100);
+		assert_ok!(LiquidityMining::start_campaign(Origin::root(), liquidity(), 100, 1_000));
+
+		System::set_block_number(4);
+		assert_noop!(LiquidityMining::stop_campaign(Origin::signed(1), liquidity()), BadOrigin);
+		assert_ok!(LiquidityMining::stop_campaign(Origin::root(), liquidity()));
+		assert_eq!(Balances::free_balance(TREASURY), 999_700);
+		assert_noop!(
+			LiquidityMining::stop_campaign(Origin::root(), liquidity()),
+			Error::<Test>::CampaignNotActive
+		);
+
+		System::set_block_number(10);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &1), 300);
+		assert_ok!(LiquidityMining::claim(Origin::signed(1), liquidity()));
+		assert_eq!(Balances::free_balance(1), 310);
+		assert_eq!(Balances::free_balance(LiquidityMining::account_id()), 0);
+
+		assert_ok!(LiquidityMining::start_campaign(Origin::root(), liquidity(), 100, 1_000));
+		System::set_block_number(12);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &1), 200);
+	});
+}
+
+#[test]
+fn claimed_rewards_are_compounded() {
+	new_test_ext().execute_with(|| {
+		LiquidityMining::set_liquidity_shares(MARKET, &1, 100);
+		LiquidityMining::set_liquidity_shares(MARKET, &3, 100);
+		assert_ok!(LiquidityMining::start_campaign(Origin::root(), liquidity(), 100, 10_000));
+
+		System::set_block_number(3);
+		assert_ok!(LiquidityMining::compound(Origin::signed(1), liquidity()));
+		assert_eq!(COMPOUNDED.with(|compounded| compounded.borrow().clone()), vec![(1, 100)]);
+		assert_eq!(Balances::free_balance(1), 110);
+
+		assert_noop!(
+			LiquidityMining::compound(Origin::signed(3), liquidity()),
+			DispatchError::Other("cannot compound")
+		);
+		assert_eq!(LiquidityMining::pending_rewards(&liquidity(), &3), 100);
+	});
+}
+
+#[test]
+fn nothing_to_claim_without_shares() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(LiquidityMining::start_campaign(Origin::root(), liquidity(), 100, 1_000));
+		System::set_block_number(5);
+		assert_noop!(
+			LiquidityMining::claim(Origin::signed(1), liquidity()),
+			Error::<Test>::NothingToClaim
+		);
+		assert_eq!(LiquidityMining::campaign(liquidity()).unwrap().budget, 1_000);
+	});
+}
@@ -0,0 +1,368 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # Oracle Pallet
+//!
+//! Provides asset prices to the runtime, quoted in USD.
+//!
+//! Governance manages a set of feeders and the HTTP source of the price of every asset. Feeders
+//! run offchain workers with their key in the keystore under [`KEY_TYPE`]. Every
+//! `FetchInterval` blocks the workers fetch the prices from the sources and submit them in a
+//! signed transaction, which is free for feeders.
+//!
+//! The price of an asset is the median of the observations of all feeders that are not older
+//! than `StalenessLimit` blocks. Without `MinObservations` such observations there is no price.
+//! Other pallets read prices through [`PriceProvider`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure, pallet_prelude::*,
+	traits::EnsureOrigin, weights::Pays,
+};
+use frame_system::{
+	ensure_signed,
+	offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer},
+};
+use lite_json::JsonValue;
+use sp_runtime::{
+	offchain::{http, Duration},
+	traits::{IdentifyAccount, One, Saturating, Zero},
+	DispatchResult, FixedPointNumber, FixedU128, KeyTypeId, RuntimeAppPublic,
+};
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+/// Key type of the keys feeders sign their submissions with.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"orcl");
+
+/// Currency prices are quoted in, as named in the responses of price sources.
+const QUOTE: &str = "USD";
+
+/// Time a price source has to respond.
+const FETCH_TIMEOUT_MS: u64 = 3_000;
+
+pub mod crypto {
+	use super::KEY_TYPE;
+	use sp_core::sr25519::Signature as Sr25519Signature;
+	use sp_runtime::{
+		app_crypto::{app_crypto, sr25519},
+		traits::Verify,
+		MultiSignature, MultiSigner,
+	};
+
+	app_crypto!(sr25519, KEY_TYPE);
+
+	/// Signs the price submissions of feeders.
+	pub struct OracleAuthId;
+
+	impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for OracleAuthId {
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+
+	impl frame_system::offchain::AppCrypto<<Sr25519Signature as Verify>::Signer, Sr25519Signature>
+		for OracleAuthId
+	{
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+}
+
+/// Price of one unit of an asset in USD.
+pub type Price = FixedU128;
+
+/// Prices of assets.
+pub trait PriceProvider<AssetId> {
+	/// Current price of `asset`, if the oracle has one.
+	fn get_price(asset: &AssetId) -> Option<Price>;
+}
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Config: CreateSignedTransaction<Call<Self>> {
+	/// Crypto feeders sign their submissions with
+	type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+	/// Identifier of an asset
+	type AssetId: Parameter + Copy;
+	/// Maximum number of feeders
+	type MaxFeeders: Get<u32>;
+	/// Number of fresh observations needed for a price
+	type MinObservations: Get<u32>;
+	/// Age in blocks after which an observation is no longer used
+	type StalenessLimit: Get<Self::BlockNumber>;
+	/// Blocks between two submissions of the offchain workers
+	type FetchInterval: Get<Self::BlockNumber>;
+	/// Origin allowed to manage feeders and price sources
+	type FeederOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as Oracle {
+		/// Accounts allowed to submit prices.
+		pub Feeders get(fn feeders): Vec<T::AccountId>;
+		/// URL the price of every asset is fetched from.
+		pub PriceSources get(fn price_source):
+			map hasher(blake2_128_concat) T::AssetId => Option<Vec<u8>>;
+		/// Latest price of an asset submitted by a feeder, with the block it was submitted in.
+		pub Observations get(fn observation):
+			double_map hasher(blake2_128_concat) T::AssetId, hasher(blake2_128_concat) T::AccountId
+			=> Option<(Price, T::BlockNumber)>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Config>::AccountId,
+		AssetId = <T as Config>::AssetId,
+	{
+		/// A feeder was added. [feeder]
+		FeederAdded(AccountId),
+		/// A feeder was removed. [feeder]
+		FeederRemoved(AccountId),
+		/// The price source of an asset was set or removed. [asset, url]
+		PriceSourceSet(AssetId, Option<Vec<u8>>),
+		/// A feeder submitted prices. [feeder, prices]
+		PricesSubmitted(AccountId, Vec<(AssetId, Price)>),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Config> {
+		/// The account is already a feeder.
+		AlreadyFeeder,
+		/// The account is not a feeder.
+		NotFeeder,
+		/// There are already `MaxFeeders` feeders.
+		TooManyFeeders,
+		/// The asset has no price source.
+		UnknownAsset,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// Maximum number of feeders.
+		const MaxFeeders: u32 = T::MaxFeeders::get();
+		/// Number of fresh observations needed for a price.
+		const MinObservations: u32 = T::MinObservations::get();
+		/// Age in blocks after which an observation is no longer used.
+		const StalenessLimit: T::BlockNumber = T::StalenessLimit::get();
+
+		fn deposit_event() = default;
+
+		/// Allow `feeder` to submit prices.
+		#[weight = T::DbWeight::get().reads_writes(1, 1)]
+		pub fn add_feeder(origin, feeder: T::AccountId) -> DispatchResult {
+			T::FeederOrigin::ensure_origin(origin)?;
+			Feeders::<T>::try_mutate(|feeders| {
+				ensure!(!feeders.contains(&feeder), Error::<T>::AlreadyFeeder);
+				ensure!(feeders.len() < T::MaxFeeders::get() as usize, Error::<T>::TooManyFeeders);
+				feeders.push(feeder.clone());
+				Ok::<_, Error<T>>(())
+			})?;
+			Self::deposit_event(RawEvent::FeederAdded(feeder));
+			Ok(())
+		}
+
+		/// Remove `feeder`. Its observations are no longer used.
+		#[weight = T::DbWeight::get().reads_writes(1, 1)]
+		pub fn remove_feeder(origin, feeder: T::AccountId) -> DispatchResult {
+			T::FeederOrigin::ensure_origin(origin)?;
+			Feeders::<T>::try_mutate(|feeders| {
+				let index = feeders.iter().position(|f| f == &feeder).ok_or(Error::<T>::NotFeeder)?;
+				feeders.remove(index);
+				Ok::<_, Error<T>>(())
+			})?;
+			Self::deposit_event(RawEvent::FeederRemoved(feeder));
+			Ok(())
+		}
+
+		/// Set the URL the price of `asset` is fetched from. The source has to respond with a
+		/// JSON object holding the price under `USD`. Removing the source also removes the
+		/// observations of the asset.
+		#[weight = T::DbWeight::get().reads_writes(1, 1)
+			.saturating_add(T::DbWeight::get().writes(T::MaxFeeders::get().into()))]
+		pub fn set_price_source(origin, asset: T::AssetId, url: Option<Vec<u8>>) -> DispatchResult {
+			T::FeederOrigin::ensure_origin(origin)?;
+			match &url {
+				Some(url) => PriceSources::<T>::insert(asset, url),
+				None => {
+					PriceSources::<T>::remove(asset);
+					for feeder in Self::feeders() {
+						Observations::<T>::remove(asset, feeder);
+					}
+				},
+			}
+			Self::deposit_event(RawEvent::PriceSourceSet(asset, url));
+			Ok(())
+		}
+
+		/// Submit prices observed by the calling feeder. Free for feeders.
+		#[weight = T::DbWeight::get().reads_writes(1 + prices.len() as u64, prices.len() as u64)]
+		pub fn submit_prices(
+			origin,
+			prices: Vec<(T::AssetId, Price)>,
+		) -> DispatchResultWithPostInfo {
+			let feeder = ensure_signed(origin)?;
+			ensure!(Self::feeders().contains(&feeder), Error::<T>::NotFeeder);
+			ensure!(
+				prices.iter().all(|(asset, _)| PriceSources::<T>::contains_key(asset)),
+				Error::<T>::UnknownAsset
+			);
+
+			let now = frame_system::Pallet::<T>::block_number();
+			for (asset, price) in prices.iter() {
+				Observations::<T>::insert(asset, &feeder, (*price, now));
+			}
+			Self::deposit_event(RawEvent::PricesSubmitted(feeder, prices));
+			Ok(Pays::No.into())
+		}
+
+		fn offchain_worker(now: T::BlockNumber) {
+			if (now % T::FetchInterval::get().max(One::one())).is_zero() {
+				if let Err(e) = Self::submit_observations() {
+					log::warn!(target: "oracle", "Could not submit prices: {}", e);
+				}
+			}
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Median of the fresh observations of the price of `asset`.
+	pub fn get_price(asset: &T::AssetId) -> Option<Price> {
+		let now = frame_system::Pallet::<T>::block_number();
+		let mut prices = Self::feeders()
+			.iter()
+			.filter_map(|feeder| Self::observation(asset, feeder))
+			.filter(|(_, at)| now.saturating_sub(*at) <= T::StalenessLimit::get())
+			.map(|(price, _)| price)
+			.collect::<Vec<_>>();
+		if prices.is_empty() || (prices.len() as u32) < T::MinObservations::get() {
+			return None
+		}
+
+		prices.sort();
+		let middle = prices.len() / 2;
+		Some(if prices.len() % 2 == 0 {
+			Price::from_inner(prices[middle - 1].into_inner() / 2 + prices[middle].into_inner() / 2)
+		} else {
+			prices[middle]
+		})
+	}
+
+	/// Fetch the price from `url`, which responds with a JSON object holding it under `USD`.
+	pub fn fetch_price(url: &[u8]) -> Result<Price, &'static str> {
+		let url = sp_std::str::from_utf8(url).map_err(|_| "Price source is not UTF-8")?;
+		let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(FETCH_TIMEOUT_MS));
+		let response = http::Request::get(url)
+			.deadline(deadline)
+			.send()
+			.map_err(|_| "Could not send the request")?
+			.try_wait(deadline)
+			.map_err(|_| "The price source did not respond in time")?
+			.map_err(|_| "The request failed")?;
+		if response.code != 200 {
+			return Err("The price source responded with an error")
+		}
+
+		let body = response.body().collect::<Vec<u8>>();
+		sp_std::str::from_utf8(&body)
+			.ok()
+			.and_then(Self::parse_price)
+			.ok_or("The price source responded with an invalid price")
+	}
+
+	fn parse_price(body: &str) -> Option<Price> {
+		let value = match lite_json::parse_json(body).ok()? {
+			JsonValue::Object(object) =>
+				object.into_iter().find(|(key, _)| key.iter().copied().eq(QUOTE.chars()))?.1,
+			_ => return None,
+		};
+		match value {
+			JsonValue::Number(number) if !number.negative && number.exponent == 0 => {
+				let fraction = Price::checked_from_rational(
+					number.fraction,
+					10u64.checked_pow(number.fraction_length)?,
+				)?;
+				Price::checked_from_integer(number.integer as u128)?.checked_add(&fraction)
+			},
+			_ => None,
+		}
+	}
+
+	/// Keys in the local keystore that belong to feeders.
+	fn local_feeders() -> Vec<T::Public> {
+		let feeders = Self::feeders();
+		<T::AuthorityId as AppCrypto<T::Public, T::Signature>>::RuntimeAppPublic::all()
+			.into_iter()
+			.map(|key| {
+				<T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic::from(key)
+					.into()
+			})
+			.filter(|public: &T::Public| feeders.contains(&public.clone().into_account()))
+			.collect()
+	}
+
+	/// Fetch the prices of all assets and submit them with every local feeder key.
+	fn submit_observations() -> Result<(), &'static str> {
+		let keys = Self::local_feeders();
+		if keys.is_empty() {
+			return Ok(())
+		}
+
+		let prices = PriceSources::<T>::iter()
+			.filter_map(|(asset, url)| match Self::fetch_price(&url) {
+				Ok(price) => Some((asset, price)),
+				Err(e) => {
+					log::warn!(target: "oracle", "Could not fetch the price of {:?}: {}", asset, e);
+					None
+				},
+			})
+			.collect::<Vec<_>>();
+		if prices.is_empty() {
+			return Err("No price could be fetched")
+		}
+
+		for (account, result) in Signer::<T, T::AuthorityId>::all_accounts()
+			.with_filter(keys)
+			.send_signed_transaction(|_| Call::submit_prices(prices.clone()))
+		{
+			if result.is_err() {
+				log::warn!(target: "oracle", "Feeder {:?} could not submit prices", account.id);
+			}
+		}
+		Ok(())
+	}
+}
+
+impl<T: Config> PriceProvider<T::AssetId> for Module<T> {
+	fn get_price(asset: &T::AssetId) -> Option<Price> {
+		Module::<T>::get_price(asset)
+	}
+}
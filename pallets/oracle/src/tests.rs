@@ -0,0 +1,262 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use super::*;
+use crate as oracle;
+use codec::Decode;
+use frame_support::{
+	assert_noop, assert_ok, parameter_types,
+	traits::{Everything, OffchainWorker},
+};
+use frame_system::EnsureRoot;
+use sp_core::{
+	offchain::{testing, OffchainWorkerExt, TransactionPoolExt},
+	sr25519::{self, Signature},
+	H256,
+};
+use sp_keystore::{testing::KeyStore, KeystoreExt, SyncCryptoStore};
+use sp_runtime::{
+	testing::{Header, TestXt},
+	traits::{BadOrigin, BlakeTwo256, Extrinsic as ExtrinsicT, IdentityLookup, Verify},
+	BuildStorage,
+};
+use std::sync::Arc;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+type Extrinsic = TestXt<Call, ()>;
+type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Oracle: oracle::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+const PDEX: u32 = 1;
+const SOURCE: &str = "https://prices.example/pdex";
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaxFeeders: u32 = 3;
+	pub const MinObservations: u32 = 2;
+	pub const StalenessLimit: u64 = 10;
+	pub const FetchInterval: u64 = 5;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+impl frame_system::offchain::SigningTypes for Test {
+	type Public = <Signature as Verify>::Signer;
+	type Signature = Signature;
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test
+where
+	Call: From<LocalCall>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = Extrinsic;
+}
+
+impl<LocalCall> CreateSignedTransaction<LocalCall> for Test
+where
+	Call: From<LocalCall>,
+{
+	fn create_transaction<C: AppCrypto<Self::Public, Self::Signature>>(
+		call: Call,
+		_public: <Signature as Verify>::Signer,
+		_account: AccountId,
+		nonce: u64,
+	) -> Option<(Call, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+		Some((call, (nonce, ())))
+	}
+}
+
+impl Config for Test {
+	type AuthorityId = crypto::OracleAuthId;
+	type Event = Event;
+	type AssetId = u32;
+	type MaxFeeders = MaxFeeders;
+	type MinObservations = MinObservations;
+	type StalenessLimit = StalenessLimit;
+	type FetchInterval = FetchInterval;
+	type FeederOrigin = EnsureRoot<AccountId>;
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut ext: sp_io::TestExternalities =
+		GenesisConfig::default().build_storage().unwrap().into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn account(id: u8) -> AccountId {
+	sr25519::Public::from_raw([id; 32])
+}
+
+fn price(cents: u64) -> Price {
+	Price::saturating_from_rational(cents, 100)
+}
+
+/// Make accounts 1 to `count` feeders of the PDEX price.
+fn setup_feeders(count: u8) {
+	assert_ok!(Oracle::set_price_source(Origin::root(), PDEX, Some(SOURCE.into())));
+	for id in 1..=count {
+		assert_ok!(Oracle::add_feeder(Origin::root(), account(id)));
+	}
+}
+
+fn expect_price_request(state: &mut testing::OffchainState, body: &[u8]) {
+	state.expect_request(testing::PendingRequest {
+		method: "GET".into(),
+		uri: SOURCE.into(),
+		response: Some(body.to_vec()),
+		sent: true,
+		..Default::default()
+	});
+}
+
+#[test]
+fn feeders_are_managed_by_governance() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Oracle::add_feeder(Origin::signed(account(1)), account(1)), BadOrigin);
+		setup_feeders(3);
+		assert_noop!(Oracle::add_feeder(Origin::root(), account(1)), Error::<Test>::AlreadyFeeder);
+		assert_noop!(Oracle::add_feeder(Origin::root(), account(4)), Error::<Test>::TooManyFeeders);
+
+		assert_ok!(Oracle::remove_feeder(Origin::root(), account(2)));
+		assert_noop!(Oracle::remove_feeder(Origin::root(), account(2)), Error::<Test>::NotFeeder);
+		assert_eq!(Oracle::feeders(), vec![account(1), account(3)]);
+	});
+}
+
+#[test]
+fn only_feeders_submit_prices_of_known_assets() {
+	new_test_ext().execute_with(|| {
+		setup_feeders(1);
+		assert_noop!(
+			Oracle::submit_prices(Origin::signed(account(2)), vec![(PDEX, price(100))]),
+			Error::<Test>::NotFeeder
+		);
+		assert_noop!(
+			Oracle::submit_prices(Origin::signed(account(1)), vec![(2, price(100))]),
+			Error::<Test>::UnknownAsset
+		);
+
+		let result = Oracle::submit_prices(Origin::signed(account(1)), vec![(PDEX, price(100))]);
+		assert_eq!(result.unwrap().pays_fee, Pays::No);
+		assert_eq!(Oracle::observation(PDEX, account(1)), Some((price(100), 1)));
+	});
+}
+
+#[test]
+fn prices_are_the_median_of_fresh_observations() {
+	new_test_ext().execute_with(|| {
+		setup_feeders(3);
+		assert_ok!(Oracle::submit_prices(Origin::signed(account(1)), vec![(PDEX, price(100))]));
+		assert_eq!(Oracle::get_price(&PDEX), None);
+
+		assert_ok!(Oracle::submit_prices(Origin::signed(account(2)), vec![(PDEX, price(300))]));
+		assert_eq!(Oracle::get_price(&PDEX), Some(price(200)));
+
+		System::set_block_number(5);
+		assert_ok!(Oracle::submit_prices(Origin::signed(account(3)), vec![(PDEX, price(5_000))]));
+		assert_eq!(Oracle::get_price(&PDEX), Some(price(300)));
+
+		System::set_block_number(12);
+		assert_eq!(Oracle::get_price(&PDEX), None);
+		assert_ok!(Oracle::submit_prices(Origin::signed(account(1)), vec![(PDEX, price(120))]));
+		assert_eq!(Oracle::get_price(&PDEX), Some(price(2_560)));
+
+		assert_ok!(Oracle::remove_feeder(Origin::root(), account(3)));
+		assert_eq!(Oracle::get_price(&PDEX), None);
+	});
+}
+
+#[test]
+fn prices_are_fetched_from_the_source() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let mut ext = new_test_ext();
+	ext.register_extension(OffchainWorkerExt::new(offchain));
+
+	expect_price_request(&mut state.write(), br#"{"USD": 1.25}"#);
+	expect_price_request(&mut state.write(), br#"{"EUR": 1.25}"#);
+	ext.execute_with(|| {
+		assert_eq!(Oracle::fetch_price(SOURCE.as_bytes()), Ok(price(125)));
+		assert!(Oracle::fetch_price(SOURCE.as_bytes()).is_err());
+	});
+}
+
+#[test]
+fn offchain_workers_submit_prices_of_local_feeders() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let keystore = KeyStore::new();
+	let feeder = SyncCryptoStore::sr25519_generate_new(&keystore, KEY_TYPE, None).unwrap();
+
+	let mut ext = new_test_ext();
+	ext.register_extension(OffchainWorkerExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+	ext.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	expect_price_request(&mut state.write(), br#"{"USD": 0.5}"#);
+	ext.execute_with(|| {
+		setup_feeders(1);
+		Oracle::offchain_worker(5);
+		assert!(pool_state.read().transactions.is_empty());
+
+		assert_ok!(Oracle::add_feeder(Origin::root(), feeder));
+		Oracle::offchain_worker(6);
+		assert!(pool_state.read().transactions.is_empty());
+		Oracle::offchain_worker(10);
+
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(tx.signature, Some((0, ())));
+		assert_eq!(tx.call, Call::Oracle(crate::Call::submit_prices(vec![(PDEX, price(50))])));
+	});
+}
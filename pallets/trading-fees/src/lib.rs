@@ -0,0 +1,380 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # Trading Fees Pallet
+//!
+//! Charges trading calls, e.g. order placement and cancellation, a predictable fee.
+//!
+//! Governance keeps a schedule of trading calls, keyed by the pallet index and call index of
+//! the SCALE encoded `Call`, with the flat fee of each call. The [`ChargeTradingFee`] signed
+//! extension charges scheduled calls their flat fee plus the tip, independent of weight,
+//! length and the fee multiplier of `pallet_transaction_payment`, and leaves every other call
+//! to `ChargeTransactionPayment`.
+//!
+//! The flat fee may be discounted per account through [`FeeDiscount`], e.g. by fee tiers.
+//!
+//! Calls scheduled without a fee or discounted to nothing, and all trading calls of accounts
+//! in good standing, are free. Instead of a fee, they are limited to `FreeTradesPerPeriod`
+//! per account in every period of `FreeTradePeriod` blocks, and further free calls are
+//! rejected until the next period starts.
+//!
+//! Wrapper calls, e.g. batches and proxy calls, are charged by weight, so transactions whose
+//! [`WrappedCalls`] contain a trading call are rejected instead of escaping its fee.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_event, decl_module, decl_storage,
+	pallet_prelude::*,
+	traits::{
+		Contains, Currency, EnsureOrigin, ExistenceRequirement, Imbalance, OnUnbalanced,
+		WithdrawReasons,
+	},
+	weights::{DispatchInfo, PostDispatchInfo},
+};
+use pallet_transaction_payment::{ChargeTransactionPayment, OnChargeTransaction};
+use sp_runtime::{
+	traits::{
+		DispatchInfoOf, Dispatchable, One, PostDispatchInfoOf, SaturatedConversion, Saturating,
+		SignedExtension, Zero,
+	},
+	transaction_validity::{TransactionPriority, TransactionValidity, ValidTransaction},
+	DispatchResult, FixedPointOperand,
+};
+use sp_std::prelude::*;
+
+/// Balance type of the fees, as charged by `pallet_transaction_payment`.
+pub type BalanceOf<T> = <OnChargeTransactionOf<T> as OnChargeTransaction<T>>::Balance;
+type OnChargeTransactionOf<T> = <T as pallet_transaction_payment::Config>::OnChargeTransaction;
+type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+	<T as frame_system::Config>::AccountId,
+>>::NegativeImbalance;
+
+/// Custom validity error of a free trading call submitted after the allowance of the current
+/// period was used up.
+pub const FREE_TRADES_EXHAUSTED: u8 = 1;
+
+/// Custom validity error of a wrapper call containing a trading call.
+pub const WRAPPED_TRADING_CALL: u8 = 2;
+
+/// Discount on the flat fees of trading calls.
+pub trait FeeDiscount<AccountId, Balance> {
+	/// Fee `who` pays for a trading call with the flat `fee`.
+	fn discounted_fee(who: &AccountId, fee: Balance) -> Balance;
+}
+
+impl<AccountId, Balance> FeeDiscount<AccountId, Balance> for () {
+	fn discounted_fee(_: &AccountId, fee: Balance) -> Balance {
+		fee
+	}
+}
+
+/// Calls dispatched by wrapper calls, e.g. `Utility::batch` or `Proxy::proxy`.
+pub trait WrappedCalls<Call> {
+	/// Whether any call that `call` dispatches matches `predicate`, false if it is not a
+	/// wrapper call. Wrapped calls may be carried encoded, so they are only visited.
+	fn any_wrapped(call: &Call, predicate: impl Fn(&Call) -> bool) -> bool;
+}
+
+impl<Call> WrappedCalls<Call> for () {
+	fn any_wrapped(_: &Call, _: impl Fn(&Call) -> bool) -> bool {
+		false
+	}
+}
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Config: frame_system::Config + pallet_transaction_payment::Config {
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+	/// Currency the fees of trading calls are withdrawn from
+	type Currency: Currency<Self::AccountId, Balance = BalanceOf<Self>>;
+	/// Handler of the withdrawn fees, given the fee and then the tip
+	type OnTradingFee: OnUnbalanced<NegativeImbalanceOf<Self>>;
+	/// Discount of an account on the flat fees
+	type FeeDiscount: FeeDiscount<Self::AccountId, BalanceOf<Self>>;
+	/// Accounts in good standing, which trade without fees within the free trade allowance
+	type GoodStanding: Contains<Self::AccountId>;
+	/// Wrapper calls, rejected when they contain a trading call
+	type WrappedCalls: WrappedCalls<<Self as frame_system::Config>::Call>;
+	/// Free trading calls an account may submit in every period
+	type FreeTradesPerPeriod: Get<u32>;
+	/// Length of a free trade period in blocks
+	type FreeTradePeriod: Get<Self::BlockNumber>;
+	/// Origin allowed to change the fee schedule
+	type UpdateOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as TradingFees {
+		/// Flat fees of trading calls by (pallet index, call index).
+		pub Schedule get(fn schedule):
+			double_map hasher(twox_64_concat) u8, hasher(twox_64_concat) u8 => Option<BalanceOf<T>>;
+		/// Period of the last free trading call of an account and the free trading calls it
+		/// submitted in that period.
+		pub FreeTrades get(fn free_trades):
+			map hasher(blake2_128_concat) T::AccountId => (T::BlockNumber, u32);
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		Balance = BalanceOf<T>,
+	{
+		/// The flat fee of a trading call was set. [pallet_index, call_index, fee]
+		TradingFeeSet(u8, u8, Balance),
+		/// A call is no longer charged as a trading call. [pallet_index, call_index]
+		TradingFeeRemoved(u8, u8),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		/// Free trading calls an account may submit in every period.
+		const FreeTradesPerPeriod: u32 = T::FreeTradesPerPeriod::get();
+
+		/// Length of a free trade period in blocks.
+		const FreeTradePeriod: T::BlockNumber = T::FreeTradePeriod::get();
+
+		fn deposit_event() = default;
+
+		/// Charge the call identified by `pallet_index` and `call_index` the flat `fee`, or
+		/// charge it by weight again if `fee` is `None`.
+		#[weight = T::DbWeight::get().writes(1)]
+		pub fn set_trading_fee(
+			origin,
+			pallet_index: u8,
+			call_index: u8,
+			fee: Option<BalanceOf<T>>,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			match fee {
+				Some(fee) => {
+					Schedule::<T>::insert(pallet_index, call_index, fee);
+					Self::deposit_event(RawEvent::TradingFeeSet(pallet_index, call_index, fee));
+				},
+				None => {
+					Schedule::<T>::remove(pallet_index, call_index);
+					Self::deposit_event(RawEvent::TradingFeeRemoved(pallet_index, call_index));
+				},
+			}
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Flat fee of `call`, or `None` if it is not a trading call.
+	///
+	/// The first two bytes of an encoded `Call` are its pallet index and call index.
+	pub fn scheduled_fee<C: Encode>(call: &C) -> Option<BalanceOf<T>> {
+		call.using_encoded(|encoded| match encoded {
+			[pallet_index, call_index, ..] => Self::schedule(pallet_index, call_index),
+			_ => None,
+		})
+	}
+
+	/// Fee `who` pays for `call` without the tip, or `None` if it is not a trading call.
+	pub fn fee_for<C: Encode>(who: &T::AccountId, call: &C) -> Option<BalanceOf<T>> {
+		Self::scheduled_fee(call).map(|fee| {
+			let fee = T::FeeDiscount::discounted_fee(who, fee);
+			if Self::is_free(who, fee) {
+				Zero::zero()
+			} else {
+				fee
+			}
+		})
+	}
+
+	/// Whether `call` wraps a trading call, at any depth.
+	pub fn wraps_trading_call(call: &<T as frame_system::Config>::Call) -> bool
+	where
+		<T as frame_system::Config>::Call: Encode,
+	{
+		T::WrappedCalls::any_wrapped(call, |inner| {
+			Self::scheduled_fee(inner).is_some() || Self::wraps_trading_call(inner)
+		})
+	}
+
+	/// Rejects wrapper calls containing a trading call.
+	fn ensure_not_wrapped(
+		call: &<T as frame_system::Config>::Call,
+	) -> Result<(), TransactionValidityError>
+	where
+		<T as frame_system::Config>::Call: Encode,
+	{
+		if Self::wraps_trading_call(call) {
+			return Err(InvalidTransaction::Custom(WRAPPED_TRADING_CALL).into())
+		}
+		Ok(())
+	}
+
+	fn is_free(who: &T::AccountId, fee: BalanceOf<T>) -> bool {
+		fee.is_zero() || T::GoodStanding::contains(who)
+	}
+
+	/// Counts a free trading call of `who`, failing if the allowance of the current period is
+	/// used up.
+	fn note_free_trade(who: &T::AccountId) -> Result<(), TransactionValidityError> {
+		let now = frame_system::Pallet::<T>::block_number();
+		let period = now / T::FreeTradePeriod::get().max(One::one());
+		FreeTrades::<T>::try_mutate(who, |(last_period, count)| {
+			if *last_period != period {
+				*last_period = period;
+				*count = 0;
+			}
+			if *count >= T::FreeTradesPerPeriod::get() {
+				return Err(InvalidTransaction::Custom(FREE_TRADES_EXHAUSTED).into())
+			}
+			*count += 1;
+			Ok(())
+		})
+	}
+
+	/// Withdraws the flat `fee` of a trading call, after discount, and the `tip` from `who`.
+	fn charge(
+		who: &T::AccountId,
+		fee: BalanceOf<T>,
+		tip: BalanceOf<T>,
+	) -> Result<(), TransactionValidityError> {
+		let fee = T::FeeDiscount::discounted_fee(who, fee);
+		let fee = if Self::is_free(who, fee) {
+			Self::note_free_trade(who)?;
+			Zero::zero()
+		} else {
+			fee
+		};
+		let total = fee.saturating_add(tip);
+		if total.is_zero() {
+			return Ok(())
+		}
+		let reason = if tip.is_zero() {
+			WithdrawReasons::TRANSACTION_PAYMENT
+		} else {
+			WithdrawReasons::TRANSACTION_PAYMENT | WithdrawReasons::TIP
+		};
+		let imbalance = T::Currency::withdraw(who, total, reason, ExistenceRequirement::KeepAlive)
+			.map_err(|_| InvalidTransaction::Payment)?;
+		let (tip, fee) = imbalance.split(tip);
+		T::OnTradingFee::on_unbalanceds(Some(fee).into_iter().chain(Some(tip)));
+		Ok(())
+	}
+}
+
+/// Charges trading calls their flat fee and every other call by `ChargeTransactionPayment`.
+///
+/// Encodes like `ChargeTransactionPayment` and keeps its identifier, so that wallets sign
+/// transactions with it unchanged.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ChargeTradingFee<T: Config>(#[codec(compact)] BalanceOf<T>);
+
+impl<T: Config> ChargeTradingFee<T> {
+	/// Create a new extension paying `tip` on top of the fee.
+	pub fn from(tip: BalanceOf<T>) -> Self {
+		Self(tip)
+	}
+
+	/// Tip paid on top of the fee.
+	pub fn tip(&self) -> BalanceOf<T> {
+		self.0
+	}
+}
+
+impl<T: Config> sp_std::fmt::Debug for ChargeTradingFee<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "ChargeTradingFee<{:?}>", self.0)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config> SignedExtension for ChargeTradingFee<T>
+where
+	BalanceOf<T>: Send + Sync + From<u64> + FixedPointOperand,
+	<T as frame_system::Config>::Call:
+		Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo> + Encode,
+{
+	const IDENTIFIER: &'static str = "ChargeTransactionPayment";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::Call;
+	type AdditionalSigned = ();
+	/// `None` for trading calls, which are not refunded after dispatch.
+	type Pre = Option<<ChargeTransactionPayment<T> as SignedExtension>::Pre>;
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		match Module::<T>::scheduled_fee(call) {
+			Some(fee) => {
+				Module::<T>::charge(who, fee, self.0)?;
+				let priority = self.0.saturated_into::<TransactionPriority>();
+				Ok(ValidTransaction { priority, ..Default::default() })
+			},
+			None => {
+				Module::<T>::ensure_not_wrapped(call)?;
+				ChargeTransactionPayment::<T>::from(self.0).validate(who, call, info, len)
+			},
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		match Module::<T>::scheduled_fee(call) {
+			Some(fee) => Module::<T>::charge(who, fee, self.0).map(|_| None),
+			None => {
+				Module::<T>::ensure_not_wrapped(call)?;
+				ChargeTransactionPayment::<T>::from(self.0)
+					.pre_dispatch(who, call, info, len)
+					.map(Some)
+			},
+		}
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		match pre {
+			Some(pre) =>
+				ChargeTransactionPayment::<T>::post_dispatch(pre, info, post_info, len, result),
+			None => Ok(()),
+		}
+	}
+}
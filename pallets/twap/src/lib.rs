@@ -0,0 +1,223 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # TWAP Pallet
+//!
+//! Tracks time-weighted average prices of asset pairs from on-chain trades.
+//!
+//! Trading pallets report the price of every trade or swap through [`OnTrade`]. For every pair
+//! the pallet accumulates the price weighted by the number of blocks it held, where a price
+//! only starts counting from the block after the trade. A price pushed within a single block
+//! and traded back before the block ends therefore has no weight.
+//!
+//! At most every `ObservationPeriod` blocks a trade stores a snapshot of the accumulator in a
+//! ring buffer of `BufferSize` observations per pair. The average over a window is taken
+//! between the current accumulator and the newest observation at least `window` blocks old,
+//! so windows up to about `ObservationPeriod * (BufferSize - 1)` blocks can be read. Other
+//! pallets read averages through [`TwapProvider`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use codec::{Decode, Encode};
+use frame_support::{decl_module, decl_storage, pallet_prelude::*};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, CheckedSub, Saturating, UniqueSaturatedInto},
+	FixedPointNumber, FixedU128,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Price of one unit of the base asset in the quote asset.
+pub type Price = FixedU128;
+
+/// Running sum of the price of a pair over blocks.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct Accumulator<BlockNumber> {
+	/// Price of the last trade.
+	pub price: Price,
+	/// Sum of the inner value of the price over all blocks up to `last_update`, wrapping on
+	/// overflow. Only differences of it are meaningful.
+	pub cumulative: u128,
+	/// Block of the last trade.
+	pub last_update: BlockNumber,
+}
+
+impl<BlockNumber: AtLeast32BitUnsigned + Copy> Accumulator<BlockNumber> {
+	/// Cumulative price at block `now`.
+	pub fn cumulative_at(&self, now: BlockNumber) -> u128 {
+		let blocks: u128 = now.saturating_sub(self.last_update).unique_saturated_into();
+		self.cumulative.wrapping_add(self.price.into_inner().wrapping_mul(blocks))
+	}
+}
+
+/// Snapshot of an accumulator.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct Observation<BlockNumber> {
+	pub block: BlockNumber,
+	pub cumulative: u128,
+}
+
+/// Position of the observations of a pair in their ring buffer.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct RingBuffer {
+	/// Slot the next observation is written to.
+	pub head: u32,
+	/// Number of stored observations.
+	pub len: u32,
+	/// Number of slots, fixed when the first observation of the pair is stored.
+	pub capacity: u32,
+}
+
+impl RingBuffer {
+	/// Slot of the `index`-th oldest observation.
+	fn slot(&self, index: u32) -> u32 {
+		(self.head + self.capacity - self.len + index) % self.capacity
+	}
+}
+
+/// Receives the prices of trades.
+pub trait OnTrade<AssetId> {
+	/// A trade of `base` against `quote` happened at `price`.
+	fn on_trade(base: AssetId, quote: AssetId, price: Price);
+}
+
+impl<AssetId> OnTrade<AssetId> for () {
+	fn on_trade(_: AssetId, _: AssetId, _: Price) {}
+}
+
+/// Time-weighted average prices.
+pub trait TwapProvider<AssetId, BlockNumber> {
+	/// Average price of `base` in `quote` over at least the last `window` blocks, if trades
+	/// were observed that long ago.
+	fn twap(base: AssetId, quote: AssetId, window: BlockNumber) -> Option<Price>;
+}
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Config: frame_system::Config {
+	/// Identifier of an asset
+	type AssetId: Parameter + Copy;
+	/// Minimum number of blocks between two observations of a pair
+	type ObservationPeriod: Get<Self::BlockNumber>;
+	/// Number of observations kept per pair
+	type BufferSize: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as Twap {
+		/// Accumulated price of every traded pair, keyed by base and quote asset.
+		pub Accumulators get(fn accumulator):
+			map hasher(blake2_128_concat) (T::AssetId, T::AssetId)
+			=> Option<Accumulator<T::BlockNumber>>;
+		/// Ring buffer position of the observations of every pair.
+		pub Buffers get(fn buffer):
+			map hasher(blake2_128_concat) (T::AssetId, T::AssetId) => RingBuffer;
+		/// Observations of every pair, keyed by their slot in the ring buffer.
+		pub Observations get(fn observation):
+			double_map hasher(blake2_128_concat) (T::AssetId, T::AssetId),
+			hasher(twox_64_concat) u32
+			=> Option<Observation<T::BlockNumber>>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		/// Minimum number of blocks between two observations of a pair.
+		const ObservationPeriod: T::BlockNumber = T::ObservationPeriod::get();
+		/// Number of observations kept per pair.
+		const BufferSize: u32 = T::BufferSize::get();
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Average price of `base` in `quote` over at least the last `window` blocks.
+	pub fn twap(base: T::AssetId, quote: T::AssetId, window: T::BlockNumber) -> Option<Price> {
+		let pair = (base, quote);
+		let accumulator = Self::accumulator(pair)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		let start = Self::observation_before(pair, now.checked_sub(&window)?)?;
+
+		let blocks: u128 = now.saturating_sub(start.block).unique_saturated_into();
+		if blocks == 0 {
+			return None
+		}
+		let cumulative = accumulator.cumulative_at(now).wrapping_sub(start.cumulative);
+		Some(Price::from_inner(cumulative / blocks))
+	}
+
+	/// Newest observation of `pair` made at or before block `target`.
+	fn observation_before(
+		pair: (T::AssetId, T::AssetId),
+		target: T::BlockNumber,
+	) -> Option<Observation<T::BlockNumber>> {
+		let buffer = Self::buffer(pair);
+		let observation = |index| Self::observation(pair, buffer.slot(index));
+
+		// Observations are ordered by block, so search for the first one after `target`.
+		let (mut low, mut high) = (0, buffer.len);
+		while low < high {
+			let middle = (low + high) / 2;
+			if observation(middle)?.block <= target {
+				low = middle + 1;
+			} else {
+				high = middle;
+			}
+		}
+		low.checked_sub(1).and_then(observation)
+	}
+}
+
+impl<T: Config> OnTrade<T::AssetId> for Module<T> {
+	fn on_trade(base: T::AssetId, quote: T::AssetId, price: Price) {
+		let pair = (base, quote);
+		let now = frame_system::Pallet::<T>::block_number();
+		let accumulator = match Self::accumulator(pair) {
+			Some(accumulator) =>
+				Accumulator { price, cumulative: accumulator.cumulative_at(now), last_update: now },
+			None => Accumulator { price, cumulative: 0, last_update: now },
+		};
+		Accumulators::<T>::insert(pair, accumulator);
+
+		let mut buffer = Self::buffer(pair);
+		if buffer.capacity == 0 {
+			buffer.capacity = T::BufferSize::get().max(1);
+		}
+		let latest = buffer
+			.len
+			.checked_sub(1)
+			.and_then(|index| Self::observation(pair, buffer.slot(index)));
+		if let Some(latest) = latest {
+			if now.saturating_sub(latest.block) < T::ObservationPeriod::get() {
+				return
+			}
+		}
+
+		let observation = Observation { block: now, cumulative: accumulator.cumulative };
+		Observations::<T>::insert(pair, buffer.head, observation);
+		buffer.head = (buffer.head + 1) % buffer.capacity;
+		buffer.len = buffer.len.saturating_add(1).min(buffer.capacity);
+		Buffers::<T>::insert(pair, buffer);
+	}
+}
+
+impl<T: Config> TwapProvider<T::AssetId, T::BlockNumber> for Module<T> {
+	fn twap(base: T::AssetId, quote: T::AssetId, window: T::BlockNumber) -> Option<Price> {
+		Module::<T>::twap(base, quote, window)
+	}
+}
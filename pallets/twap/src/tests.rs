@@ -0,0 +1,137 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use super::*;
+use crate as twap;
+use frame_support::{parameter_types, traits::Everything};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	BuildStorage,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Twap: twap::{Pallet, Storage},
+	}
+);
+
+const BASE: u32 = 1;
+const QUOTE: u32 = 2;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const ObservationPeriod: u64 = 5;
+	pub const BufferSize: u32 = 4;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+impl Config for Test {
+	type AssetId = u32;
+	type ObservationPeriod = ObservationPeriod;
+	type BufferSize = BufferSize;
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	GenesisConfig::default().build_storage().unwrap().into()
+}
+
+fn price(price: u128) -> Price {
+	Price::saturating_from_integer(price)
+}
+
+fn trade_at(block: u64, trade_price: u128) {
+	System::set_block_number(block);
+	Twap::on_trade(BASE, QUOTE, price(trade_price));
+}
+
+#[test]
+fn prices_are_weighted_by_blocks() {
+	new_test_ext().execute_with(|| {
+		trade_at(1, 10);
+		trade_at(11, 20);
+		System::set_block_number(21);
+
+		assert_eq!(Twap::twap(BASE, QUOTE, 20), Some(price(15)));
+		assert_eq!(Twap::twap(BASE, QUOTE, 10), Some(price(20)));
+		assert_eq!(Twap::twap(BASE, QUOTE, 15), Some(price(15)));
+		assert_eq!(Twap::twap(BASE, QUOTE, 21), None);
+		assert_eq!(Twap::twap(QUOTE, BASE, 10), None);
+	});
+}
+
+#[test]
+fn prices_within_a_block_have_no_weight() {
+	new_test_ext().execute_with(|| {
+		trade_at(1, 10);
+		trade_at(5, 1_000);
+		trade_at(5, 10);
+		System::set_block_number(11);
+
+		assert_eq!(Twap::twap(BASE, QUOTE, 10), Some(price(10)));
+		assert_eq!(Twap::buffer((BASE, QUOTE)).len, 1);
+	});
+}
+
+#[test]
+fn old_observations_are_overwritten() {
+	new_test_ext().execute_with(|| {
+		for (block, trade_price) in [(1, 10), (6, 10), (11, 20), (16, 30), (21, 30)].iter() {
+			trade_at(*block, *trade_price);
+		}
+
+		assert_eq!(Twap::buffer((BASE, QUOTE)), RingBuffer { head: 1, len: 4, capacity: 4 });
+		assert_eq!(Twap::observation((BASE, QUOTE), 0).map(|o| o.block), Some(21));
+		assert_eq!(Twap::twap(BASE, QUOTE, 20), None);
+		assert_eq!(Twap::twap(BASE, QUOTE, 15), Some(price(20)));
+		assert_eq!(Twap::twap(BASE, QUOTE, 0), None);
+	});
+}
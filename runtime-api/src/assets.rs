@@ -0,0 +1,52 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Balances of every asset held by an account.
+
+use codec::{Codec, Decode, Encode};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+/// Balance of a single asset held by an account.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct AssetBalance<Balance> {
+	/// Balance that can be transferred, including frozen funds.
+	pub free: Balance,
+	/// Balance reserved by pallets such as staking or identity.
+	pub reserved: Balance,
+	/// Part of the free balance that cannot be withdrawn because of locks.
+	pub frozen: Balance,
+	/// Part of the frozen balance locked by vesting schedules.
+	pub vesting_locked: Balance,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Exposes the native and `orml_tokens` balances of accounts in a single call.
+	pub trait AssetsApi<AccountId, AssetId, Balance> where
+		AccountId: Codec,
+		AssetId: Codec,
+		Balance: Codec,
+	{
+		/// Balances of every asset held by `account`, starting with the native asset.
+		fn account_balances(account: AccountId) -> Vec<(AssetId, AssetBalance<Balance>)>;
+		/// Balances of every asset held by each of `accounts`, in the same order.
+		fn accounts_balances(
+			accounts: Vec<AccountId>,
+		) -> Vec<(AccountId, Vec<(AssetId, AssetBalance<Balance>)>)>;
+	}
+}
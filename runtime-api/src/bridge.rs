@@ -0,0 +1,44 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Status of the Ethereum bridge.
+
+use sp_core::H160;
+use sp_std::prelude::*;
+
+/// Number and hash of an Ethereum block known to the light client, as stored by it.
+pub use snowbridge_ethereum_light_client::EthereumHeaderId;
+
+sp_api::decl_runtime_apis! {
+	/// Exposes the state of the Ethereum light client and the inbound channels.
+	///
+	/// The bridge pallets do not expose their storage, so it is read by prefix. `None` means
+	/// the value is missing or no longer decodes, i.e. the pallet's storage layout changed.
+	pub trait BridgeApi {
+		/// Latest Ethereum block considered final by the light client.
+		fn finalized_block() -> Option<EthereumHeaderId>;
+		/// Ethereum block with the highest total difficulty imported so far.
+		fn best_block() -> Option<EthereumHeaderId>;
+		/// Lowest Ethereum block whose header the light client may still hold.
+		fn oldest_header() -> Option<u64>;
+		/// Source contract and last processed nonce of every inbound channel.
+		fn inbound_nonces() -> Option<Vec<(H160, u64)>>;
+		/// Address of the ERC20 PDEX migration contract.
+		fn erc20_pdex_address() -> H160;
+	}
+}
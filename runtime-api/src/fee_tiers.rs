@@ -0,0 +1,59 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Trading fee tier of an account and the stake it needs for the next tier.
+
+use codec::{Codec, Decode, Encode};
+use sp_runtime::{Perbill, RuntimeDebug};
+
+/// A fee tier above the current tier of an account.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct NextFeeTier<Balance> {
+	/// Index of the tier.
+	pub tier: u32,
+	/// Stake required to reach the tier.
+	pub min_stake: Balance,
+	/// Discount of the tier on trading fees.
+	pub discount: Perbill,
+	/// Stake missing to reach the tier.
+	pub remaining: Balance,
+}
+
+/// Fee tier of an account.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct FeeTierInfo<Balance> {
+	/// Stake counted towards the tier.
+	pub staked: Balance,
+	/// Index of the current tier, if the account reached one.
+	pub tier: Option<u32>,
+	/// Discount of the current tier on trading fees.
+	pub discount: Perbill,
+	/// The next tier, if the account is not in the highest tier yet.
+	pub next: Option<NextFeeTier<Balance>>,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Exposes the trading fee tiers of accounts.
+	pub trait FeeTiersApi<AccountId, Balance> where
+		AccountId: Codec,
+		Balance: Codec,
+	{
+		/// Fee tier of `account` and the stake it needs to reach the next tier.
+		fn fee_tier(account: AccountId) -> FeeTierInfo<Balance>;
+	}
+}
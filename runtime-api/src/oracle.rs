@@ -0,0 +1,32 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Asset prices aggregated by the oracle.
+
+use codec::Codec;
+use sp_runtime::FixedU128;
+
+sp_api::decl_runtime_apis! {
+	/// Exposes the prices of the oracle pallet.
+	pub trait OracleApi<AssetId> where
+		AssetId: Codec,
+	{
+		/// Price of `asset` in USD, if enough feeders reported a fresh price.
+		fn get_price(asset: AssetId) -> Option<FixedU128>;
+	}
+}
@@ -0,0 +1,75 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Projected and unclaimed staking rewards.
+
+use codec::{Codec, Decode, Encode};
+use sp_runtime::{Perbill, RuntimeDebug};
+use sp_std::prelude::*;
+
+/// Projected return of staking behind a validator of the active era.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ValidatorReturn<AccountId, Balance> {
+	/// Stash of the validator.
+	pub validator: AccountId,
+	/// Commission the validator takes from its rewards.
+	pub commission: Perbill,
+	/// Stake exposed by the validator in the active era.
+	pub total_stake: Balance,
+	/// Projected reward of the stake per era.
+	pub era_reward: Balance,
+	/// Projected yearly reward relative to the stake.
+	pub annual_return: Perbill,
+}
+
+/// Projected staking rewards of a stake.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct RewardEstimate<AccountId, Balance> {
+	/// Projected payout of an era to all validators at the current staked ratio.
+	pub era_payout: Balance,
+	/// Share of the total issuance that is staked in the active era.
+	pub staked_ratio: Perbill,
+	/// Projected return behind every validator of the active era.
+	pub validators: Vec<ValidatorReturn<AccountId, Balance>>,
+}
+
+/// Reward of an era that was not paid out yet.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct UnclaimedPayout<AccountId, Balance> {
+	/// The era.
+	pub era: u32,
+	/// Stash of the validator whose payout includes the reward.
+	pub validator: AccountId,
+	/// Reward of the account.
+	pub amount: Balance,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Estimates staking rewards from the reward curve and the staking exposures.
+	pub trait StakingRewardsApi<AccountId, Balance> where
+		AccountId: Codec,
+		Balance: Codec,
+	{
+		/// Projected rewards of nominating every validator of the active era with `stake`,
+		/// assuming all validators earn the same era points.
+		fn estimate_rewards(stake: Balance) -> RewardEstimate<AccountId, Balance>;
+		/// Rewards of `account`, as a validator stash or as a nominator, in eras within the
+		/// history depth that were not paid out yet.
+		fn unclaimed_payouts(account: AccountId) -> Vec<UnclaimedPayout<AccountId, Balance>>;
+	}
+}
@@ -0,0 +1,34 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Time-weighted average prices of asset pairs traded on chain.
+
+use codec::Codec;
+use sp_runtime::FixedU128;
+
+sp_api::decl_runtime_apis! {
+	/// Exposes the time-weighted average prices of the twap pallet.
+	pub trait TwapApi<AssetId, BlockNumber> where
+		AssetId: Codec,
+		BlockNumber: Codec,
+	{
+		/// Average price of `base` in `quote` over at least the last `window` blocks, if
+		/// trades of the pair were observed that long ago.
+		fn twap(base: AssetId, quote: AssetId, window: BlockNumber) -> Option<FixedU128>;
+	}
+}
@@ -0,0 +1,84 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Vesting schedules of an account and the amounts they unlock.
+
+use codec::{Codec, Decode, Encode};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+/// Block at which vested funds unlock, with its expected time.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct Unlock<BlockNumber, Moment> {
+	/// Block number of the unlock.
+	pub block: BlockNumber,
+	/// Expected Unix time of the unlock in milliseconds.
+	pub time: Moment,
+}
+
+/// A vesting schedule and the amount it vested so far.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct VestingScheduleInfo<BlockNumber, Balance, Moment> {
+	/// Block the schedule starts at.
+	pub start: BlockNumber,
+	/// Number of blocks between two unlocks.
+	pub period: BlockNumber,
+	/// Number of unlocks.
+	pub period_count: u32,
+	/// Amount unlocked every period.
+	pub per_period: Balance,
+	/// Total amount of the schedule.
+	pub total: Balance,
+	/// Amount vested to date.
+	pub vested: Balance,
+	/// Next unlock of the schedule, if it is not fully vested.
+	pub next_unlock: Option<Unlock<BlockNumber, Moment>>,
+}
+
+/// Vesting state of an account.
+///
+/// `orml_vesting` drops the schedules that are fully vested when they are claimed, so the
+/// amounts only cover the schedules that are still stored.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct VestingInfo<BlockNumber, Balance, Moment> {
+	/// Vesting schedules of the account.
+	pub schedules: Vec<VestingScheduleInfo<BlockNumber, Balance, Moment>>,
+	/// Total amount of all schedules.
+	pub total: Balance,
+	/// Amount vested to date.
+	pub vested: Balance,
+	/// Amount of the stored schedules already claimed, i.e. no longer locked.
+	pub claimed: Balance,
+	/// Amount vested but not claimed yet.
+	pub claimable: Balance,
+	/// Next unlock of any schedule.
+	pub next_unlock: Option<Unlock<BlockNumber, Moment>>,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Exposes the `orml_vesting` schedules of accounts.
+	pub trait VestingApi<AccountId, BlockNumber, Balance, Moment> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+		Balance: Codec,
+		Moment: Codec,
+	{
+		/// Vesting schedules of `account` and the amounts they vested, claimed and unlock next.
+		fn vesting(account: AccountId) -> VestingInfo<BlockNumber, Balance, Moment>;
+	}
+}
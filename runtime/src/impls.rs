@@ -363,3 +363,738 @@ mod multiplier_tests {
 		});
 	}
 }
+
+#[cfg(test)]
+mod trading_fee_tests {
+	use codec::Encode;
+	use frame_support::{assert_ok, traits::Get, weights::GetDispatchInfo};
+	use pallet_transaction_payment::Multiplier;
+	use pallet_transaction_payment_rpc_runtime_api::runtime_decl_for_TransactionPaymentApi::{
+		TransactionPaymentApi,
+	};
+	use sp_core::sr25519;
+	use sp_runtime::{
+		generic::Era,
+		traits::{One, SignedExtension},
+		transaction_validity::{InvalidTransaction, TransactionValidityError},
+		FixedPointNumber,
+	};
+	use trading_fees_pallet::{ChargeTradingFee, FREE_TRADES_EXHAUSTED, WRAPPED_TRADING_CALL};
+
+	use crate::{
+		constants::currency::*, AccountId, Balance, Balances, BalancesCall, Call, FreeTradePeriod,
+		FreeTradesPerPeriod, Identity, Origin, Runtime, System, SystemCall, UncheckedExtrinsic,
+	};
+
+	fn trader() -> AccountId {
+		AccountId::from([1u8; 32])
+	}
+
+	fn trading_call() -> Call {
+		Call::System(SystemCall::remark(vec![]))
+	}
+
+	fn other_call() -> Call {
+		Call::Balances(BalancesCall::transfer(AccountId::from([2u8; 32]).into(), PDEX))
+	}
+
+	fn set_trading_fee(call: &Call, fee: Balance) {
+		let encoded = call.encode();
+		trading_fees_pallet::Schedule::<Runtime>::insert(encoded[0], encoded[1], fee);
+	}
+
+	fn run_with_multiplier<F>(multiplier: Multiplier, assertions: F)
+	where
+		F: Fn() -> (),
+	{
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		pallet_balances::GenesisConfig::<Runtime> { balances: vec![(trader(), 1_000 * PDEX)] }
+			.assimilate_storage(&mut t)
+			.unwrap();
+		let mut t: sp_io::TestExternalities = t.into();
+		t.execute_with(|| {
+			System::set_block_number(1);
+			pallet_transaction_payment::NextFeeMultiplier::<Runtime>::put(multiplier);
+			assertions()
+		});
+	}
+
+	// charges `call` through the signed extension and returns the amount withdrawn.
+	fn charge(call: &Call, tip: Balance) -> Result<Balance, TransactionValidityError> {
+		let before = Balances::free_balance(&trader());
+		let info = call.get_dispatch_info();
+		let len = call.encode().len();
+		ChargeTradingFee::<Runtime>::from(tip).pre_dispatch(&trader(), call, &info, len)?;
+		Ok(before - Balances::free_balance(&trader()))
+	}
+
+	fn multipliers() -> Vec<Multiplier> {
+		vec![
+			Multiplier::saturating_from_rational(1, 1_000_000_000u128),
+			Multiplier::one(),
+			Multiplier::saturating_from_integer(1_000),
+		]
+	}
+
+	#[test]
+	fn trading_fee_is_independent_of_multiplier() {
+		multipliers().into_iter().for_each(|multiplier| {
+			run_with_multiplier(multiplier, || {
+				set_trading_fee(&trading_call(), CENT);
+				assert_eq!(charge(&trading_call(), 0), Ok(CENT));
+				assert_eq!(charge(&trading_call(), MILLICENT), Ok(CENT + MILLICENT));
+			})
+		})
+	}
+
+	#[test]
+	fn other_calls_are_charged_by_weight() {
+		let fees: Vec<_> = multipliers()
+			.into_iter()
+			.map(|multiplier| {
+				let mut fee = 0;
+				run_with_multiplier(multiplier, || {
+					set_trading_fee(&trading_call(), CENT);
+					fee = charge(&other_call(), 0).unwrap();
+				});
+				fee
+			})
+			.collect();
+		// the weight based fee follows the multiplier.
+		assert!(fees[0] < fees[1] && fees[1] < fees[2], "{:?}", fees);
+	}
+
+	#[test]
+	fn query_info_reports_trading_fee() {
+		run_with_multiplier(Multiplier::saturating_from_integer(1_000), || {
+			set_trading_fee(&trading_call(), CENT);
+			let signed = |call: Call| {
+				let extra = (
+					frame_system::CheckSpecVersion::new(),
+					frame_system::CheckTxVersion::new(),
+					frame_system::CheckGenesis::new(),
+					frame_system::CheckEra::from(Era::Immortal),
+					frame_system::CheckNonce::from(0),
+					frame_system::CheckWeight::new(),
+					ChargeTradingFee::from(MILLICENT),
+					extrinsic_index_pallet::IndexExtrinsic::new(),
+				);
+				let signature = sr25519::Signature::from_raw([0u8; 64]).into();
+				UncheckedExtrinsic::new_signed(call, trader().into(), signature, extra)
+			};
+
+			let info = Runtime::query_info(signed(trading_call()), 100);
+			assert_eq!(info.partial_fee, CENT);
+			let details = Runtime::query_fee_details(signed(trading_call()), 100);
+			assert_eq!(details.final_fee(), CENT + MILLICENT);
+
+			let info = Runtime::query_info(signed(other_call()), 100);
+			assert!(info.partial_fee > CENT, "{:?}", info);
+		})
+	}
+
+	#[test]
+	fn free_trades_are_limited_per_period() {
+		run_with_multiplier(Multiplier::one(), || {
+			set_trading_fee(&trading_call(), 0);
+			for _ in 0..FreeTradesPerPeriod::get() {
+				assert_eq!(charge(&trading_call(), 0), Ok(0));
+			}
+			assert_eq!(
+				charge(&trading_call(), 0),
+				Err(InvalidTransaction::Custom(FREE_TRADES_EXHAUSTED).into())
+			);
+
+			// the allowance is renewed in the next period.
+			System::set_block_number(FreeTradePeriod::get());
+			assert_eq!(charge(&trading_call(), 0), Ok(0));
+		})
+	}
+
+	#[test]
+	fn trading_fee_requires_funds() {
+		run_with_multiplier(Multiplier::one(), || {
+			set_trading_fee(&trading_call(), 1_000 * PDEX);
+			assert_eq!(charge(&trading_call(), 0), Err(InvalidTransaction::Payment.into()));
+		})
+	}
+
+	#[test]
+	fn wrapped_trading_calls_are_rejected() {
+		run_with_multiplier(Multiplier::one(), || {
+			set_trading_fee(&trading_call(), CENT);
+			let wrapped = |call: Call| {
+				vec![
+					Call::Utility(pallet_utility::Call::batch(vec![other_call(), call.clone()])),
+					Call::Proxy(pallet_proxy::Call::proxy(trader(), None, Box::new(call))),
+				]
+			};
+			let rejected = Err(InvalidTransaction::Custom(WRAPPED_TRADING_CALL).into());
+			for call in wrapped(trading_call()) {
+				assert_eq!(charge(&call, 0), rejected);
+				// nested wrappers are unwrapped as well.
+				let nested = Call::Utility(pallet_utility::Call::batch_all(vec![call]));
+				assert_eq!(charge(&nested, 0), rejected);
+			}
+			for call in wrapped(other_call()) {
+				assert!(charge(&call, 0).is_ok());
+			}
+		})
+	}
+
+	#[test]
+	fn multisig_and_sudo_trading_calls_are_rejected() {
+		run_with_multiplier(Multiplier::one(), || {
+			set_trading_fee(&trading_call(), CENT);
+			let as_multi = |call: Vec<u8>| {
+				Call::Multisig(pallet_multisig::Call::as_multi(
+					2,
+					vec![AccountId::from([2u8; 32])],
+					None,
+					call,
+					false,
+					0,
+				))
+			};
+			let sudo_as = |call: Call| {
+				Call::Sudo(pallet_sudo::Call::sudo_as(trader().into(), Box::new(call)))
+			};
+			let rejected = Err(InvalidTransaction::Custom(WRAPPED_TRADING_CALL).into());
+
+			assert_eq!(charge(&as_multi(trading_call().encode()), 0), rejected);
+			let batch = Call::Utility(pallet_utility::Call::batch(vec![trading_call()]));
+			assert_eq!(charge(&as_multi(batch.encode()), 0), rejected);
+			assert_eq!(charge(&sudo_as(trading_call()), 0), rejected);
+
+			assert!(charge(&as_multi(other_call().encode()), 0).is_ok());
+			// undecodable calls are never dispatched by the multisig.
+			assert!(charge(&as_multi(vec![0xff; 4]), 0).is_ok());
+			assert!(charge(&sudo_as(other_call()), 0).is_ok());
+		})
+	}
+
+	#[test]
+	fn verified_traders_trade_within_the_free_allowance() {
+		use pallet_identity::{IdentityInfo, Judgement};
+
+		run_with_multiplier(Multiplier::one(), || {
+			set_trading_fee(&trading_call(), CENT);
+			let registrar = AccountId::from([3u8; 32]);
+			assert_ok!(Identity::add_registrar(Origin::root(), registrar.clone()));
+			assert_ok!(Identity::set_identity(
+				Origin::signed(trader()),
+				Box::new(IdentityInfo::default())
+			));
+			assert_eq!(charge(&trading_call(), 0), Ok(CENT));
+
+			assert_ok!(Identity::provide_judgement(
+				Origin::signed(registrar),
+				0,
+				trader().into(),
+				Judgement::KnownGood
+			));
+			assert_eq!(charge(&trading_call(), 0), Ok(0));
+			assert_eq!(
+				trading_fees_pallet::FreeTrades::<Runtime>::get(trader()).1,
+				1,
+				"free trades count against the allowance"
+			);
+		})
+	}
+}
+
+#[cfg(test)]
+mod vesting_tests {
+	use frame_support::{assert_ok, traits::GenesisBuild};
+	use polkadex_runtime_api::vesting::Unlock;
+
+	use crate::{
+		constants::{currency::*, time::MILLISECS_PER_BLOCK},
+		vesting_info, AccountId, Origin, OrmlVesting, Runtime, System,
+	};
+
+	const NOW: u64 = 1_000_000;
+
+	fn investor() -> AccountId {
+		AccountId::from([1u8; 32])
+	}
+
+	/// Vests 400 PDEX to the investor in four unlocks every 10 blocks from block 10 and, when
+	/// `with_second` is set, 100 PDEX at block 100.
+	fn new_test_ext(with_second: bool) -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		pallet_balances::GenesisConfig::<Runtime> { balances: vec![(investor(), 1_000 * PDEX)] }
+			.assimilate_storage(&mut t)
+			.unwrap();
+		let mut vesting = vec![(investor(), 10, 10, 4, 100 * PDEX)];
+		if with_second {
+			vesting.push((investor(), 0, 100, 1, 100 * PDEX));
+		}
+		let vesting = orml_vesting::GenesisConfig::<Runtime> { vesting };
+		GenesisBuild::<Runtime>::assimilate_storage(&vesting, &mut t).unwrap();
+		let mut ext: sp_io::TestExternalities = t.into();
+		ext.execute_with(|| {
+			System::set_block_number(1);
+			pallet_timestamp::Now::<Runtime>::put(NOW);
+		});
+		ext
+	}
+
+	fn unlock(block: u32, now: u32) -> Option<Unlock<u32, u64>> {
+		Some(Unlock { block, time: NOW + u64::from(block - now) * MILLISECS_PER_BLOCK })
+	}
+
+	#[test]
+	fn nothing_vests_before_the_first_period() {
+		new_test_ext(false).execute_with(|| {
+			let info = vesting_info(&investor());
+			assert_eq!(info.schedules.len(), 1);
+			assert_eq!(
+				(info.total, info.vested, info.claimed, info.claimable),
+				(400 * PDEX, 0, 0, 0)
+			);
+			assert_eq!(info.next_unlock, unlock(20, 1));
+			assert_eq!(info.schedules[0].next_unlock, info.next_unlock);
+		})
+	}
+
+	#[test]
+	fn claims_reduce_the_claimable_amount() {
+		new_test_ext(false).execute_with(|| {
+			System::set_block_number(25);
+			let info = vesting_info(&investor());
+			assert_eq!((info.vested, info.claimed, info.claimable), (100 * PDEX, 0, 100 * PDEX));
+			assert_eq!(info.next_unlock, unlock(30, 25));
+
+			assert_ok!(OrmlVesting::claim(Origin::signed(investor())));
+			let info = vesting_info(&investor());
+			assert_eq!((info.vested, info.claimed, info.claimable), (100 * PDEX, 100 * PDEX, 0));
+
+			System::set_block_number(50);
+			let info = vesting_info(&investor());
+			assert_eq!(
+				(info.vested, info.claimed, info.claimable),
+				(400 * PDEX, 100 * PDEX, 300 * PDEX)
+			);
+			assert_eq!(info.next_unlock, None);
+		})
+	}
+
+	#[test]
+	fn next_unlock_is_the_earliest_of_all_schedules() {
+		new_test_ext(true).execute_with(|| {
+			System::set_block_number(45);
+			let info = vesting_info(&investor());
+			assert_eq!(info.total, 500 * PDEX);
+			assert_eq!(info.next_unlock, unlock(50, 45));
+
+			System::set_block_number(60);
+			assert_eq!(vesting_info(&investor()).next_unlock, unlock(100, 60));
+		})
+	}
+
+	#[test]
+	fn claimed_schedules_are_dropped() {
+		new_test_ext(true).execute_with(|| {
+			System::set_block_number(60);
+			assert_ok!(OrmlVesting::claim(Origin::signed(investor())));
+
+			// the first schedule is fully vested and removed by the claim.
+			let info = vesting_info(&investor());
+			assert_eq!(info.schedules.len(), 1);
+			assert_eq!(
+				(info.total, info.vested, info.claimed, info.claimable),
+				(100 * PDEX, 0, 0, 0)
+			);
+
+			System::set_block_number(100);
+			assert_ok!(OrmlVesting::claim(Origin::signed(investor())));
+			assert_eq!(vesting_info(&investor()), Default::default());
+		})
+	}
+}
+
+#[cfg(test)]
+mod asset_balance_tests {
+	use frame_support::{
+		assert_ok,
+		traits::{GenesisBuild, LockableCurrency, ReservableCurrency, WithdrawReasons},
+	};
+	use orml_traits::MultiReservableCurrency;
+	use polkadex_primitives::assets::AssetId;
+	use polkadex_runtime_api::assets::{runtime_decl_for_AssetsApi::AssetsApi, AssetBalance};
+
+	use crate::{
+		asset_balances, constants::currency::*, AccountId, Balance, Balances, Runtime, Tokens,
+	};
+
+	fn holder() -> AccountId {
+		AccountId::from([1u8; 32])
+	}
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		pallet_balances::GenesisConfig::<Runtime> { balances: vec![(holder(), 1_000 * PDEX)] }
+			.assimilate_storage(&mut t)
+			.unwrap();
+		let tokens = orml_tokens::GenesisConfig::<Runtime> {
+			balances: vec![
+				(holder(), AssetId::Asset(1), 50),
+				(holder(), AssetId::Asset(2), 70),
+				// native balances live in `pallet_balances`, not in `orml_tokens`.
+				(holder(), AssetId::POLKADEX, 5 * PDEX),
+			],
+		};
+		GenesisBuild::<Runtime>::assimilate_storage(&tokens, &mut t).unwrap();
+		let vesting = orml_vesting::GenesisConfig::<Runtime> {
+			vesting: vec![(holder(), 10, 10, 4, 100 * PDEX)],
+		};
+		GenesisBuild::<Runtime>::assimilate_storage(&vesting, &mut t).unwrap();
+		t.into()
+	}
+
+	fn balance(free: Balance, reserved: Balance) -> AssetBalance<Balance> {
+		AssetBalance { free, reserved, frozen: 0, vesting_locked: 0 }
+	}
+
+	#[test]
+	fn native_and_token_balances_are_combined() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Balances::reserve(&holder(), 10 * PDEX));
+			Balances::set_lock(*b"testlock", &holder(), 600 * PDEX, WithdrawReasons::all());
+			assert_ok!(Tokens::reserve(AssetId::Asset(1), &holder(), 20));
+
+			let balances = asset_balances(&holder());
+			assert_eq!(
+				balances[0],
+				(
+					AssetId::POLKADEX,
+					AssetBalance {
+						free: 990 * PDEX,
+						reserved: 10 * PDEX,
+						frozen: 600 * PDEX,
+						vesting_locked: 400 * PDEX,
+					}
+				)
+			);
+			// tokens follow in storage order.
+			assert_eq!(balances.len(), 3);
+			assert!(balances.contains(&(AssetId::Asset(1), balance(30, 20))));
+			assert!(balances.contains(&(AssetId::Asset(2), balance(70, 0))));
+		})
+	}
+
+	#[test]
+	fn batch_query_keeps_the_account_order() {
+		new_test_ext().execute_with(|| {
+			let unknown = AccountId::from([2u8; 32]);
+			let balances = Runtime::accounts_balances(vec![unknown.clone(), holder()]);
+
+			assert_eq!(balances.len(), 2);
+			assert_eq!(balances[0], (unknown, vec![(AssetId::POLKADEX, balance(0, 0))]));
+			assert_eq!(balances[1], (holder(), asset_balances(&holder())));
+		})
+	}
+}
+
+#[cfg(test)]
+mod staking_reward_tests {
+	use pallet_staking::{
+		ActiveEraInfo, EraRewardPoints, Exposure, IndividualExposure, StakingLedger, ValidatorPrefs,
+	};
+	use polkadex_runtime_api::staking::UnclaimedPayout;
+	use sp_runtime::Perbill;
+
+	use crate::{
+		constants::currency::*, estimate_staking_rewards, unclaimed_staking_payouts, AccountId,
+		Balance, Runtime,
+	};
+
+	fn account(n: u8) -> AccountId {
+		AccountId::from([n; 32])
+	}
+
+	fn validator() -> AccountId {
+		account(1)
+	}
+
+	fn controller() -> AccountId {
+		account(2)
+	}
+
+	fn nominator() -> AccountId {
+		account(3)
+	}
+
+	fn other_validator() -> AccountId {
+		account(4)
+	}
+
+	/// Starts era 2 with 4_000 PDEX issued.
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		pallet_balances::GenesisConfig::<Runtime> {
+			balances: (1..=4).map(|n| (account(n), 1_000 * PDEX)).collect(),
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+		let mut ext: sp_io::TestExternalities = t.into();
+		ext.execute_with(|| {
+			pallet_staking::CurrentEra::<Runtime>::put(2);
+			pallet_staking::ActiveEra::<Runtime>::put(ActiveEraInfo { index: 2, start: None });
+		});
+		ext
+	}
+
+	fn expose(era: u32, validator: AccountId, own: Balance, nominated: Balance, commission: u32) {
+		let exposure = Exposure {
+			total: own + nominated,
+			own,
+			others: vec![IndividualExposure { who: nominator(), value: nominated }],
+		};
+		pallet_staking::ErasStakers::<Runtime>::insert(era, &validator, exposure.clone());
+		pallet_staking::ErasStakersClipped::<Runtime>::insert(era, &validator, exposure);
+		pallet_staking::ErasValidatorPrefs::<Runtime>::insert(
+			era,
+			&validator,
+			ValidatorPrefs { commission: Perbill::from_percent(commission), blocked: false },
+		);
+	}
+
+	/// Pays 1_000 PDEX in era 1, half of it to the validator.
+	fn reward_era_one(claimed_rewards: Vec<u32>) {
+		expose(1, validator(), 50 * PDEX, 50 * PDEX, 10);
+		pallet_staking::ErasValidatorReward::<Runtime>::insert(1, 1_000 * PDEX);
+		pallet_staking::ErasRewardPoints::<Runtime>::insert(
+			1,
+			EraRewardPoints {
+				total: 200,
+				individual: vec![(validator(), 100), (other_validator(), 100)]
+					.into_iter()
+					.collect(),
+			},
+		);
+		pallet_staking::Bonded::<Runtime>::insert(validator(), controller());
+		pallet_staking::Ledger::<Runtime>::insert(
+			controller(),
+			StakingLedger {
+				stash: validator(),
+				total: 50 * PDEX,
+				active: 50 * PDEX,
+				unlocking: vec![],
+				claimed_rewards,
+			},
+		);
+	}
+
+	#[test]
+	fn estimate_splits_the_era_payout_by_stake_and_commission() {
+		new_test_ext().execute_with(|| {
+			expose(2, validator(), 50 * PDEX, 50 * PDEX, 0);
+			expose(2, other_validator(), 100 * PDEX, 200 * PDEX, 50);
+			pallet_staking::ErasTotalStake::<Runtime>::insert(2, 400 * PDEX);
+
+			let stake = 100 * PDEX;
+			let estimate = estimate_staking_rewards(stake);
+			assert_eq!(estimate.staked_ratio, Perbill::from_percent(10));
+			assert!(estimate.era_payout > 0);
+
+			let validator_payout = estimate.era_payout / 2;
+			let reward_of = |validator: AccountId| {
+				estimate
+					.validators
+					.iter()
+					.find(|estimate| estimate.validator == validator)
+					.map(|estimate| estimate.era_reward)
+					.unwrap()
+			};
+			assert_eq!(estimate.validators.len(), 2);
+			assert_eq!(reward_of(validator()), Perbill::from_percent(50) * validator_payout);
+			assert_eq!(
+				reward_of(other_validator()),
+				Perbill::from_percent(25) *
+					(validator_payout - Perbill::from_percent(50) * validator_payout)
+			);
+		})
+	}
+
+	#[test]
+	fn unclaimed_payouts_follow_the_payout_split() {
+		new_test_ext().execute_with(|| {
+			reward_era_one(vec![]);
+
+			// 500 PDEX for the validator: 50 PDEX of commission and the rest split by stake.
+			assert_eq!(
+				unclaimed_staking_payouts(&nominator()),
+				vec![UnclaimedPayout { era: 1, validator: validator(), amount: 225 * PDEX }]
+			);
+			assert_eq!(
+				unclaimed_staking_payouts(&validator()),
+				vec![UnclaimedPayout { era: 1, validator: validator(), amount: 275 * PDEX }]
+			);
+			assert_eq!(unclaimed_staking_payouts(&other_validator()), vec![]);
+		})
+	}
+
+	#[test]
+	fn claimed_eras_are_skipped() {
+		new_test_ext().execute_with(|| {
+			reward_era_one(vec![1]);
+
+			assert_eq!(unclaimed_staking_payouts(&nominator()), vec![]);
+			assert_eq!(unclaimed_staking_payouts(&validator()), vec![]);
+		})
+	}
+}
+
+#[cfg(test)]
+mod staked_pdex_tests {
+	use fee_tiers_pallet::StakedBalance;
+	use pallet_staking::{StakingLedger, UnlockChunk};
+
+	use crate::{constants::currency::*, AccountId, Runtime, StakedPdex};
+
+	fn stash() -> AccountId {
+		AccountId::from([1u8; 32])
+	}
+
+	fn controller() -> AccountId {
+		AccountId::from([2u8; 32])
+	}
+
+	#[test]
+	fn stash_and_controller_hold_the_active_stake() {
+		let t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		sp_io::TestExternalities::from(t).execute_with(|| {
+			assert_eq!(StakedPdex::staked(&stash()), 0);
+
+			pallet_staking::Bonded::<Runtime>::insert(stash(), controller());
+			pallet_staking::Ledger::<Runtime>::insert(
+				controller(),
+				StakingLedger {
+					stash: stash(),
+					total: 100 * PDEX,
+					active: 60 * PDEX,
+					unlocking: vec![UnlockChunk { value: 40 * PDEX, era: 3 }],
+					claimed_rewards: vec![],
+				},
+			);
+
+			assert_eq!(StakedPdex::staked(&stash()), 60 * PDEX);
+			assert_eq!(StakedPdex::staked(&controller()), 60 * PDEX);
+			assert_eq!(StakedPdex::staked(&AccountId::from([3u8; 32])), 0);
+		})
+	}
+}
+
+#[cfg(test)]
+mod compound_tests {
+	use frame_support::{assert_noop, assert_ok};
+	use liquidity_mining_pallet::{Compound, PoolId, RewardKind};
+	use pallet_staking::RewardDestination;
+	use polkadex_primitives::assets::AssetId;
+	use sp_runtime::{traits::StaticLookup, DispatchError};
+
+	use crate::{
+		constants::currency::*, AccountId, Balance, CompoundIntoStake, Indices, Origin, Runtime,
+		Staking,
+	};
+
+	fn account(n: u8) -> AccountId {
+		AccountId::from([n; 32])
+	}
+
+	fn pool() -> PoolId<(AssetId, AssetId)> {
+		PoolId { market: (AssetId::POLKADEX, AssetId::Asset(1)), kind: RewardKind::Liquidity }
+	}
+
+	fn active_stake(controller: AccountId) -> Option<Balance> {
+		Staking::ledger(controller).map(|ledger| ledger.active)
+	}
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		pallet_balances::GenesisConfig::<Runtime> {
+			balances: (1..=3).map(|n| (account(n), 1_000 * PDEX)).collect(),
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+		let mut ext: sp_io::TestExternalities = t.into();
+		ext.execute_with(|| {
+			assert_ok!(Staking::bond(
+				Origin::signed(account(1)),
+				Indices::unlookup(account(2)),
+				100 * PDEX,
+				RewardDestination::Staked
+			));
+		});
+		ext
+	}
+
+	#[test]
+	fn stashes_compound_into_their_stake() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(CompoundIntoStake::compound(&account(1), &pool(), 10 * PDEX));
+			assert_eq!(active_stake(account(2)), Some(110 * PDEX));
+		})
+	}
+
+	#[test]
+	fn unbonded_accounts_are_bonded() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(CompoundIntoStake::compound(&account(3), &pool(), 10 * PDEX));
+			assert_eq!(Staking::bonded(account(3)), Some(account(3)));
+			assert_eq!(active_stake(account(3)), Some(10 * PDEX));
+		})
+	}
+
+	#[test]
+	fn controllers_cannot_compound() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				CompoundIntoStake::compound(&account(2), &pool(), 10 * PDEX),
+				DispatchError::Other("Controllers cannot compound, claim instead")
+			);
+		})
+	}
+}
+
+#[cfg(test)]
+mod dispatch_whitelist_tests {
+	use codec::Encode;
+	use frame_support::traits::{Filter, OnRuntimeUpgrade};
+	use sp_core::U256;
+	use sp_runtime::traits::StaticLookup;
+
+	use crate::{AccountId, Call, CallFilter, DispatchWhitelist, Indices, Runtime};
+
+	fn mint() -> Call {
+		Call::ERC20PDEX(erc20_pdex_migration_pallet::Call::mint(
+			Default::default(),
+			Default::default(),
+			Indices::unlookup(AccountId::from([1u8; 32])),
+			U256::from(1),
+		))
+	}
+
+	#[test]
+	fn upgrade_keeps_migration_calls_dispatchable() {
+		let t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		sp_io::TestExternalities::from(t).execute_with(|| {
+			assert!(!CallFilter::filter(&mint()));
+
+			DispatchWhitelist::on_runtime_upgrade();
+
+			assert!(CallFilter::filter(&mint()));
+			assert_eq!(
+				DispatchWhitelist::check(Some(Default::default()), &mint().encode()),
+				Ok(())
+			);
+			let transfer = Call::Balances(pallet_balances::Call::transfer(
+				Indices::unlookup(AccountId::from([1u8; 32])),
+				1,
+			));
+			assert!(!CallFilter::filter(&transfer));
+		})
+	}
+}
@@ -33,11 +33,12 @@ use frame_support::{
     },
     weights::{
         constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
-        DispatchClass, IdentityFee, Weight,
+        DispatchClass, DispatchInfo, IdentityFee, Weight,
     },
 };
 use frame_support::{PalletId, traits::InstanceFilter};
-use frame_support::traits::{Filter, OnUnbalanced, Contains, Everything};
+use frame_support::storage::migration::{get_storage_value, have_storage_value};
+use frame_support::traits::{Filter, Get, OnUnbalanced, Contains, Everything};
 use frame_system::{
     EnsureOneOf,
     EnsureRoot, limits::{BlockLength, BlockWeights}, RawOrigin,
@@ -59,7 +60,14 @@ use pallet_session::historical as pallet_session_historical;
 #[cfg(any(feature = "std", test))]
 pub use pallet_staking::StakerStatus;
 pub use pallet_transaction_payment::{CurrencyAdapter, Multiplier, TargetedFeeAdjustment};
-use pallet_transaction_payment::{FeeDetails, RuntimeDispatchInfo};
+use pallet_transaction_payment::{FeeDetails, InclusionFee, RuntimeDispatchInfo};
+use polkadex_runtime_api::{
+    assets::AssetBalance,
+    bridge::EthereumHeaderId,
+    fee_tiers::{FeeTierInfo, NextFeeTier},
+    staking::{RewardEstimate, UnclaimedPayout, ValidatorReturn},
+    vesting::{Unlock, VestingInfo, VestingScheduleInfo},
+};
 pub use polkadex_primitives::{AccountId, Signature};
 pub use polkadex_primitives::{AccountIndex, Balance, BlockNumber, Hash, Index, Moment};
 use polkadex_primitives::assets::AssetId;
@@ -123,10 +131,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     // and set impl_version to 0. If only runtime
     // implementation changes and behavior does not, then leave spec_version as
     // is and increment impl_version.
-    spec_version: 268,
+    spec_version: 269,
     impl_version: 0,
     apis: RUNTIME_API_VERSIONS,
-    transaction_version: 2,
+    transaction_version: 3,
 };
 
 /// The BABE epoch configuration at genesis.
@@ -419,6 +427,164 @@ impl pallet_transaction_payment::Config for Runtime {
     TargetedFeeAdjustment<Self, TargetBlockFullness, AdjustmentVariable, MinimumMultiplier>;
 }
 
+parameter_types! {
+    pub const FreeTradesPerPeriod: u32 = 100;
+    pub const FreeTradePeriod: BlockNumber = 10 * MINUTE;
+}
+
+impl trading_fees_pallet::Config for Runtime {
+    type Event = Event;
+    type Currency = Balances;
+    type OnTradingFee = DealWithFees;
+    type FeeDiscount = FeeTiers;
+    type GoodStanding = IdentityVerified;
+    type WrappedCalls = DispatchedCalls;
+    type FreeTradesPerPeriod = FreeTradesPerPeriod;
+    type FreeTradePeriod = FreeTradePeriod;
+    type UpdateOrigin = EnsureRootOrHalfCouncil;
+}
+
+/// Calls dispatched by `Utility`, `Proxy`, `Multisig` and `Sudo` wrapper calls, which are
+/// charged by weight and must not carry trading calls.
+///
+/// `Multisig::as_multi` carries the call encoded, and a stored call is only dispatched once
+/// approved, so the call is decoded when it is submitted. Calls that do not decode are never
+/// dispatched.
+pub struct DispatchedCalls;
+
+impl trading_fees_pallet::WrappedCalls<Call> for DispatchedCalls {
+    fn any_wrapped(call: &Call, predicate: impl Fn(&Call) -> bool) -> bool {
+        match call {
+            Call::Utility(pallet_utility::Call::batch(calls)) |
+            Call::Utility(pallet_utility::Call::batch_all(calls)) => calls.iter().any(predicate),
+            Call::Utility(pallet_utility::Call::as_derivative(_, call)) |
+            Call::Proxy(pallet_proxy::Call::proxy(_, _, call)) |
+            Call::Proxy(pallet_proxy::Call::proxy_announced(_, _, _, call)) |
+            Call::Multisig(pallet_multisig::Call::as_multi_threshold_1(_, call)) |
+            Call::Sudo(pallet_sudo::Call::sudo_as(_, call)) => predicate(call),
+            Call::Multisig(pallet_multisig::Call::as_multi(_, _, _, call, _, _)) =>
+                Call::decode(&mut &call[..]).map_or(false, |call| predicate(&call)),
+            _ => false,
+        }
+    }
+}
+
+/// Active stake bonded with `pallet_staking` by an account, excluding funds that are
+/// unbonding.
+///
+/// Both the stash and its controller count as holding the stake, so fee tiers apply whichever
+/// of the two accounts trades. A stash is resolved before a controller.
+pub struct StakedPdex;
+
+impl fee_tiers_pallet::StakedBalance<AccountId, Balance> for StakedPdex {
+    fn staked(who: &AccountId) -> Balance {
+        Staking::bonded(who)
+            .and_then(|controller| Staking::ledger(controller))
+            .or_else(|| Staking::ledger(who))
+            .map(|ledger| ledger.active)
+            .unwrap_or_default()
+    }
+}
+
+parameter_types! {
+    pub const MaxFeeTiers: u32 = 16;
+}
+
+impl fee_tiers_pallet::Config for Runtime {
+    type Event = Event;
+    type Balance = Balance;
+    type Staked = StakedPdex;
+    type MaxTiers = MaxFeeTiers;
+    type UpdateOrigin = EnsureRootOrHalfCouncil;
+}
+
+/// Compounds liquidity mining rewards by bonding them as stake.
+///
+/// Stashes bond the rewards as additional stake, and accounts that are not bonded yet are
+/// bonded as their own controller with rewards paid into the stake. A controller cannot bond
+/// funds of its own for its stash, so it has to claim its rewards instead.
+///
+/// `LiquidityMining` is left out of `construct_runtime!` until a trading pallet reports LP
+/// shares or maker volume, as no campaign could be started without one.
+pub struct CompoundIntoStake;
+
+impl liquidity_mining_pallet::Compound<AccountId, (AssetId, AssetId), Balance>
+    for CompoundIntoStake
+{
+    fn compound(
+        who: &AccountId,
+        _pool: &liquidity_mining_pallet::PoolId<(AssetId, AssetId)>,
+        amount: Balance,
+    ) -> sp_runtime::DispatchResult {
+        let origin = Origin::signed(who.clone());
+        if Staking::bonded(who).is_some() {
+            Staking::bond_extra(origin, amount)
+        } else if Staking::ledger(who).is_some() {
+            Err(sp_runtime::DispatchError::Other("Controllers cannot compound, claim instead"))
+        } else {
+            let controller = Indices::unlookup(who.clone());
+            Staking::bond(origin, controller, amount, pallet_staking::RewardDestination::Staked)
+        }
+    }
+}
+
+/// Accounts whose identity a registrar judged `Reasonable` or `KnownGood`.
+pub struct IdentityVerified;
+
+impl Contains<AccountId> for IdentityVerified {
+    fn contains(who: &AccountId) -> bool {
+        use pallet_identity::Judgement;
+
+        Identity::identity(who).map_or(false, |registration| {
+            registration.judgements.iter().any(|(_, judgement)| {
+                matches!(judgement, Judgement::Reasonable | Judgement::KnownGood)
+            })
+        })
+    }
+}
+
+parameter_types! {
+    pub const IdoPalletId: PalletId = PalletId(*b"pdx/ido_");
+}
+
+impl ido_pallet::Config for Runtime {
+    type Event = Event;
+    type Currency = Currencies;
+    type NativeAssetId = GetNativeCurrencyId;
+    type PalletId = IdoPalletId;
+    type Verified = IdentityVerified;
+    type GovernanceOrigin = EnsureRootOrHalfCouncil;
+}
+
+parameter_types! {
+    pub const MaxOracleFeeders: u32 = 16;
+    pub const MinPriceObservations: u32 = 3;
+    pub const PriceStalenessLimit: BlockNumber = 10 * MINUTE;
+    pub const PriceFetchInterval: BlockNumber = MINUTE;
+}
+
+impl oracle_pallet::Config for Runtime {
+    type AuthorityId = oracle_pallet::crypto::OracleAuthId;
+    type Event = Event;
+    type AssetId = AssetId;
+    type MaxFeeders = MaxOracleFeeders;
+    type MinObservations = MinPriceObservations;
+    type StalenessLimit = PriceStalenessLimit;
+    type FetchInterval = PriceFetchInterval;
+    type FeederOrigin = EnsureRootOrHalfCouncil;
+}
+
+parameter_types! {
+    pub const TwapObservationPeriod: BlockNumber = MINUTE;
+    pub const TwapBufferSize: u32 = 24 * 60;
+}
+
+impl twap_pallet::Config for Runtime {
+    type AssetId = AssetId;
+    type ObservationPeriod = TwapObservationPeriod;
+    type BufferSize = TwapBufferSize;
+}
+
 parameter_types! {
     pub const MinimumPeriod: Moment = SLOT_DURATION / 2;
 }
@@ -816,7 +982,8 @@ impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for R
             frame_system::CheckEra::<Runtime>::from(era),
             frame_system::CheckNonce::<Runtime>::from(nonce),
             frame_system::CheckWeight::<Runtime>::new(),
-            pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
+            trading_fees_pallet::ChargeTradingFee::<Runtime>::from(tip),
+            extrinsic_index_pallet::IndexExtrinsic::<Runtime>::new(),
         );
         let raw_payload = SignedPayload::new(call, extra)
             .map_err(|e| {
@@ -958,11 +1125,15 @@ impl snowbridge_ethereum_light_client::Config for Runtime {
     type WeightInfo = weights::ethereum_light_client_weights::WeightInfo<Runtime>;
 }
 
+/// Only calls whitelisted by governance can be dispatched from Ethereum.
+///
+/// Source contract restrictions are enforced by `DispatchWhitelist` before a message
+/// reaches `Dispatch`, so this filter only checks the pallet and call index.
 pub struct CallFilter;
 
 impl Filter<Call> for CallFilter {
     fn filter(call: &Call) -> bool {
-        matches!(call, Call::ERC20PDEX(_))
+        DispatchWhitelist::is_whitelisted(&call.encode())
     }
 }
 
@@ -976,13 +1147,96 @@ impl snowbridge_dispatch::Config for Runtime {
 
 use snowbridge_basic_channel::inbound as basic_inbound_channel;
 
+/// Storage prefix of `EthereumLightClient`, used to report its state through `BridgeApi`
+/// and to prune its headers.
+const ETHEREUM_LIGHT_CLIENT_PREFIX: &[u8] = b"EthereumLightClient";
+/// Storage prefix of `BasicInboundChannel`, used to report its state through `BridgeApi`.
+const BASIC_INBOUND_CHANNEL_PREFIX: &[u8] = b"BasicInboundModule";
+
 impl basic_inbound_channel::Config for Runtime {
     type Event = Event;
     type Verifier = snowbridge_ethereum_light_client::Module<Runtime>;
-    type MessageDispatch = snowbridge_dispatch::Module<Runtime>;
+    type MessageDispatch = dispatch_whitelist_pallet::Module<Runtime>;
     type WeightInfo = weights::basic_channel_inbound_weights::WeightInfo<Runtime>;
 }
 
+/// Calls whitelisted when the whitelist is added to a running chain: the ERC20 PDEX migration
+/// calls, which the previous hard-coded filter accepted from any source contract.
+pub struct InitialDispatchWhitelist;
+
+impl Get<Vec<(u8, u8, Option<sp_core::H160>)>> for InitialDispatchWhitelist {
+    fn get() -> Vec<(u8, u8, Option<sp_core::H160>)> {
+        let mint = Call::ERC20PDEX(erc20_pdex_migration_pallet::Call::mint(
+            Default::default(),
+            Default::default(),
+            Indices::unlookup(Default::default()),
+            Default::default(),
+        ));
+        match mint.encode()[..] {
+            [pallet_index, call_index, ..] => vec![(pallet_index, call_index, None)],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl dispatch_whitelist_pallet::Config for Runtime {
+    type Event = Event;
+    type MessageId = MessageId;
+    type Dispatcher = snowbridge_dispatch::Module<Runtime>;
+    type WhitelistOrigin = EnsureRootOrHalfCouncil;
+    type InitialWhitelist = InitialDispatchWhitelist;
+}
+
+parameter_types! {
+    pub const EthereumLightClientPrefix: &'static [u8] = ETHEREUM_LIGHT_CLIENT_PREFIX;
+    // About a day and a half of Ethereum blocks past finalization.
+    pub const EthereumHeadersToKeep: u64 = 10_000;
+    pub const MaxEthereumHeadersPrunedPerBlock: u64 = 8;
+    // Initial header of the light client in the genesis of every Polkadex chain.
+    pub const EthereumOldestHeader: u64 = 11_010_246;
+}
+
+impl eth_header_pruning_pallet::Config for Runtime {
+    type LightClientPrefix = EthereumLightClientPrefix;
+    type DescendantsUntilFinalized = DescendantsUntilFinalized;
+    type HeadersToKeep = EthereumHeadersToKeep;
+    type MaxPrunedPerBlock = MaxEthereumHeadersPrunedPerBlock;
+    type OldestHeader = EthereumOldestHeader;
+}
+
+/// Flat fee charged by `ChargeTradingFee` for a signed trading call, without the tip, or
+/// `None` for extrinsics charged by weight.
+fn trading_fee(uxt: &UncheckedExtrinsic) -> Option<Balance> {
+    let (address, _, _) = uxt.signature.as_ref()?;
+    let who = Indices::lookup(address.clone()).ok()?;
+    TradingFees::fee_for(&who, &uxt.function)
+}
+
+/// Fee paid by an applied extrinsic, as charged by `ChargeTradingFee`.
+pub struct ExtrinsicFee;
+
+impl extrinsic_index_pallet::ComputeFee<Balance> for ExtrinsicFee {
+    fn compute_fee(extrinsic: &[u8], info: &DispatchInfo) -> Balance {
+        let uxt = match UncheckedExtrinsic::decode(&mut &extrinsic[..]) {
+            Ok(uxt) => uxt,
+            Err(_) => return 0,
+        };
+        let tip = match &uxt.signature {
+            Some((_, _, extra)) => extra.6.tip(),
+            None => return 0,
+        };
+        match trading_fee(&uxt) {
+            Some(fee) => fee.saturating_add(tip),
+            None => TransactionPayment::compute_fee(extrinsic.len() as u32, info, tip),
+        }
+    }
+}
+
+impl extrinsic_index_pallet::Config for Runtime {
+    type Balance = Balance;
+    type ComputeFee = ExtrinsicFee;
+}
+
 impl erc20_pdex_migration_pallet::Config for Runtime{
     type Event = Event;
     type Balance = Balance;
@@ -1033,7 +1287,15 @@ construct_runtime!(
         Dispatch: snowbridge_dispatch::{Pallet, Call, Storage, Event<T>, Origin} = 33,
         BasicInboundChannel: basic_inbound_channel::{Pallet, Call, Config, Storage, Event} = 34,
         EthereumLightClient: snowbridge_ethereum_light_client::{Pallet, Call, Storage, Event, Config} = 35,
-        ERC20PDEX: erc20_pdex_migration_pallet::{Pallet, Call, Storage, Config, Event<T>} = 40
+        ERC20PDEX: erc20_pdex_migration_pallet::{Pallet, Call, Storage, Config, Event<T>} = 40,
+        DispatchWhitelist: dispatch_whitelist_pallet::{Pallet, Call, Storage, Config, Event<T>} = 41,
+        EthereumHeaderPruning: eth_header_pruning_pallet::{Pallet, Storage, Config} = 42,
+        ExtrinsicIndex: extrinsic_index_pallet::{Pallet} = 43,
+        TradingFees: trading_fees_pallet::{Pallet, Call, Storage, Event<T>} = 44,
+        FeeTiers: fee_tiers_pallet::{Pallet, Call, Storage, Event<T>} = 45,
+        Ido: ido_pallet::{Pallet, Call, Storage, Event<T>} = 47,
+        Oracle: oracle_pallet::{Pallet, Call, Storage, Event<T>} = 48,
+        Twap: twap_pallet::{Pallet, Storage} = 49
     }
 );
 
@@ -1059,7 +1321,8 @@ pub type SignedExtra = (
     frame_system::CheckEra<Runtime>,
     frame_system::CheckNonce<Runtime>,
     frame_system::CheckWeight<Runtime>,
-    pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+    trading_fees_pallet::ChargeTradingFee<Runtime>,
+    extrinsic_index_pallet::IndexExtrinsic<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -1077,6 +1340,214 @@ pub type Executive = frame_executive::Executive<
     (),
 >;
 
+/// Amount of the native balance of `account` locked by `orml_vesting`.
+fn vesting_locked(account: &AccountId) -> Balance {
+    Balances::locks(account)
+        .iter()
+        .find(|lock| lock.id == orml_vesting::VESTING_LOCK_ID)
+        .map(|lock| lock.amount)
+        .unwrap_or_default()
+}
+
+/// Balances of every asset held by `account`, starting with the native asset.
+fn asset_balances(account: &AccountId) -> Vec<(AssetId, AssetBalance<Balance>)> {
+    let native = System::account(account).data;
+    let vesting_locked = vesting_locked(account);
+    let mut balances = vec![(
+        AssetId::POLKADEX,
+        AssetBalance {
+            free: native.free,
+            reserved: native.reserved,
+            frozen: native.misc_frozen.max(native.fee_frozen),
+            vesting_locked,
+        },
+    )];
+    balances.extend(
+        orml_tokens::Accounts::<Runtime>::iter_prefix(account)
+            .filter(|(asset, _)| *asset != AssetId::POLKADEX)
+            .map(|(asset, data)| {
+                let balance = AssetBalance {
+                    free: data.free,
+                    reserved: data.reserved,
+                    frozen: data.frozen,
+                    vesting_locked: Zero::zero(),
+                };
+                (asset, balance)
+            }),
+    );
+    balances
+}
+
+/// Vesting schedules of `account` at the current block.
+///
+/// `orml_vesting` only keeps the amount that is still locked, so the claimed amount is the
+/// part of the stored schedules that is no longer locked. Claiming removes the schedules that
+/// are fully vested, so their amounts no longer count towards `total`, `vested` or `claimed`.
+fn vesting_info(account: &AccountId) -> VestingInfo<BlockNumber, Balance, Moment> {
+    let now = System::block_number();
+    let unlock_at = |block: BlockNumber| Unlock {
+        block,
+        time: Timestamp::now().saturating_add(
+            Moment::from(block.saturating_sub(now)).saturating_mul(MILLISECS_PER_BLOCK),
+        ),
+    };
+
+    let mut info = VestingInfo::default();
+    for schedule in OrmlVesting::vesting_schedules(account).iter() {
+        let total = schedule.total_amount().unwrap_or(Balance::max_value());
+        let vested = total.saturating_sub(schedule.locked_amount(now));
+        let unlocked_periods = now
+            .saturating_sub(schedule.start)
+            .checked_div(schedule.period)
+            .unwrap_or(schedule.period_count);
+        let next_unlock = (unlocked_periods < schedule.period_count).then(|| {
+            let periods = unlocked_periods.saturating_add(1);
+            unlock_at(schedule.start.saturating_add(schedule.period.saturating_mul(periods)))
+        });
+
+        info.total = info.total.saturating_add(total);
+        info.vested = info.vested.saturating_add(vested);
+        info.next_unlock = match (info.next_unlock, next_unlock) {
+            (Some(current), Some(next)) if current.block <= next.block => Some(current),
+            (current, None) => current,
+            (_, next) => next,
+        };
+        info.schedules.push(VestingScheduleInfo {
+            start: schedule.start,
+            period: schedule.period,
+            period_count: schedule.period_count,
+            per_period: schedule.per_period,
+            total,
+            vested,
+            next_unlock,
+        });
+    }
+    info.claimed = info.total.saturating_sub(vesting_locked(account));
+    info.claimable = info.vested.saturating_sub(info.claimed);
+    info
+}
+
+/// Fee tier of `account` and the stake it needs to reach the next tier.
+fn fee_tier_info(account: &AccountId) -> FeeTierInfo<Balance> {
+    use fee_tiers_pallet::StakedBalance;
+
+    let staked = StakedPdex::staked(account);
+    let (current, next) = FeeTiers::tiers_around(staked);
+    FeeTierInfo {
+        staked,
+        tier: current.map(|(index, _)| index),
+        discount: current.map(|(_, tier)| tier.discount).unwrap_or_default(),
+        next: next.map(|(index, tier)| NextFeeTier {
+            tier: index,
+            min_stake: tier.min_stake,
+            discount: tier.discount,
+            remaining: tier.min_stake.saturating_sub(staked),
+        }),
+    }
+}
+
+/// Projected rewards of nominating every validator of the active era with `stake`.
+fn estimate_staking_rewards(stake: Balance) -> RewardEstimate<AccountId, Balance> {
+    use pallet_staking::EraPayout;
+
+    let era = Staking::active_era().map(|era| era.index).unwrap_or_default();
+    let total_issuance = Balances::total_issuance();
+    let total_staked = Staking::eras_total_stake(era);
+    let era_duration_in_blocks = EPOCH_DURATION_IN_BLOCKS * SessionsPerEra::get();
+    let eras_per_year = Balance::from(365 * DAY / era_duration_in_blocks);
+    let (era_payout, _) = <Runtime as pallet_staking::Config>::EraPayout::era_payout(
+        total_staked,
+        total_issuance,
+        Moment::from(era_duration_in_blocks) * MILLISECS_PER_BLOCK,
+    );
+
+    let exposures: Vec<_> = pallet_staking::ErasStakers::<Runtime>::iter_prefix(era).collect();
+    let validator_payout = era_payout / (exposures.len() as Balance).max(1);
+    let validators = exposures
+        .into_iter()
+        .map(|(validator, exposure)| {
+            let commission = Staking::eras_validator_prefs(era, &validator).commission;
+            let leftover = validator_payout.saturating_sub(commission * validator_payout);
+            let era_reward =
+                Perbill::from_rational(stake, exposure.total.saturating_add(stake)) * leftover;
+            ValidatorReturn {
+                validator,
+                commission,
+                total_stake: exposure.total,
+                era_reward,
+                annual_return: Perbill::from_rational(
+                    era_reward.saturating_mul(eras_per_year),
+                    stake.max(1),
+                ),
+            }
+        })
+        .collect();
+
+    RewardEstimate {
+        era_payout,
+        staked_ratio: Perbill::from_rational(total_staked, total_issuance.max(1)),
+        validators,
+    }
+}
+
+/// Rewards of `account` in eras within the history depth that were not paid out yet.
+///
+/// Mirrors the split of `pallet_staking::Pallet::payout_stakers`: the validator takes its
+/// commission and both the validator and its nominators share the rest by exposure.
+fn unclaimed_staking_payouts(account: &AccountId) -> Vec<UnclaimedPayout<AccountId, Balance>> {
+    let current_era = match Staking::current_era() {
+        Some(era) => era,
+        None => return Vec::new(),
+    };
+    let active_era = Staking::active_era().map(|era| era.index).unwrap_or_default();
+    let first_era = current_era.saturating_sub(Staking::history_depth());
+
+    let mut payouts = Vec::new();
+    for era in first_era..active_era {
+        let era_payout = match Staking::eras_validator_reward(era) {
+            Some(era_payout) => era_payout,
+            None => continue,
+        };
+        let points = Staking::eras_reward_points(era);
+        for (validator, exposure) in pallet_staking::ErasStakersClipped::<Runtime>::iter_prefix(era) {
+            let is_validator = &validator == account;
+            let nominated = exposure
+                .others
+                .iter()
+                .find(|nominator| &nominator.who == account)
+                .map(|nominator| nominator.value);
+            if !is_validator && nominated.is_none() {
+                continue
+            }
+            let claimed = Staking::bonded(&validator)
+                .and_then(|controller| Staking::ledger(&controller))
+                .map(|ledger| ledger.claimed_rewards.contains(&era))
+                .unwrap_or(true);
+            if claimed {
+                continue
+            }
+
+            let validator_points = points.individual.get(&validator).copied().unwrap_or_default();
+            let validator_payout = Perbill::from_rational(validator_points, points.total) * era_payout;
+            let commission = Staking::eras_validator_prefs(era, &validator).commission;
+            let commission_payout = commission * validator_payout;
+            let leftover = validator_payout.saturating_sub(commission_payout);
+
+            let mut amount = nominated
+                .map(|value| Perbill::from_rational(value, exposure.total) * leftover)
+                .unwrap_or_default();
+            if is_validator {
+                let own = Perbill::from_rational(exposure.own, exposure.total) * leftover;
+                amount = amount.saturating_add(commission_payout).saturating_add(own);
+            }
+            if !amount.is_zero() {
+                payouts.push(UnclaimedPayout { era, validator, amount });
+            }
+        }
+    }
+    payouts
+}
+
 impl_runtime_apis! {
     impl sp_api::Core<Block> for Runtime {
         fn version() -> RuntimeVersion {
@@ -1239,10 +1710,112 @@ impl_runtime_apis! {
         Balance,
     > for Runtime {
         fn query_info(uxt: <Block as BlockT>::Extrinsic, len: u32) -> RuntimeDispatchInfo<Balance> {
-            TransactionPayment::query_info(uxt, len)
+            let fee = trading_fee(&uxt);
+            let mut info = TransactionPayment::query_info(uxt, len);
+            if let Some(fee) = fee {
+                info.partial_fee = fee;
+            }
+            info
         }
         fn query_fee_details(uxt: <Block as BlockT>::Extrinsic, len: u32) -> FeeDetails<Balance> {
-            TransactionPayment::query_fee_details(uxt, len)
+            let fee = trading_fee(&uxt);
+            let mut details = TransactionPayment::query_fee_details(uxt, len);
+            if let Some(fee) = fee {
+                details.inclusion_fee =
+                    Some(InclusionFee { base_fee: fee, len_fee: 0, adjusted_weight_fee: 0 });
+            }
+            details
+        }
+    }
+
+    impl polkadex_runtime_api::assets::AssetsApi<Block, AccountId, AssetId, Balance> for Runtime {
+        fn account_balances(account: AccountId) -> Vec<(AssetId, AssetBalance<Balance>)> {
+            asset_balances(&account)
+        }
+
+        fn accounts_balances(
+            accounts: Vec<AccountId>,
+        ) -> Vec<(AccountId, Vec<(AssetId, AssetBalance<Balance>)>)> {
+            accounts
+                .into_iter()
+                .map(|account| {
+                    let balances = asset_balances(&account);
+                    (account, balances)
+                })
+                .collect()
+        }
+    }
+
+    impl polkadex_runtime_api::vesting::VestingApi<Block, AccountId, BlockNumber, Balance, Moment> for Runtime {
+        fn vesting(account: AccountId) -> VestingInfo<BlockNumber, Balance, Moment> {
+            vesting_info(&account)
+        }
+    }
+
+    impl polkadex_runtime_api::fee_tiers::FeeTiersApi<Block, AccountId, Balance> for Runtime {
+        fn fee_tier(account: AccountId) -> FeeTierInfo<Balance> {
+            fee_tier_info(&account)
+        }
+    }
+
+    impl polkadex_runtime_api::oracle::OracleApi<Block, AssetId> for Runtime {
+        fn get_price(asset: AssetId) -> Option<sp_runtime::FixedU128> {
+            Oracle::get_price(&asset)
+        }
+    }
+
+    impl polkadex_runtime_api::twap::TwapApi<Block, AssetId, BlockNumber> for Runtime {
+        fn twap(
+            base: AssetId,
+            quote: AssetId,
+            window: BlockNumber,
+        ) -> Option<sp_runtime::FixedU128> {
+            Twap::twap(base, quote, window)
+        }
+    }
+
+    impl polkadex_runtime_api::staking::StakingRewardsApi<Block, AccountId, Balance> for Runtime {
+        fn estimate_rewards(stake: Balance) -> RewardEstimate<AccountId, Balance> {
+            estimate_staking_rewards(stake)
+        }
+
+        fn unclaimed_payouts(account: AccountId) -> Vec<UnclaimedPayout<AccountId, Balance>> {
+            unclaimed_staking_payouts(&account)
+        }
+    }
+
+    impl polkadex_runtime_api::bridge::BridgeApi<Block> for Runtime {
+        fn finalized_block() -> Option<EthereumHeaderId> {
+            get_storage_value(ETHEREUM_LIGHT_CLIENT_PREFIX, b"FinalizedBlock", &[])
+        }
+
+        fn best_block() -> Option<EthereumHeaderId> {
+            get_storage_value::<(EthereumHeaderId, sp_core::U256)>(
+                ETHEREUM_LIGHT_CLIENT_PREFIX,
+                b"BestBlock",
+                &[],
+            )
+            .map(|(best, _total_difficulty)| best)
+        }
+
+        fn oldest_header() -> Option<u64> {
+            EthereumHeaderPruning::oldest_unpruned()
+        }
+
+        fn inbound_nonces() -> Option<Vec<(sp_core::H160, u64)>> {
+            let source_channel =
+                get_storage_value(BASIC_INBOUND_CHANNEL_PREFIX, b"SourceChannel", &[])?;
+            // `Nonce` is only written once the first message is processed.
+            let nonce = if have_storage_value(BASIC_INBOUND_CHANNEL_PREFIX, b"Nonce", &[]) {
+                get_storage_value(BASIC_INBOUND_CHANNEL_PREFIX, b"Nonce", &[])?
+            } else {
+                0
+            };
+            Some(vec![(source_channel, nonce)])
+        }
+
+        fn erc20_pdex_address() -> sp_core::H160 {
+            ERC20PDEX::address()
         }
     }
 
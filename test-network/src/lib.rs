@@ -0,0 +1,472 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! In-process network of Polkadex validators, for tests.
+//!
+//! [`TestNetwork`] starts full nodes running the Polkadex runtime as validators of a local
+//! chain, connected over an in-memory transport in the current process. Tests can submit
+//! extrinsics, wait for blocks to be finalized and stop or restart validators to check how
+//! GRANDPA recovers.
+//!
+//! Every validator keeps its database in a temporary directory that lives as long as the
+//! network, so a restarted validator resumes from its own chain and keeps its peer id.
+//!
+//! Blocks are produced at the pace of the runtime, so tests using the network take minutes
+//! and are usually `#[ignore]`d.
+
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use codec::Encode;
+use frame_system_rpc_runtime_api::AccountNonceApi;
+use node_polkadex_runtime::{Call, SignedExtra, UncheckedExtrinsic};
+use polkadex_node::{
+	chain_spec::{self, ChainSpec},
+	genesis::AuthorityDescription,
+	service::{self, FullClient, NewFullBase},
+};
+use polkadex_primitives::{AccountId, Block, BlockNumber, Hash, Index};
+use sc_cli::{CliConfiguration, RunCmd, RuntimeVersion, SubstrateCli};
+use sc_client_api::BlockBackend;
+use sc_network::{
+	config::{MultiaddrWithPeerId, TransportConfig},
+	multiaddr::{Multiaddr, Protocol},
+	NetworkService,
+};
+use sc_service::{ChainType, Configuration, TaskManager};
+use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_keyring::AccountKeyring;
+use sp_runtime::{
+	generic::{BlockId, Era, SignedPayload},
+	traits::{BlakeTwo256, Hash as HashT},
+};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+/// Interval at which the conditions of [`TestNetwork::wait_until`] are checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Transaction pool of a full node.
+pub type FullPool = sc_transaction_pool::FullPool<Block, FullClient>;
+
+/// Errors of the test network.
+#[derive(Debug)]
+pub enum Error {
+	/// A validator could not be configured.
+	Cli(sc_cli::Error),
+	/// A validator could not be started.
+	Service(sc_service::Error),
+	/// The chain of a validator could not be read.
+	Client(sp_blockchain::Error),
+	/// An extrinsic could not be built or was rejected by the transaction pool.
+	Extrinsic(String),
+	/// The validator with the given index is stopped.
+	Stopped(usize),
+	/// A condition did not hold before the timeout.
+	Timeout(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Cli(e) => write!(f, "Invalid validator configuration: {}", e),
+			Error::Service(e) => write!(f, "Unable to start validator: {}", e),
+			Error::Client(e) => write!(f, "Unable to read the chain: {}", e),
+			Error::Extrinsic(e) => write!(f, "Unable to submit extrinsic: {}", e),
+			Error::Stopped(index) => write!(f, "Validator {} is stopped", index),
+			Error::Timeout(condition) => write!(f, "Timed out waiting for {}", condition),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<sc_cli::Error> for Error {
+	fn from(e: sc_cli::Error) -> Self {
+		Error::Cli(e)
+	}
+}
+
+impl From<sc_service::Error> for Error {
+	fn from(e: sc_service::Error) -> Self {
+		Error::Service(e)
+	}
+}
+
+impl From<sp_blockchain::Error> for Error {
+	fn from(e: sp_blockchain::Error) -> Self {
+		Error::Client(e)
+	}
+}
+
+/// Result of the test network operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Serves the chain spec of the test network to the CLI configuration of its validators.
+struct TestCli {
+	chain_spec: ChainSpec,
+}
+
+impl SubstrateCli for TestCli {
+	fn impl_name() -> String {
+		"Polkadex Test Node".into()
+	}
+
+	fn impl_version() -> String {
+		env!("CARGO_PKG_VERSION").into()
+	}
+
+	fn description() -> String {
+		env!("CARGO_PKG_DESCRIPTION").into()
+	}
+
+	fn author() -> String {
+		env!("CARGO_PKG_AUTHORS").into()
+	}
+
+	fn support_url() -> String {
+		"business@polkadex.trade".into()
+	}
+
+	fn copyright_start_year() -> i32 {
+		2017
+	}
+
+	fn load_spec(&self, _: &str) -> std::result::Result<Box<dyn sc_service::ChainSpec>, String> {
+		Ok(Box::new(self.chain_spec.clone()))
+	}
+
+	fn native_runtime_version(_: &Box<dyn sc_service::ChainSpec>) -> &'static RuntimeVersion {
+		&node_polkadex_runtime::VERSION
+	}
+}
+
+/// Chain spec of a local chain validated by `validators`, with the accounts and balances of
+/// the Soba testnet.
+///
+/// The Soba testnet endows the stashes of Alice to Ferdie only, so other keyring accounts
+/// cannot validate.
+pub fn chain_spec(validators: &[AccountKeyring]) -> std::result::Result<ChainSpec, String> {
+	let mut description = chain_spec::embedded_description("soba.toml")?;
+	description.name = "Integration Test".into();
+	description.id = "test".into();
+	description.chain_type = ChainType::Development;
+	description.authorities = validators
+		.iter()
+		.map(|validator| AuthorityDescription::Seed { seed: format!("{:?}", validator) })
+		.collect();
+	chain_spec::from_description(description)
+}
+
+/// A running validator.
+pub struct TestNode {
+	task_manager: TaskManager,
+	/// Client of the validator.
+	pub client: Arc<FullClient>,
+	/// Network service of the validator.
+	pub network: Arc<NetworkService<Block, Hash>>,
+	/// Transaction pool of the validator.
+	pub transaction_pool: Arc<FullPool>,
+}
+
+/// Validators of a local chain running in the current process.
+pub struct TestNetwork {
+	runtime: tokio::runtime::Runtime,
+	base_path: TempDir,
+	chain_spec: ChainSpec,
+	validators: Vec<AccountKeyring>,
+	/// Listen address of every validator, known to the others once it started.
+	addresses: Vec<MultiaddrWithPeerId>,
+	nodes: Vec<Option<TestNode>>,
+	/// Next nonce of every account that submitted extrinsics, including the ones still in
+	/// the transaction pools.
+	nonces: HashMap<AccountId, Index>,
+}
+
+impl TestNetwork {
+	/// Starts a network of `validators`, each validating with the keys derived from its seed.
+	pub fn new(validators: &[AccountKeyring]) -> Result<Self> {
+		let chain_spec = chain_spec(validators).map_err(sc_cli::Error::Input)?;
+		Self::with_chain_spec(chain_spec, validators)
+	}
+
+	/// Starts a network of `validators` on the chain of `chain_spec`.
+	pub fn with_chain_spec(chain_spec: ChainSpec, validators: &[AccountKeyring]) -> Result<Self> {
+		let runtime = tokio::runtime::Builder::new_multi_thread()
+			.enable_all()
+			.build()
+			.map_err(|e| sc_service::Error::Other(e.to_string()))?;
+		let base_path = tempfile::tempdir().map_err(sc_service::Error::Io)?;
+		let mut network = TestNetwork {
+			runtime,
+			base_path,
+			chain_spec,
+			validators: validators.to_vec(),
+			addresses: Vec::new(),
+			nodes: Vec::new(),
+			nonces: HashMap::new(),
+		};
+		for index in 0..validators.len() {
+			let multiaddr: Multiaddr = Protocol::Memory(rand::random()).into();
+			let node = network.start_node(index, &multiaddr, network.addresses.clone())?;
+			let peer_id = node.network.local_peer_id().clone();
+			network.addresses.push(MultiaddrWithPeerId { multiaddr, peer_id });
+			network.nodes.push(Some(node));
+		}
+		Ok(network)
+	}
+
+	/// Configuration of the validator with the given index.
+	fn configuration(
+		&self,
+		index: usize,
+		listen_address: &Multiaddr,
+		boot_nodes: Vec<MultiaddrWithPeerId>,
+	) -> Result<Configuration> {
+		let seed = format!("{:?}", self.validators[index]).to_lowercase();
+		let base_path = self.base_path.path().join(&seed);
+		let run = RunCmd::from_iter(&[
+			"polkadex-test-node".into(),
+			"--validator".into(),
+			format!("--{}", seed),
+			"--base-path".into(),
+			base_path.to_string_lossy().into_owned(),
+		]);
+		let cli = TestCli { chain_spec: self.chain_spec.clone() };
+		let mut config = run.create_configuration(&cli, self.runtime.handle().clone())?;
+
+		// Validators of the same process only reach each other in memory and must not bind
+		// any port.
+		config.network.transport = TransportConfig::MemoryOnly;
+		config.network.listen_addresses = vec![listen_address.clone()];
+		config.network.public_addresses = Vec::new();
+		config.network.boot_nodes = boot_nodes;
+		config.network.allow_non_globals_in_dht = true;
+		config.rpc_http = None;
+		config.rpc_ws = None;
+		config.rpc_ipc = None;
+		config.prometheus_config = None;
+		config.telemetry_endpoints = None;
+		Ok(config)
+	}
+
+	fn start_node(
+		&self,
+		index: usize,
+		listen_address: &Multiaddr,
+		boot_nodes: Vec<MultiaddrWithPeerId>,
+	) -> Result<TestNode> {
+		let config = self.configuration(index, listen_address, boot_nodes)?;
+		let _guard = self.runtime.enter();
+		let NewFullBase { task_manager, client, network, transaction_pool } =
+			service::new_full_base(config, None, None, |_, _| ())?;
+		Ok(TestNode { task_manager, client, network, transaction_pool })
+	}
+
+	/// Addresses of the running validators other than `index`.
+	fn running_addresses(&self, index: usize) -> Vec<MultiaddrWithPeerId> {
+		self.addresses
+			.iter()
+			.enumerate()
+			.filter(|(other, _)| *other != index && self.nodes[*other].is_some())
+			.map(|(_, address)| address.clone())
+			.collect()
+	}
+
+	/// Number of validators, running or stopped.
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	/// Whether the network has no validators.
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+
+	/// The validator with the given index, if it is running.
+	pub fn node(&self, index: usize) -> Result<&TestNode> {
+		self.nodes.get(index).and_then(Option::as_ref).ok_or(Error::Stopped(index))
+	}
+
+	/// The running validators.
+	pub fn running(&self) -> impl Iterator<Item = &TestNode> {
+		self.nodes.iter().flatten()
+	}
+
+	/// Stops the validator with the given index and waits for its tasks to end.
+	pub fn stop(&mut self, index: usize) {
+		if let Some(TestNode { task_manager, client, network, transaction_pool }) =
+			self.nodes.get_mut(index).and_then(Option::take)
+		{
+			// The database is only closed once no task holds the client any more.
+			drop((client, network, transaction_pool));
+			self.runtime.block_on(task_manager.clean_shutdown());
+		}
+	}
+
+	/// Starts the validator with the given index again, on top of its own database.
+	pub fn restart(&mut self, index: usize) -> Result<()> {
+		self.stop(index);
+		let listen_address = self.addresses[index].multiaddr.clone();
+		let node = self.start_node(index, &listen_address, self.running_addresses(index))?;
+		self.nodes[index] = Some(node);
+		Ok(())
+	}
+
+	/// Best block number of the validator with the given index.
+	pub fn best_number(&self, index: usize) -> Result<BlockNumber> {
+		Ok(self.node(index)?.client.info().best_number)
+	}
+
+	/// Finalized block number of the validator with the given index.
+	pub fn finalized_number(&self, index: usize) -> Result<BlockNumber> {
+		Ok(self.node(index)?.client.info().finalized_number)
+	}
+
+	/// Signs `call` by `signer` and submits it to the validator with the given index,
+	/// returning the hash of the extrinsic.
+	pub fn submit(&mut self, index: usize, signer: AccountKeyring, call: Call) -> Result<Hash> {
+		let node = self.nodes.get(index).and_then(Option::as_ref).ok_or(Error::Stopped(index))?;
+		let info = node.client.info();
+		let at = BlockId::hash(info.best_hash);
+		let account = signer.to_account_id();
+		let chain_nonce = node
+			.client
+			.runtime_api()
+			.account_nonce(&at, account.clone())
+			.map_err(|e| Error::Extrinsic(e.to_string()))?;
+		let nonce = self.nonces.entry(account).or_default();
+		*nonce = chain_nonce.max(*nonce);
+
+		let version = node.client.runtime_version_at(&at)?;
+		let extra: SignedExtra = (
+			frame_system::CheckSpecVersion::new(),
+			frame_system::CheckTxVersion::new(),
+			frame_system::CheckGenesis::new(),
+			frame_system::CheckEra::from(Era::Immortal),
+			frame_system::CheckNonce::from(*nonce),
+			frame_system::CheckWeight::new(),
+			trading_fees_pallet::ChargeTradingFee::from(0),
+			extrinsic_index_pallet::IndexExtrinsic::new(),
+		);
+		let payload = SignedPayload::from_raw(
+			call,
+			extra,
+			(
+				version.spec_version,
+				version.transaction_version,
+				info.genesis_hash,
+				info.genesis_hash,
+				(),
+				(),
+				(),
+				(),
+			),
+		);
+		let signature = payload.using_encoded(|payload| signer.sign(payload));
+		let (call, extra, _) = payload.deconstruct();
+		let address = signer.to_account_id().into();
+		let extrinsic = UncheckedExtrinsic::new_signed(call, address, signature.into(), extra);
+
+		let hash = self
+			.runtime
+			.block_on(node.transaction_pool.submit_one(
+				&at,
+				TransactionSource::External,
+				extrinsic.into(),
+			))
+			.map_err(|e| Error::Extrinsic(e.to_string()))?;
+		*nonce += 1;
+		Ok(hash)
+	}
+
+	/// Number of the finalized block of the validator with the given index that includes the
+	/// extrinsic with the given hash.
+	pub fn finalized_inclusion(&self, index: usize, hash: Hash) -> Result<Option<BlockNumber>> {
+		let client = &self.node(index)?.client;
+		for number in 1..=client.info().finalized_number {
+			let body = match client.block_body(&BlockId::number(number))? {
+				Some(body) => body,
+				None => continue,
+			};
+			if body.iter().any(|extrinsic| BlakeTwo256::hash_of(extrinsic) == hash) {
+				return Ok(Some(number))
+			}
+		}
+		Ok(None)
+	}
+
+	/// Waits until `condition` holds, for at most `timeout`. `condition` is described by
+	/// `description` in the timeout error.
+	pub fn wait_until(
+		&self,
+		description: &str,
+		timeout: Duration,
+		mut condition: impl FnMut(&Self) -> Result<bool>,
+	) -> Result<()> {
+		let deadline = std::time::Instant::now() + timeout;
+		while !condition(self)? {
+			if std::time::Instant::now() >= deadline {
+				return Err(Error::Timeout(description.into()))
+			}
+			self.runtime.block_on(tokio::time::sleep(POLL_INTERVAL));
+		}
+		Ok(())
+	}
+
+	/// Waits until every running validator finalized block `number`.
+	pub fn wait_for_finalized(&self, number: BlockNumber, timeout: Duration) -> Result<()> {
+		self.wait_until(&format!("block {} to be finalized", number), timeout, |network| {
+			Ok(network.running().all(|node| node.client.info().finalized_number >= number))
+		})
+	}
+
+	/// Waits until the best block of every running validator is at least `number`.
+	pub fn wait_for_best(&self, number: BlockNumber, timeout: Duration) -> Result<()> {
+		self.wait_until(&format!("block {} to be imported", number), timeout, |network| {
+			Ok(network.running().all(|node| node.client.info().best_number >= number))
+		})
+	}
+
+	/// Waits until the extrinsic with the given hash is finalized on every running validator,
+	/// returning the number of the including block.
+	pub fn wait_for_extrinsic(&self, hash: Hash, timeout: Duration) -> Result<BlockNumber> {
+		let mut included = None;
+		self.wait_until(&format!("extrinsic {:?} to be finalized", hash), timeout, |network| {
+			for index in 0..network.len() {
+				if network.nodes[index].is_some() {
+					included = network.finalized_inclusion(index, hash)?;
+					if included.is_none() {
+						return Ok(false)
+					}
+				}
+			}
+			Ok(included.is_some())
+		})?;
+		Ok(included.expect("the wait only ends once the extrinsic is included; qed"))
+	}
+}
+
+impl Drop for TestNetwork {
+	fn drop(&mut self) {
+		for index in 0..self.nodes.len() {
+			self.stop(index);
+		}
+	}
+}
@@ -0,0 +1,66 @@
+// This file is part of Polkadex.
+
+// Copyright (C) 2020-2021 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Runs validator networks in process. Block production takes minutes, so the tests are
+//! ignored by default and run with `cargo test -p polkadex-test-network -- --ignored`.
+
+use std::time::Duration;
+
+use node_polkadex_runtime::{constants::currency::PDEX, BalancesCall, Call};
+use polkadex_test_network::TestNetwork;
+use sp_keyring::AccountKeyring::{Alice, Bob, Charlie, Ferdie};
+
+const TIMEOUT: Duration = Duration::from_secs(300);
+
+#[test]
+#[ignore]
+fn validators_finalize_blocks() {
+	let network = TestNetwork::new(&[Alice, Bob]).unwrap();
+	network.wait_for_finalized(3, TIMEOUT).unwrap();
+}
+
+#[test]
+#[ignore]
+fn transfer_is_finalized_on_every_validator() {
+	let mut network = TestNetwork::new(&[Alice, Bob]).unwrap();
+	network.wait_for_best(1, TIMEOUT).unwrap();
+
+	let transfer = Call::Balances(BalancesCall::transfer(Ferdie.to_account_id().into(), PDEX));
+	let first = network.submit(0, Charlie, transfer.clone()).unwrap();
+	let second = network.submit(1, Charlie, transfer).unwrap();
+	assert_ne!(first, second);
+
+	network.wait_for_extrinsic(first, TIMEOUT).unwrap();
+	network.wait_for_extrinsic(second, TIMEOUT).unwrap();
+}
+
+#[test]
+#[ignore]
+fn finality_recovers_after_restart() {
+	let mut network = TestNetwork::new(&[Alice, Bob, Charlie]).unwrap();
+	network.wait_for_finalized(2, TIMEOUT).unwrap();
+
+	// GRANDPA needs all three votes of a set of three validators to finalize.
+	network.stop(2);
+	let stalled = network.finalized_number(0).unwrap();
+	network.wait_for_best(stalled + 4, TIMEOUT).unwrap();
+	assert!(network.finalized_number(0).unwrap() <= stalled + 1);
+
+	network.restart(2).unwrap();
+	network.wait_for_finalized(stalled + 4, TIMEOUT).unwrap();
+}